@@ -0,0 +1,78 @@
+#ifndef HTTP_LANG_ARRAY_H
+#define HTTP_LANG_ARRAY_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "mem.h"
+
+// growable array of elements of any type, which are stored by value and copied with memcpy
+typedef struct {
+    size_t len;
+    size_t cap;
+    size_t elemSize;
+    char* data;
+} array_t;
+
+array_t* array_new(size_t elemSize) {
+    array_t* array = (array_t*) memAlloc(sizeof(array_t));
+    array->len = 0;
+    array->cap = 0;
+    array->elemSize = elemSize;
+    array->data = NULL;
+    return array;
+}
+
+void array_reserve(array_t* array, size_t cap) {
+    if (cap <= array->cap) {
+        return;
+    }
+    char* data = (char*) memAlloc(cap * array->elemSize);
+    if (array->len != 0) {
+        memcpy(data, array->data, array->len * array->elemSize);
+    }
+    memFree(array->data);
+    array->data = data;
+    array->cap = cap;
+}
+
+// copies `len` elements from `items`
+array_t* array_of(size_t elemSize, size_t len, const void* items) {
+    array_t* array = array_new(elemSize);
+    array_reserve(array, len);
+    if (len != 0) {
+        memcpy(array->data, items, len * elemSize);
+    }
+    array->len = len;
+    return array;
+}
+
+// pointer to the element, the program is aborted if the index is out of bounds
+void* array_at(array_t* array, int64_t index) {
+    if (index < 0 || (uint64_t) index >= array->len) {
+        // the output written so far goes before the error
+        fflush(stdout);
+        fprintf(stderr, "index %lld is out of bounds of array of length %zu\n", (long long) index, array->len);
+        exit(1);
+    }
+    return array->data + (size_t) index * array->elemSize;
+}
+
+// copies the element to the end of the array, doubling its capacity when it is full
+void array_append(array_t* array, const void* elem) {
+    if (array->len == array->cap) {
+        array_reserve(array, array->cap == 0 ? 4 : array->cap * 2);
+    }
+    memcpy(array->data + array->len * array->elemSize, elem, array->elemSize);
+    array->len++;
+}
+
+void array_free(array_t* array) {
+    memFree(array->data);
+    memFree(array);
+}
+
+#endif // HTTP_LANG_ARRAY_H
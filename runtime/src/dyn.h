@@ -4,6 +4,8 @@
 #include <stdbool.h>
 #include <stdint.h>
 
+#include "financial.h"
+#include "strconv.h"
 #include "string.h"
 
 enum {
@@ -69,6 +71,8 @@ DYN_GEN_IMPL(Int, int32_t, Dyn_INT)
 DYN_GEN_IMPL(UInt, uint32_t, Dyn_UINT)
 DYN_GEN_IMPL(Long, int64_t, Dyn_LONG)
 DYN_GEN_IMPL(ULong, uint64_t, Dyn_ULONG)
+DYN_GEN_IMPL(Double, double, Dyn_DOUBLE)
+DYN_GEN_IMPL(Financial, financial_t, Dyn_FINANCIAL)
 DYN_GEN_IMPL(Bool, bool, Dyn_BOOL)
 DYN_GEN_IMPL(Rune, uint64_t, Dyn_RUNE)
 DYN_GEN_IMPL(String, string_t, Dyn_STRING)
@@ -82,16 +86,65 @@ dyn_t dyn_StringFromCstr(const char* cstr) {
     return object;
 }
 
+// returns a new string, even if the object holds one
 string_t dyn_toString(dyn_t object) {
-    string_t string;
-
     switch (object.type) {
+    case Dyn_BYTE:
+        return strconv_ulongToString(BIT_CAST(DynData, uint8_t, object.data));
+    case Dyn_SHORT:
+        return strconv_longToString(BIT_CAST(DynData, int16_t, object.data));
+    case Dyn_USHORT:
+        return strconv_ulongToString(BIT_CAST(DynData, uint16_t, object.data));
+    case Dyn_INT:
+        return strconv_longToString(BIT_CAST(DynData, int32_t, object.data));
+    case Dyn_UINT:
+        return strconv_ulongToString(BIT_CAST(DynData, uint32_t, object.data));
+    case Dyn_LONG:
+        return strconv_longToString(BIT_CAST(DynData, int64_t, object.data));
+    case Dyn_ULONG:
+        return strconv_ulongToString(BIT_CAST(DynData, uint64_t, object.data));
+    case Dyn_DOUBLE:
+        return strconv_doubleToString(BIT_CAST(DynData, double, object.data));
+    case Dyn_FINANCIAL:
+        return strconv_financialToString(BIT_CAST(DynData, financial_t, object.data));
+    case Dyn_BOOL:
+        return strconv_boolToString(BIT_CAST(DynData, bool, object.data));
     case Dyn_STRING:
-        string = BIT_CAST(DynData, string_t, object.data);
-        return string;
+        return string_clone(BIT_CAST(DynData, string_t, object.data));
+    case Dyn_RUNE:
+        return strconv_runeToString(BIT_CAST(DynData, uint64_t, object.data));
+    default:
+        abort();
+    }
+}
 
+// name of the type of the value as it is written in the language
+string_t dyn_typeName(dyn_t object) {
+    switch (object.type) {
     case Dyn_BYTE:
-        break;
+        return string_new("byte");
+    case Dyn_SHORT:
+        return string_new("short");
+    case Dyn_USHORT:
+        return string_new("ushort");
+    case Dyn_INT:
+        return string_new("int");
+    case Dyn_UINT:
+        return string_new("uint");
+    case Dyn_LONG:
+        return string_new("long");
+    case Dyn_ULONG:
+        return string_new("ulong");
+    case Dyn_DOUBLE:
+        return string_new("double");
+    case Dyn_FINANCIAL:
+        return string_new("financial");
+    case Dyn_BOOL:
+        return string_new("bool");
+    case Dyn_STRING:
+        return string_new("string");
+    case Dyn_RUNE:
+        return string_new("rune");
     default:
         abort();
     }
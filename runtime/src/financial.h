@@ -0,0 +1,45 @@
+#ifndef HTTP_LANG_FINANCIAL_H
+#define HTTP_LANG_FINANCIAL_H
+
+#include <stdint.h>
+
+#define FINANCIAL_SCALE 10000
+
+// fixed point number with 4 digits after the decimal point
+typedef struct {
+    int64_t units;
+} financial_t;
+
+financial_t financial_fromUnits(int64_t units) {
+    financial_t value;
+    value.units = units;
+    return value;
+}
+
+financial_t financial_add(financial_t left, financial_t right) {
+    return financial_fromUnits(left.units + right.units);
+}
+
+financial_t financial_subtract(financial_t left, financial_t right) {
+    return financial_fromUnits(left.units - right.units);
+}
+
+// the product is truncated towards zero, the right operand is split so the units don't overflow in between
+financial_t financial_multiply(financial_t left, financial_t right) {
+    int64_t whole = right.units / FINANCIAL_SCALE;
+    int64_t fraction = right.units % FINANCIAL_SCALE;
+    return financial_fromUnits(left.units * whole + left.units * fraction / FINANCIAL_SCALE);
+}
+
+// the quotient is truncated towards zero, dividing by zero is undefined like for integers
+financial_t financial_divide(financial_t left, financial_t right) {
+    int64_t whole = left.units / right.units;
+    int64_t remainder = left.units % right.units;
+    return financial_fromUnits(whole * FINANCIAL_SCALE + remainder * FINANCIAL_SCALE / right.units);
+}
+
+financial_t financial_negate(financial_t value) {
+    return financial_fromUnits(-value.units);
+}
+
+#endif // HTTP_LANG_FINANCIAL_H
@@ -0,0 +1,22 @@
+#ifndef HTTP_LANG_HASH_H
+#define HTTP_LANG_HASH_H
+
+#include <stdint.h>
+#include <string.h>
+
+// mixes the hash of the next value into the hash of the previous ones
+uint64_t hash_combine(uint64_t seed, uint64_t value) {
+    return seed ^ (value + 0x9e3779b97f4a7c15ull + (seed << 6) + (seed >> 2));
+}
+
+uint64_t hash_double(double value) {
+    // 0.0 and -0.0 are equal, so they must have the same hash
+    if (value == 0) {
+        return 0;
+    }
+    uint64_t bits;
+    memcpy(&bits, &value, sizeof(bits));
+    return bits;
+}
+
+#endif // HTTP_LANG_HASH_H
@@ -1,10 +1,97 @@
 #ifndef HTTP_LANG_PRINTER_H
 #define HTTP_LANG_PRINTER_H
 
+#include <inttypes.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+
 #include "dyn.h"
+#include "financial.h"
+#include "strconv.h"
+#include "string.h"
 
-void printDyn(dyn_t object) {
+void printString(string_t string) {
+    fwrite(string.data, sizeof(char), string.len, stdout);
+}
+
+// prints and frees the string
+void printOwnedString(string_t string) {
+    printString(string);
+    string_free(string);
+}
+
+void printLong(int64_t n) {
+    char buf[32];
+    string_t string = string_fromBuf(buf, snprintf(NULL, 0, "%" PRId64, n));
+    strconv_itoa(n, string);
+    printString(string);
+}
+
+void printULong(uint64_t n) {
+    char buf[32];
+    string_t string = string_fromBuf(buf, snprintf(NULL, 0, "%" PRIu64, n));
+    strconv_uitoa(n, string);
+    printString(string);
+}
 
+void printDouble(double n) {
+    printOwnedString(strconv_doubleToString(n));
+}
+
+void printFinancial(financial_t n) {
+    printOwnedString(strconv_financialToString(n));
+}
+
+void printBool(bool value) {
+    fputs(value ? "true" : "false", stdout);
+}
+
+void printRune(uint64_t rune) {
+    printOwnedString(strconv_runeToString(rune));
+}
+
+void printDyn(dyn_t object) {
+    switch (object.type) {
+    case Dyn_BYTE:
+        printULong(BIT_CAST(DynData, uint8_t, object.data));
+        break;
+    case Dyn_SHORT:
+        printLong(BIT_CAST(DynData, int16_t, object.data));
+        break;
+    case Dyn_USHORT:
+        printULong(BIT_CAST(DynData, uint16_t, object.data));
+        break;
+    case Dyn_INT:
+        printLong(BIT_CAST(DynData, int32_t, object.data));
+        break;
+    case Dyn_UINT:
+        printULong(BIT_CAST(DynData, uint32_t, object.data));
+        break;
+    case Dyn_LONG:
+        printLong(BIT_CAST(DynData, int64_t, object.data));
+        break;
+    case Dyn_ULONG:
+        printULong(BIT_CAST(DynData, uint64_t, object.data));
+        break;
+    case Dyn_DOUBLE:
+        printDouble(BIT_CAST(DynData, double, object.data));
+        break;
+    case Dyn_FINANCIAL:
+        printFinancial(BIT_CAST(DynData, financial_t, object.data));
+        break;
+    case Dyn_BOOL:
+        printBool(BIT_CAST(DynData, bool, object.data));
+        break;
+    case Dyn_STRING:
+        printString(BIT_CAST(DynData, string_t, object.data));
+        break;
+    case Dyn_RUNE:
+        printRune(BIT_CAST(DynData, uint64_t, object.data));
+        break;
+    default:
+        abort();
+    }
 }
 
 #endif // HTTP_LANG_PRINTER_H
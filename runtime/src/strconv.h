@@ -1,21 +1,75 @@
 #ifndef HTTP_LANG_STRCONV_H
 #define HTTP_LANG_STRCONV_H
 
+#include <inttypes.h>
+#include <stdbool.h>
 #include <stdint.h>
 #include <stdio.h>
 
+#include "financial.h"
 #include "string.h"
 
 void strconv_itoa(int64_t n, string_t s) {
     char buf[s.len + 1];
-    snprintf(buf, s.len + 1, "%ld", n);
+    snprintf(buf, s.len + 1, "%" PRId64, n);
     memcpy(s.data, buf, s.len);
 }
 
 void strconv_uitoa(uint64_t n, string_t s) {
     char buf[s.len + 1];
-    snprintf(buf, s.len + 1, "%lu", n);
+    snprintf(buf, s.len + 1, "%" PRIu64, n);
     memcpy(s.data, buf, s.len);
 }
 
+string_t strconv_longToString(int64_t n) {
+    char buf[32];
+    snprintf(buf, sizeof(buf), "%" PRId64, n);
+    return string_new(buf);
+}
+
+string_t strconv_ulongToString(uint64_t n) {
+    char buf[32];
+    snprintf(buf, sizeof(buf), "%" PRIu64, n);
+    return string_new(buf);
+}
+
+string_t strconv_doubleToString(double n) {
+    char buf[32];
+    snprintf(buf, sizeof(buf), "%.15g", n);
+    return string_new(buf);
+}
+
+// always prints all 4 digits after the decimal point: 12.5000
+string_t strconv_financialToString(financial_t n) {
+    uint64_t units = n.units < 0 ? -(uint64_t) n.units : (uint64_t) n.units;
+    char buf[32];
+    snprintf(buf, sizeof(buf), "%s%" PRIu64 ".%04" PRIu64, n.units < 0 ? "-" : "", units / FINANCIAL_SCALE, units % FINANCIAL_SCALE);
+    return string_new(buf);
+}
+
+// encodes the code point in UTF-8
+string_t strconv_runeToString(uint64_t rune) {
+    char buf[5] = {0};
+    if (rune < 0x80) {
+        buf[0] = (char) rune;
+    } else if (rune < 0x800) {
+        buf[0] = (char) (0xC0 | (rune >> 6));
+        buf[1] = (char) (0x80 | (rune & 0x3F));
+    } else if (rune < 0x10000) {
+        buf[0] = (char) (0xE0 | (rune >> 12));
+        buf[1] = (char) (0x80 | ((rune >> 6) & 0x3F));
+        buf[2] = (char) (0x80 | (rune & 0x3F));
+    } else {
+        buf[0] = (char) (0xF0 | (rune >> 18));
+        buf[1] = (char) (0x80 | ((rune >> 12) & 0x3F));
+        buf[2] = (char) (0x80 | ((rune >> 6) & 0x3F));
+        buf[3] = (char) (0x80 | (rune & 0x3F));
+    }
+    return string_new(buf);
+}
+
+string_t strconv_boolToString(bool value) {
+    return string_new(value ? "true" : "false");
+}
+
 #endif // HTTP_LANG_STRCONV_H
@@ -1,7 +1,9 @@
 #ifndef HTTP_LANG_STRING_H
 #define HTTP_LANG_STRING_H
 
+#include <stdbool.h>
 #include <stddef.h>
+#include <stdint.h>
 #include <string.h>
 
 #include "mem.h"
@@ -70,4 +72,53 @@ void string_free(string_t string) {
     memFree(string.data);
 }
 
+bool string_equals(string_t left, string_t right) {
+    return left.len == right.len && (left.len == 0 || memcmp(left.data, right.data, left.len) == 0);
+}
+
+// compares bytes, a prefix is less than the longer string: negative, zero or positive like memcmp
+int string_compare(string_t left, string_t right) {
+    size_t len = left.len < right.len ? left.len : right.len;
+    int result = len == 0 ? 0 : memcmp(left.data, right.data, len);
+    if (result != 0) {
+        return result;
+    }
+    return left.len < right.len ? -1 : left.len > right.len ? 1 : 0;
+}
+
+// 64-bit FNV-1a
+uint64_t string_hash(string_t string) {
+    uint64_t hash = 14695981039346656037ull;
+    for (size_t i = 0; i < string.len; i++) {
+        hash ^= (unsigned char) string.data[i];
+        hash *= 1099511628211ull;
+    }
+    return hash;
+}
+
+// copies the part to the end of the string, which gets a new buffer
+void string_append(string_t* string, string_t part) {
+    if (part.len == 0) {
+        return;
+    }
+    char* data = (char*) memAlloc(string->len + part.len);
+    if (string->len != 0) {
+        memcpy(data, string->data, string->len);
+    }
+    memcpy(data + string->len, part.data, part.len);
+    memFree(string->data);
+    string->data = data;
+    string->len += part.len;
+}
+
+void string_appendCstr(string_t* string, const char* cstr) {
+    string_append(string, string_fromBuf((char*) cstr, strlen(cstr)));
+}
+
+// appends and frees the part
+void string_appendOwned(string_t* string, string_t part) {
+    string_append(string, part);
+    string_free(part);
+}
+
 #endif // HTTP_LANG_STRING_H
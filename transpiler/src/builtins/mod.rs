@@ -0,0 +1,145 @@
+use crate::parsing::ast::{Type, TypeOrVoid};
+
+/// Type of a builtin param.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ParamType<'a> {
+    /// Accepts values of every type without converting them.
+    Any,
+    Exact(Type<'a>),
+    /// Accepts arrays with elements of every type.
+    Array,
+    /// Accepts values of the element type of the array passed to the [`ParamType::Array`] param.
+    Element,
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub name: &'static str,
+    pub type_: ParamType<'static>,
+}
+
+/// How a call of the builtin is generated in C.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum CLowering {
+    /// Call of the runtime function with the same arguments.
+    Function { name: &'static str, header: &'static str },
+    /// Writes the argument to stdout according to its static type, with a line break after it
+    /// if `newline` is set.
+    Print { newline: bool },
+    /// Appends the second argument to the array passed as the first one.
+    Append,
+}
+
+/// Function provided by the compiler instead of being declared in the source code.
+#[derive(Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub params: &'static [Param],
+    /// The last param may be repeated any number of times, including zero.
+    pub variadic: bool,
+    pub returns: TypeOrVoid<'static>,
+    pub lowering: CLowering,
+}
+
+impl Builtin {
+    /// Types of the params which `arg_count` arguments are passed to, variadic params are repeated
+    /// for every extra argument.
+    pub fn param_types(&self, arg_count: usize) -> Vec<ParamType<'static>> {
+        let count = match self.variadic {
+            true => arg_count.max(self.params.len() - 1),
+            false => self.params.len(),
+        };
+        (0..count)
+            .map(|index| self.params[index.min(self.params.len() - 1)].type_)
+            .collect()
+    }
+}
+
+const fn param(name: &'static str, type_: Type<'static>) -> Param {
+    Param {
+        name,
+        type_: ParamType::Exact(type_),
+    }
+}
+
+const fn runtime_function(name: &'static str, header: &'static str) -> CLowering {
+    CLowering::Function { name, header }
+}
+
+pub static BUILTINS: [Builtin; 8] = [
+    Builtin {
+        name: "print",
+        params: &[Param {
+            name: "value",
+            type_: ParamType::Any,
+        }],
+        variadic: true,
+        returns: TypeOrVoid::Void,
+        lowering: CLowering::Print { newline: false },
+    },
+    Builtin {
+        name: "println",
+        params: &[Param {
+            name: "value",
+            type_: ParamType::Any,
+        }],
+        variadic: true,
+        returns: TypeOrVoid::Void,
+        lowering: CLowering::Print { newline: true },
+    },
+    Builtin {
+        name: "longToString",
+        params: &[param("value", Type::Long)],
+        variadic: false,
+        returns: TypeOrVoid::Type(Type::String),
+        lowering: runtime_function("strconv_longToString", "strconv.h"),
+    },
+    Builtin {
+        name: "ulongToString",
+        params: &[param("value", Type::ULong)],
+        variadic: false,
+        returns: TypeOrVoid::Type(Type::String),
+        lowering: runtime_function("strconv_ulongToString", "strconv.h"),
+    },
+    Builtin {
+        name: "doubleToString",
+        params: &[param("value", Type::Double)],
+        variadic: false,
+        returns: TypeOrVoid::Type(Type::String),
+        lowering: runtime_function("strconv_doubleToString", "strconv.h"),
+    },
+    Builtin {
+        name: "dynToString",
+        params: &[param("value", Type::Dyn)],
+        variadic: false,
+        returns: TypeOrVoid::Type(Type::String),
+        lowering: runtime_function("dyn_toString", "dyn.h"),
+    },
+    Builtin {
+        name: "dynTypeName",
+        params: &[param("value", Type::Dyn)],
+        variadic: false,
+        returns: TypeOrVoid::Type(Type::String),
+        lowering: runtime_function("dyn_typeName", "dyn.h"),
+    },
+    Builtin {
+        name: "append",
+        params: &[
+            Param {
+                name: "array",
+                type_: ParamType::Array,
+            },
+            Param {
+                name: "value",
+                type_: ParamType::Element,
+            },
+        ],
+        variadic: false,
+        returns: TypeOrVoid::Void,
+        lowering: CLowering::Append,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
@@ -1,49 +1,223 @@
-use crate::parsing::ast;
-use crate::parsing::ast::stmt::Statement;
-use crate::parsing::ast::Literal;
-
-// fn lang_type_to_c_type<'a>(type_: ast::Type<'a>) -> &'static str {
-//     match type_ {
-//         ast::Type::Int => "int",
-//         _ => panic!("failed to map type to c"),
-//     }
-// }
-
-pub fn generate_c_code(tree: &ast::AST) -> String {
+use crate::builtins::{self, CLowering};
+use crate::ir::{self, Block, Constant, Instr, InstrId, InstrKind, Place, Value};
+use crate::parsing::ast::expr::{BinaryOp, UnaryOp};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
+enum Include {
+    /// Header of the C standard library.
+    External(&'static str),
+    /// Header of the language runtime from `runtime/src`.
+    Runtime(&'static str),
+}
+
+/// C type used for the IR type and the header that declares it.
+/// Representations match the runtime's `DYN_GEN_IMPL` table in `dyn.h`.
+fn ir_type_to_c_type(type_: ir::Type, program: &ir::Program) -> (Cow<'static, str>, Option<Include>) {
+    let (c_type, include) = match type_ {
+        ir::Type::Byte => ("uint8_t", Some(Include::External("stdint.h"))),
+        ir::Type::Short => ("int16_t", Some(Include::External("stdint.h"))),
+        ir::Type::UShort => ("uint16_t", Some(Include::External("stdint.h"))),
+        ir::Type::Int => ("int32_t", Some(Include::External("stdint.h"))),
+        ir::Type::UInt => ("uint32_t", Some(Include::External("stdint.h"))),
+        ir::Type::Long => ("int64_t", Some(Include::External("stdint.h"))),
+        ir::Type::ULong => ("uint64_t", Some(Include::External("stdint.h"))),
+        ir::Type::Double => ("double", None),
+        ir::Type::Financial => ("financial_t", Some(Include::Runtime("financial.h"))),
+        ir::Type::Bool => ("bool", Some(Include::External("stdbool.h"))),
+        ir::Type::String => ("string_t", Some(Include::Runtime("string.h"))),
+        ir::Type::Rune => ("uint64_t", Some(Include::External("stdint.h"))),
+        ir::Type::Dyn => ("dyn_t", Some(Include::Runtime("dyn.h"))),
+        ir::Type::Class(id) => {
+            let class = program.class(id);
+            // objects are allocated on the heap and passed by pointer, values are copied
+            let pointer = if class.value { "" } else { "*" };
+            return (format!("{}{}", mangle_class_name(&class.name), pointer).into(), None);
+        }
+        ir::Type::Array(_) => ("array_t*", Some(Include::Runtime("array.h"))),
+        ir::Type::Any => unreachable!("only builtins have params of any type"),
+    };
+    (c_type.into(), include)
+}
+
+/// Name of the C function generated for a user function. The prefix keeps user functions
+/// from clashing with C keywords, the standard library and the runtime.
+///
+/// Other declarations get their own prefixes, so that they can't clash with functions either:
+/// `hlt_` for the structs of classes, the prefixes of [`ClassFunction`] for the functions
+/// generated for them and `hlv_` for variables, params and fields.
+fn mangle_function_name(name: &str) -> String {
+    format!("hl_{}", name)
+}
+
+/// Name of the C variable, param or struct field generated for a variable, param or field.
+/// Identifiers of the language have no underscores, so they can't clash with the hidden
+/// variables which the lowering names like `array_2`.
+fn mangle_var_name(name: &str) -> String {
+    format!("hlv_{}", name)
+}
+
+/// Name of the C struct generated for a class.
+fn mangle_class_name(name: &str) -> String {
+    format!("hlt_{}", name)
+}
+
+/// Function generated for a class.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum ClassFunction {
+    Constructor,
+    /// Checks whether all fields of two values are equal.
+    Equals,
+    /// Compares values field by field: negative, zero or positive like `memcmp`.
+    Compare,
+    Hash,
+    /// Formats the value as `Name(field: value, ...)`.
+    ToString,
+}
+
+impl ClassFunction {
+    /// Functions which are generated for classes of the kind, in the order of generation.
+    fn of(class: &ir::Class) -> &'static [ClassFunction] {
+        match class.value {
+            true => &[
+                ClassFunction::Constructor,
+                ClassFunction::Equals,
+                ClassFunction::Compare,
+                ClassFunction::Hash,
+                ClassFunction::ToString,
+            ],
+            false => &[ClassFunction::Constructor],
+        }
+    }
+
+    fn name(self, class: &ir::Class) -> String {
+        let prefix = match self {
+            ClassFunction::Constructor => "hlnew_",
+            ClassFunction::Equals => "hleq_",
+            ClassFunction::Compare => "hlcmp_",
+            ClassFunction::Hash => "hlhash_",
+            ClassFunction::ToString => "hltostring_",
+        };
+        format!("{}{}", prefix, class.name)
+    }
+}
+
+/// Order in which the structs are defined. Values are embedded into the structs which contain them,
+/// so value classes go first, each after the value classes of its fields.
+fn struct_order(program: &ir::Program) -> Vec<&ir::Class> {
+    fn visit<'p>(program: &'p ir::Program, id: ir::ClassId, visited: &mut [bool], order: &mut Vec<&'p ir::Class>) {
+        if visited[id.0] {
+            return;
+        }
+        visited[id.0] = true;
+        let class = program.class(id);
+        for field in &class.fields {
+            if let ir::Type::Class(field_class) = field.type_ {
+                if program.class(field_class).value {
+                    visit(program, field_class, visited, order);
+                }
+            }
+        }
+        order.push(class);
+    }
+
+    let mut visited = vec![false; program.classes.len()];
+    let mut order = Vec::with_capacity(program.classes.len());
+    for (index, class) in program.classes.iter().enumerate() {
+        if class.value {
+            visit(program, ir::ClassId(index), &mut visited, &mut order);
+        }
+    }
+    order.extend(program.classes.iter().filter(|class| !class.value));
+    order
+}
+
+/// Generates C code of the program.
+pub fn generate_c_code(program: &ir::Program) -> String {
     let mut buf = CodeBuf::new();
-    buf.push_external_include("stdio.h");
-    buf.push_linebreak();
-    buf.push_linebreak();
+    buf.require_include(Include::External("stdio.h"));
+
+    // structs are declared before their definitions, so that fields may refer to any class
+    for class in &program.classes {
+        buf.push_ident(format!("typedef struct {0} {0};", mangle_class_name(&class.name)).as_str());
+        buf.push_linebreak();
+    }
+    for class in struct_order(program) {
+        buf.push_linebreak();
+        generate_class(&mut buf, class, program);
+    }
+    if !program.classes.is_empty() {
+        buf.push_linebreak();
+    }
 
-    let mut main_function_found = false;
-    for decl in tree.declarations() {
-        if decl.name() == "main" && decl.return_type() == ast::TypeOrVoid::Void {
-            main_function_found = true;
-            generate_main_function(&mut buf, decl);
-            break;
+    let functions: Vec<_> = program.functions.iter().filter(|function| !function.is_builtin()).collect();
+    // prototypes go first, so that functions may call each other regardless of their order
+    for class in &program.classes {
+        for function in ClassFunction::of(class) {
+            generate_class_function_signature(&mut buf, class, *function, program);
+            buf.push_semicolon();
+            buf.push_linebreak();
         }
     }
-    if !main_function_found {
-        panic!("failed to generated c code: main function not found");
+    for function in &functions {
+        generate_function_signature(&mut buf, function, program);
+        buf.push_semicolon();
+        buf.push_linebreak();
     }
+    for class in &program.classes {
+        for function in ClassFunction::of(class) {
+            buf.push_linebreak();
+            generate_class_function(&mut buf, class, *function, program);
+        }
+    }
+    for function in &functions {
+        buf.push_linebreak();
+        generate_function(&mut buf, function, program);
+    }
+    buf.push_linebreak();
+    generate_entry_point(&mut buf);
 
     return buf.into_string();
 }
 
 struct CodeBuf {
     content: String,
+    includes: BTreeSet<Include>,
 }
 
 impl CodeBuf {
     fn new() -> CodeBuf {
-        CodeBuf { content: String::new() }
+        CodeBuf {
+            content: String::new(),
+            includes: BTreeSet::new(),
+        }
+    }
+
+    /// Adds header which is emitted at the top of the file.
+    fn require_include(&mut self, include: Include) {
+        self.includes.insert(include);
     }
 
-    fn push_function_decl(&mut self, return_type: &str, name: &str) {
+    /// Pushes function signature, `params` are pairs of C type and name.
+    fn push_function_decl(&mut self, return_type: &str, name: &str, params: &[(&str, &str)]) {
         self.content.push_str(return_type);
-        self.content.push_str(" ");
+        self.content.push(' ');
         self.content.push_str(name);
-        self.content.push_str("()");
+        self.content.push('(');
+        if params.is_empty() {
+            // empty parens would declare a function with unspecified params
+            self.content.push_str("void");
+        }
+        for (index, (param_type, param_name)) in params.iter().enumerate() {
+            if index != 0 {
+                self.content.push_str(", ");
+            }
+            self.content.push_str(param_type);
+            self.content.push(' ');
+            self.content.push_str(param_name);
+        }
+        self.content.push(')');
     }
 
     fn push_spaces(&mut self, count: usize) {
@@ -51,88 +225,612 @@ impl CodeBuf {
     }
 
     fn push_code_block_open(&mut self) {
-        self.content.push_str("{");
+        self.content.push('{');
     }
 
     fn push_code_block_close(&mut self) {
-        self.content.push_str("}");
+        self.content.push('}');
     }
 
     fn push_paren_open(&mut self) {
-        self.content.push_str("(");
+        self.content.push('(');
     }
 
     fn push_paren_close(&mut self) {
-        self.content.push_str(")");
+        self.content.push(')');
     }
 
     fn push_semicolon(&mut self) {
-        self.content.push_str(";");
+        self.content.push(';');
     }
 
     fn push_linebreak(&mut self) {
-        self.content.push_str("\n");
+        self.content.push('\n');
     }
 
     fn push_ident(&mut self, ident: &str) {
         self.content.push_str(ident);
     }
 
+    fn push_assign(&mut self) {
+        self.content.push_str(" = ");
+    }
+
+    fn push_sep(&mut self) {
+        self.content.push_str(", ");
+    }
+
     fn push_string_literal(&mut self, string: &str) {
-        self.content.push_str("\"");
-        self.content.push_str(string);
-        self.content.push_str("\"");
+        self.content.push('"');
+        for byte in string.bytes() {
+            self.push_escaped_byte(byte, b'"');
+        }
+        self.content.push('"');
+    }
+
+    /// Pushes byte as it should be written inside of a C string or char literal.
+    /// Everything except printable ASCII is written as a 3-digit octal escape,
+    /// so that it can't be merged with the following chars.
+    fn push_escaped_byte(&mut self, byte: u8, quote: u8) {
+        match byte {
+            b'\\' => self.content.push_str("\\\\"),
+            b'\n' => self.content.push_str("\\n"),
+            b'\t' => self.content.push_str("\\t"),
+            b'\r' => self.content.push_str("\\r"),
+            // `??` may start a trigraph
+            b'?' => self.content.push_str("\\?"),
+            _ if byte == quote => {
+                self.content.push('\\');
+                self.content.push(byte as char);
+            }
+            0x20..=0x7e => self.content.push(byte as char),
+            _ => self.content.push_str(format!("\\{:03o}", byte).as_str()),
+        }
     }
 
     fn push_function_return(&mut self, value: &str) {
         self.content.push_str("return ");
         self.content.push_str(value);
-        self.content.push_str(";");
+        self.content.push(';');
     }
 
-    fn push_external_include(&mut self, what: &str) {
-        self.content.push_str("#include <");
-        self.content.push_str(what);
-        self.content.push_str(">");
+    fn push_doc_comment(&mut self, lines: &[&str]) {
+        if lines.is_empty() {
+            return;
+        }
+        self.content.push_str("/**\n");
+        for line in lines {
+            self.content.push_str(" *");
+            if !line.is_empty() {
+                self.content.push(' ');
+                // C comments don't nest, so `/*` and `*/` are broken apart
+                self.content.push_str(line.replace("/*", "/ *").replace("*/", "* /").as_str());
+            }
+            self.content.push('\n');
+        }
+        self.content.push_str(" */\n");
     }
 
     fn into_string(self) -> String {
-        self.content
+        let mut file = String::new();
+        for include in &self.includes {
+            match include {
+                Include::External(what) => {
+                    file.push_str("#include <");
+                    file.push_str(what);
+                    file.push('>');
+                }
+                Include::Runtime(what) => {
+                    file.push_str("#include \"");
+                    file.push_str(what);
+                    file.push('"');
+                }
+            }
+            file.push('\n');
+        }
+        file.push('\n');
+        file.push_str(self.content.as_str());
+        file
     }
 }
 
-fn generate_main_function(code: &mut CodeBuf, decl: &ast::FunctionDeclaration) {
-    code.push_function_decl("int", "main");
+/// State of the function which is being generated.
+struct FunctionContext<'p> {
+    program: &'p ir::Program,
+    function: &'p ir::Function,
+    /// Names of the variables by the numbers of their declarations.
+    names: HashMap<InstrId, &'p str>,
+    /// Steps of the loops around the current instruction, the innermost is the last one.
+    loop_steps: Vec<&'p Block>,
+}
+
+impl<'p> FunctionContext<'p> {
+    /// Mangled name of the variable or param.
+    fn place_name(&self, place: Place) -> String {
+        let name = match place {
+            Place::Var(id) => self.names[&id],
+            Place::Param(index) => self.function.args[index].name.as_str(),
+        };
+        mangle_var_name(name)
+    }
+
+    fn is_builtin(&self, function: &str) -> bool {
+        self.program.function(function).is_some_and(|function| function.is_builtin())
+    }
+
+    /// Whether the instruction is `print()` without arguments, which doesn't generate any code.
+    fn writes_nothing(&self, instr: &Instr) -> bool {
+        match &instr.kind {
+            InstrKind::Call { function, args } if args.is_empty() && self.is_builtin(function) => {
+                builtins::find(function).is_some_and(|builtin| builtin.lowering == CLowering::Print { newline: false })
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns C type for the IR type and requires the header that declares it.
+fn require_c_type(code: &mut CodeBuf, type_: ir::Type, program: &ir::Program) -> Cow<'static, str> {
+    let (c_type, include) = ir_type_to_c_type(type_, program);
+    if let Some(include) = include {
+        code.require_include(include);
+    }
+    c_type
+}
+
+fn generate_function_signature(code: &mut CodeBuf, function: &ir::Function, program: &ir::Program) {
+    let return_type = match function.returns {
+        None => "void".into(),
+        Some(type_) => require_c_type(code, type_, program),
+    };
+    let params: Vec<_> = function
+        .args
+        .iter()
+        .map(|arg| (require_c_type(code, arg.type_, program), mangle_var_name(&arg.name)))
+        .collect();
+    let params: Vec<_> = params.iter().map(|(c_type, name)| (c_type.as_ref(), name.as_str())).collect();
+    code.push_function_decl(&return_type, mangle_function_name(&function.name).as_str(), params.as_slice());
+}
+
+fn generate_function(code: &mut CodeBuf, function: &ir::Function, program: &ir::Program) {
+    let mut ctx = FunctionContext {
+        program,
+        function,
+        names: HashMap::new(),
+        loop_steps: Vec::new(),
+    };
+    let docs: Vec<_> = function.docs.iter().map(String::as_str).collect();
+    code.push_doc_comment(docs.as_slice());
+    generate_function_signature(code, function, program);
+    code.push_spaces(1);
+    let body = function.body.as_ref().expect("builtins are not generated");
+    generate_block(code, &body.instrs, &mut ctx, 0);
+    code.push_linebreak();
+}
+
+/// Generates the struct with the fields of the class.
+fn generate_class(code: &mut CodeBuf, class: &ir::Class, program: &ir::Program) {
+    let docs: Vec<_> = class.docs.iter().map(String::as_str).collect();
+    code.push_doc_comment(docs.as_slice());
+    code.push_ident("struct ");
+    code.push_ident(&mangle_class_name(&class.name));
     code.push_spaces(1);
     code.push_code_block_open();
     code.push_linebreak();
+    for field in &class.fields {
+        let c_type = require_c_type(code, field.type_, program);
+        code.push_spaces(INDENT_WIDTH);
+        code.push_ident(&c_type);
+        code.push_spaces(1);
+        code.push_ident(&mangle_var_name(&field.name));
+        code.push_semicolon();
+        code.push_linebreak();
+    }
+    code.push_code_block_close();
+    code.push_semicolon();
+    code.push_linebreak();
+}
 
-    for stmt in decl.stmts() {
-        code.push_spaces(4);
-        match stmt {
-            Statement::VariableDeclaration(_) => todo!("not supported yet"),
-            Statement::FunctionCall(func_call) => {
-                if func_call.name() != "print" {
-                    todo!("other functions are not supported yet");
-                }
+/// Constructor takes the values of the fields in their order, the other functions take the objects.
+fn generate_class_function_signature(code: &mut CodeBuf, class: &ir::Class, function: ClassFunction, program: &ir::Program) {
+    let name = function.name(class);
+    let object_type = if class.value {
+        mangle_class_name(&class.name)
+    } else {
+        format!("{}*", mangle_class_name(&class.name))
+    };
+    match function {
+        ClassFunction::Constructor => {
+            let params: Vec<_> = class
+                .fields
+                .iter()
+                .map(|field| (require_c_type(code, field.type_, program), mangle_var_name(&field.name)))
+                .collect();
+            let params: Vec<_> = params.iter().map(|(c_type, name)| (c_type.as_ref(), name.as_str())).collect();
+            code.push_function_decl(&object_type, &name, params.as_slice());
+        }
+        ClassFunction::Equals => {
+            code.require_include(Include::External("stdbool.h"));
+            code.push_function_decl("bool", &name, &[(&object_type, "left"), (&object_type, "right")]);
+        }
+        ClassFunction::Compare => code.push_function_decl("int", &name, &[(&object_type, "left"), (&object_type, "right")]),
+        ClassFunction::Hash => {
+            code.require_include(Include::External("stdint.h"));
+            code.push_function_decl("uint64_t", &name, &[(&object_type, "value")]);
+        }
+        ClassFunction::ToString => {
+            code.require_include(Include::Runtime("string.h"));
+            code.push_function_decl("string_t", &name, &[(&object_type, "value")]);
+        }
+    }
+}
 
-                let mut args = func_call.args().iter();
-                let string = match args.next().unwrap() {
-                    Literal::String(s) => *s,
-                    Literal::Integer(_) => panic!("string literal expected"),
-                };
-                assert!(args.next().is_none(), "only one arg expected");
+fn generate_class_function(code: &mut CodeBuf, class: &ir::Class, function: ClassFunction, program: &ir::Program) {
+    let lines = match function {
+        ClassFunction::Constructor => constructor_body(code, class),
+        ClassFunction::Equals => equals_body(class, program),
+        ClassFunction::Compare => compare_body(class, program),
+        ClassFunction::Hash => {
+            code.require_include(Include::Runtime("hash.h"));
+            hash_body(class, program)
+        }
+        ClassFunction::ToString => {
+            code.require_include(Include::Runtime("strconv.h"));
+            to_string_body(class, program)
+        }
+    };
+    generate_class_function_signature(code, class, function, program);
+    code.push_spaces(1);
+    code.push_code_block_open();
+    code.push_linebreak();
+    for line in lines {
+        code.push_spaces(INDENT_WIDTH);
+        code.push_ident(&line);
+        code.push_linebreak();
+    }
+    code.push_code_block_close();
+    code.push_linebreak();
+}
 
-                code.push_ident("printf");
-                code.push_paren_open();
-                code.push_string_literal(string);
-                code.push_paren_close();
-                code.push_semicolon();
+/// Objects of reference classes are allocated on the heap, values are built on the stack.
+///
+/// Objects are never freed. References to them are shared by variables, params, fields and
+/// return values, and nothing tracks when the last of them goes away, so every object lives
+/// until the program exits.
+fn constructor_body(code: &mut CodeBuf, class: &ir::Class) -> Vec<String> {
+    let struct_name = mangle_class_name(&class.name);
+    // params are named after the fields, whose names are mangled, so they can't be `object`
+    let object = "object";
+
+    let mut lines = Vec::with_capacity(class.fields.len() + 2);
+    let access = if class.value {
+        lines.push(format!("{} {};", struct_name, object));
+        "."
+    } else {
+        code.require_include(Include::Runtime("mem.h"));
+        lines.push(format!("{0}* {1} = memAlloc(sizeof({0}));", struct_name, object));
+        "->"
+    };
+    for field in &class.fields {
+        lines.push(format!("{0}{1}{2} = {2};", object, access, mangle_var_name(&field.name)));
+    }
+    lines.push(format!("return {};", object));
+    lines
+}
+
+/// Class of the field if it is a value class, whose functions are used for the field.
+fn value_class(type_: ir::Type, program: &ir::Program) -> Option<&ir::Class> {
+    match type_ {
+        ir::Type::Class(id) => Some(program.class(id)),
+        _ => None,
+    }
+    .filter(|class| class.value)
+}
+
+fn equals_body(class: &ir::Class, program: &ir::Program) -> Vec<String> {
+    let conditions: Vec<_> = class
+        .fields
+        .iter()
+        .map(|field| {
+            let name = &mangle_var_name(&field.name);
+            match field.type_ {
+                ir::Type::String => format!("string_equals(left.{0}, right.{0})", name),
+                ir::Type::Financial => format!("left.{0}.units == right.{0}.units", name),
+                type_ => match value_class(type_, program) {
+                    Some(field_class) => format!("{}(left.{1}, right.{1})", ClassFunction::Equals.name(field_class), name),
+                    None => format!("left.{0} == right.{0}", name),
+                },
+            }
+        })
+        .collect();
+    if conditions.is_empty() {
+        return vec!["return true;".to_string()];
+    }
+    vec![format!("return {};", conditions.join(" && "))]
+}
+
+fn compare_body(class: &ir::Class, program: &ir::Program) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut uses_result = false;
+    for field in &class.fields {
+        let name = &mangle_var_name(&field.name);
+        let compare_function = match field.type_ {
+            ir::Type::String => Some("string_compare".to_string()),
+            type_ => value_class(type_, program).map(|field_class| ClassFunction::Compare.name(field_class)),
+        };
+        match compare_function {
+            Some(function) => {
+                uses_result = true;
+                lines.push(format!("result = {}(left.{1}, right.{1});", function, name));
+                lines.push("if (result != 0) {".to_string());
+                lines.push("    return result;".to_string());
+            }
+            None => {
+                let units = if field.type_ == ir::Type::Financial { ".units" } else { "" };
+                lines.push(format!("if (left.{0}{1} != right.{0}{1}) {{", name, units));
+                lines.push(format!("    return left.{0}{1} < right.{0}{1} ? -1 : 1;", name, units));
             }
         }
+        lines.push("}".to_string());
+    }
+    if uses_result {
+        lines.insert(0, "int result;".to_string());
+    }
+    lines.push("return 0;".to_string());
+    lines
+}
+
+fn hash_body(class: &ir::Class, program: &ir::Program) -> Vec<String> {
+    let mut lines = vec!["uint64_t hash = 0;".to_string()];
+    for field in &class.fields {
+        let name = &mangle_var_name(&field.name);
+        let field_hash = match field.type_ {
+            ir::Type::String => format!("string_hash(value.{})", name),
+            ir::Type::Double => format!("hash_double(value.{})", name),
+            ir::Type::Financial => format!("(uint64_t) value.{}.units", name),
+            type_ => match value_class(type_, program) {
+                Some(field_class) => format!("{}(value.{})", ClassFunction::Hash.name(field_class), name),
+                None => format!("(uint64_t) value.{}", name),
+            },
+        };
+        lines.push(format!("hash = hash_combine(hash, {});", field_hash));
+    }
+    lines.push("return hash;".to_string());
+    lines
+}
+
+fn to_string_body(class: &ir::Class, program: &ir::Program) -> Vec<String> {
+    // names of classes and fields are identifiers, so they don't need escaping
+    let mut lines = vec![format!("string_t result = string_new(\"{}(\");", class.name)];
+    for (index, field) in class.fields.iter().enumerate() {
+        let separator = if index == 0 { "" } else { ", " };
+        lines.push(format!("string_appendCstr(&result, \"{}{}: \");", separator, field.name));
+        let name = &mangle_var_name(&field.name);
+        let line = match field.type_ {
+            ir::Type::String => format!("string_append(&result, value.{});", name),
+            type_ => {
+                let to_string = match type_ {
+                    ir::Type::Byte | ir::Type::UShort | ir::Type::UInt | ir::Type::ULong => "strconv_ulongToString".to_string(),
+                    ir::Type::Short | ir::Type::Int | ir::Type::Long => "strconv_longToString".to_string(),
+                    ir::Type::Double => "strconv_doubleToString".to_string(),
+                    ir::Type::Financial => "strconv_financialToString".to_string(),
+                    ir::Type::Bool => "strconv_boolToString".to_string(),
+                    ir::Type::Rune => "strconv_runeToString".to_string(),
+                    _ => match value_class(type_, program) {
+                        Some(field_class) => ClassFunction::ToString.name(field_class),
+                        None => unreachable!("fields of value classes are checked to be values"),
+                    },
+                };
+                format!("string_appendOwned(&result, {}(value.{}));", to_string, name)
+            }
+        };
+        lines.push(line);
+    }
+    lines.push("string_appendCstr(&result, \")\");".to_string());
+    lines.push("return result;".to_string());
+    lines
+}
+
+const INDENT_WIDTH: usize = 4;
+
+/// Generates instructions in braces, `depth` is the nesting level of the braces.
+fn generate_block<'p>(code: &mut CodeBuf, instrs: &'p [Instr], ctx: &mut FunctionContext<'p>, depth: usize) {
+    code.push_code_block_open();
+    code.push_linebreak();
+    generate_instrs(code, instrs, ctx, depth + 1);
+    code.push_spaces(depth * INDENT_WIDTH);
+    code.push_code_block_close();
+}
+
+/// Generates instructions on separate lines, indented to `depth`.
+fn generate_instrs<'p>(code: &mut CodeBuf, instrs: &'p [Instr], ctx: &mut FunctionContext<'p>, depth: usize) {
+    for instr in instrs {
+        if ctx.writes_nothing(instr) {
+            continue;
+        }
+        code.push_spaces(depth * INDENT_WIDTH);
+        generate_instr(code, instr, ctx, depth);
         code.push_linebreak();
     }
+}
+
+fn generate_instr<'p>(code: &mut CodeBuf, instr: &'p Instr, ctx: &mut FunctionContext<'p>, depth: usize) {
+    match &instr.kind {
+        InstrKind::Return { value } => {
+            code.push_ident("return");
+            if let Some(value) = value {
+                code.push_spaces(1);
+                generate_value(code, value, ctx);
+            }
+            code.push_semicolon();
+        }
+        InstrKind::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            code.push_ident("if");
+            code.push_spaces(1);
+            generate_condition(code, condition, ctx);
+            code.push_spaces(1);
+            generate_block(code, &then.instrs, ctx, depth);
+            match otherwise.instrs.as_slice() {
+                [] => (),
+                // `else if` is kept on the same line
+                [else_if @ Instr {
+                    kind: InstrKind::If { .. },
+                    ..
+                }] => {
+                    code.push_ident(" else ");
+                    generate_instr(code, else_if, ctx, depth);
+                }
+                instrs => {
+                    code.push_ident(" else ");
+                    generate_block(code, instrs, ctx, depth);
+                }
+            }
+        }
+        InstrKind::Loop { condition, body, step } => generate_loop(code, condition, body, step, ctx, depth),
+        InstrKind::Break => {
+            code.push_ident("break");
+            code.push_semicolon();
+        }
+        InstrKind::Continue => {
+            // steps which can't be put into the header of `for` are repeated before every `continue`
+            let step = *ctx.loop_steps.last().expect("`continue` is checked to be inside of a loop");
+            if step_as_header(step).is_none() {
+                for instr in &step.instrs {
+                    // steps may declare hidden variables in blocks
+                    generate_instr(code, instr, ctx, depth);
+                    code.push_linebreak();
+                    code.push_spaces(depth * INDENT_WIDTH);
+                }
+            }
+            code.push_ident("continue");
+            code.push_semicolon();
+        }
+        InstrKind::Block(block) => generate_block(code, &block.instrs, ctx, depth),
+        InstrKind::Call { function, args } if ctx.is_builtin(function) => {
+            let separator = format!(";\n{}", " ".repeat(depth * INDENT_WIDTH));
+            generate_builtin_call(code, function, args, ctx, &separator);
+            code.push_semicolon();
+        }
+        _ => {
+            generate_simple_instr(code, instr, ctx);
+            code.push_semicolon();
+        }
+    }
+}
+
+/// Step of the loop which fits into the header of C `for`: a single instruction which is a C expression.
+fn step_as_header(step: &Block) -> Option<&Instr> {
+    match step.instrs.as_slice() {
+        [instr @ Instr {
+            kind: InstrKind::Assign { .. } | InstrKind::SetField { .. } | InstrKind::SetIndex { .. } | InstrKind::Call { .. },
+            ..
+        }] => Some(instr),
+        _ => None,
+    }
+}
+
+fn generate_loop<'p>(
+    code: &mut CodeBuf,
+    condition: &'p Value,
+    body: &'p Block,
+    step: &'p Block,
+    ctx: &mut FunctionContext<'p>,
+    depth: usize,
+) {
+    let header_step = step_as_header(step);
+    if step.instrs.is_empty() {
+        code.push_ident("while");
+        code.push_spaces(1);
+        generate_condition(code, condition, ctx);
+    } else {
+        code.push_ident("for");
+        code.push_spaces(1);
+        code.push_paren_open();
+        code.push_semicolon();
+        code.push_spaces(1);
+        generate_value(code, condition, ctx);
+        code.push_semicolon();
+        if let Some(instr) = header_step {
+            code.push_spaces(1);
+            generate_simple_instr(code, instr, ctx);
+        }
+        code.push_paren_close();
+    }
+    code.push_spaces(1);
 
+    ctx.loop_steps.push(step);
+    code.push_code_block_open();
+    code.push_linebreak();
+    generate_instrs(code, &body.instrs, ctx, depth + 1);
+    if header_step.is_none() {
+        generate_instrs(code, &step.instrs, ctx, depth + 1);
+    }
+    code.push_spaces(depth * INDENT_WIDTH);
+    code.push_code_block_close();
+    ctx.loop_steps.pop();
+}
+
+/// Generates instruction which is a C expression or declaration, without the trailing semicolon.
+fn generate_simple_instr<'p>(code: &mut CodeBuf, instr: &'p Instr, ctx: &mut FunctionContext<'p>) {
+    match &instr.kind {
+        InstrKind::VarDecl { name, type_, value } => {
+            let c_type = require_c_type(code, *type_, ctx.program);
+            code.push_ident(&c_type);
+            code.push_spaces(1);
+            code.push_ident(&mangle_var_name(name));
+            code.push_assign();
+            generate_value(code, value, ctx);
+            // the variable becomes visible after its initialiser
+            ctx.names.insert(instr.id, name.as_str());
+        }
+        InstrKind::Call { function, args } => generate_call(code, function, args, ctx),
+        InstrKind::Assign { target, value } => {
+            code.push_ident(&ctx.place_name(*target));
+            code.push_assign();
+            generate_value(code, value, ctx);
+        }
+        InstrKind::SetField { object, field, value } => {
+            generate_field(code, object, *field, ctx);
+            code.push_assign();
+            generate_value(code, value, ctx);
+        }
+        InstrKind::SetIndex { array, index, value } => {
+            code.push_ident("*");
+            generate_element_pointer(code, array, index, ctx);
+            code.push_assign();
+            generate_value(code, value, ctx);
+        }
+        _ => unreachable!("instruction with blocks in a simple position"),
+    }
+}
+
+fn generate_condition(code: &mut CodeBuf, condition: &Value, ctx: &FunctionContext) {
+    // binary and unary expressions are already parenthesised
+    let has_parens = matches!(condition, Value::Binary { .. } | Value::Unary { .. });
+    if !has_parens {
+        code.push_paren_open();
+    }
+    generate_value(code, condition, ctx);
+    if !has_parens {
+        code.push_paren_close();
+    }
+}
+
+/// Generates C `main` which runs `void main()` of the program.
+fn generate_entry_point(code: &mut CodeBuf) {
+    code.push_function_decl("int", "main", &[]);
+    code.push_spaces(1);
+    code.push_code_block_open();
+    code.push_linebreak();
+    code.push_spaces(4);
+    code.push_ident(mangle_function_name("main").as_str());
+    code.push_paren_open();
+    code.push_paren_close();
+    code.push_semicolon();
     code.push_linebreak();
     code.push_spaces(4);
     code.push_function_return("0");
@@ -140,3 +838,972 @@ fn generate_main_function(code: &mut CodeBuf, decl: &ast::FunctionDeclaration) {
     code.push_code_block_close();
     code.push_linebreak();
 }
+
+fn generate_call(code: &mut CodeBuf, function: &str, args: &[Value], ctx: &FunctionContext) {
+    if ctx.is_builtin(function) {
+        generate_builtin_call(code, function, args, ctx, ", ");
+        return;
+    }
+    code.push_ident(mangle_function_name(function).as_str());
+    generate_args(code, args, ctx);
+}
+
+fn generate_args(code: &mut CodeBuf, args: &[Value], ctx: &FunctionContext) {
+    code.push_paren_open();
+    for (index, arg) in args.iter().enumerate() {
+        if index != 0 {
+            code.push_sep();
+        }
+        generate_value(code, arg, ctx);
+    }
+    code.push_paren_close();
+}
+
+/// Generates call of the builtin. Prints of several values are generated as several C calls,
+/// `separator` is put between them.
+fn generate_builtin_call(code: &mut CodeBuf, function: &str, args: &[Value], ctx: &FunctionContext, separator: &str) {
+    let builtin = builtins::find(function).unwrap_or_else(|| unreachable!("`{}` is not in the builtin registry", function));
+    let newline = match builtin.lowering {
+        CLowering::Function { name, header } => {
+            code.require_include(Include::Runtime(header));
+            code.push_ident(name);
+            generate_args(code, args, ctx);
+            return;
+        }
+        CLowering::Append => {
+            let element = array_element(&args[0], ctx);
+            code.require_include(Include::Runtime("array.h"));
+            code.push_ident("array_append");
+            code.push_paren_open();
+            generate_value(code, &args[0], ctx);
+            code.push_sep();
+            generate_array_items(code, element, &args[1..], ctx);
+            code.push_paren_close();
+            return;
+        }
+        CLowering::Print { newline } => newline,
+    };
+    let mut newline_written = !newline;
+    for (index, arg) in args.iter().enumerate() {
+        if index != 0 {
+            code.push_ident(separator);
+        }
+        match arg {
+            Value::Const(Constant::String(string)) => {
+                // the line break is merged into the last string literal
+                let is_last = index + 1 == args.len();
+                newline_written |= is_last;
+                // the string is passed as an argument, so `%` in it is not treated as a format specifier
+                code.push_ident("printf");
+                code.push_paren_open();
+                code.push_string_literal(if newline && is_last { "%s\n" } else { "%s" });
+                code.push_sep();
+                code.push_string_literal(string);
+                code.push_paren_close();
+            }
+            _ => {
+                let type_ = ctx
+                    .program
+                    .value_type(ctx.function, arg)
+                    .expect("printed values are checked not to be void");
+                code.require_include(Include::Runtime("printer.h"));
+                match value_class(type_, ctx.program) {
+                    // values are formatted into a temporary string
+                    Some(class) => {
+                        code.push_ident("printOwnedString");
+                        code.push_paren_open();
+                        code.push_ident(&ClassFunction::ToString.name(class));
+                        code.push_paren_open();
+                        generate_value(code, arg, ctx);
+                        code.push_paren_close();
+                        code.push_paren_close();
+                    }
+                    None => {
+                        code.push_ident(print_writer(type_));
+                        code.push_paren_open();
+                        generate_value(code, arg, ctx);
+                        code.push_paren_close();
+                    }
+                }
+            }
+        }
+    }
+    if !newline_written {
+        if !args.is_empty() {
+            code.push_ident(separator);
+        }
+        code.push_ident("printf");
+        code.push_paren_open();
+        code.push_string_literal("\n");
+        code.push_paren_close();
+    }
+}
+
+/// Function from `printer.h` which writes values of the type.
+fn print_writer(type_: ir::Type) -> &'static str {
+    match type_ {
+        // integers are widened to 64 bits
+        ir::Type::Byte | ir::Type::UShort | ir::Type::UInt | ir::Type::ULong => "printULong",
+        ir::Type::Short | ir::Type::Int | ir::Type::Long => "printLong",
+        ir::Type::Double => "printDouble",
+        ir::Type::Financial => "printFinancial",
+        ir::Type::Bool => "printBool",
+        ir::Type::String => "printString",
+        ir::Type::Rune => "printRune",
+        ir::Type::Dyn => "printDyn",
+        ir::Type::Class(_) => unreachable!("values are formatted before printing, objects can't be printed"),
+        ir::Type::Array(_) => unreachable!("arrays can't be printed"),
+        ir::Type::Any => unreachable!("values don't have the type of builtin params"),
+    }
+}
+
+/// Name of the `dyn_new*` constructor from `dyn.h` which wraps value of the type.
+fn dyn_constructor(value_type: ir::Type) -> &'static str {
+    match value_type {
+        ir::Type::Byte => "dyn_newByte",
+        ir::Type::Short => "dyn_newShort",
+        ir::Type::UShort => "dyn_newUShort",
+        ir::Type::Int => "dyn_newInt",
+        ir::Type::UInt => "dyn_newUInt",
+        ir::Type::Long => "dyn_newLong",
+        ir::Type::ULong => "dyn_newULong",
+        ir::Type::Double => "dyn_newDouble",
+        ir::Type::Financial => "dyn_newFinancial",
+        ir::Type::Bool => "dyn_newBool",
+        ir::Type::String => "dyn_newString",
+        ir::Type::Rune => "dyn_newRune",
+        ir::Type::Dyn | ir::Type::Any => unreachable!("`dyn` values are not wrapped again"),
+        ir::Type::Class(_) | ir::Type::Array(_) => unreachable!("objects and arrays can't be wrapped into `dyn`"),
+    }
+}
+
+fn generate_value(code: &mut CodeBuf, value: &Value, ctx: &FunctionContext) {
+    match value {
+        Value::Const(constant) => generate_constant(code, constant, ctx.program),
+        Value::Place(place) => code.push_ident(&ctx.place_name(*place)),
+        // operands are parenthesised, so C precedence rules don't matter
+        Value::Binary { op, left, right } if value_class_of(left, ctx).is_some() => {
+            let class = value_class_of(left, ctx).unwrap();
+            let function = match op {
+                BinaryOp::Equal | BinaryOp::NotEqual => ClassFunction::Equals,
+                _ => ClassFunction::Compare,
+            };
+            code.push_paren_open();
+            if *op == BinaryOp::NotEqual {
+                code.push_ident("!");
+            }
+            code.push_ident(&function.name(class));
+            code.push_paren_open();
+            generate_value(code, left, ctx);
+            code.push_sep();
+            generate_value(code, right, ctx);
+            code.push_paren_close();
+            if function == ClassFunction::Compare {
+                code.push_spaces(1);
+                code.push_ident(op.symbol());
+                code.push_ident(" 0");
+            }
+            code.push_paren_close();
+        }
+        Value::Binary { op, left, right } if ctx.program.value_type(ctx.function, left) == Some(ir::Type::Financial) => {
+            generate_financial_binary(code, *op, left, right, ctx);
+        }
+        Value::Binary { op, left, right } if ctx.program.value_type(ctx.function, left) == Some(ir::Type::String) => {
+            generate_string_comparison(code, *op, left, right, ctx);
+        }
+        Value::Binary { op, left, right } => {
+            code.push_paren_open();
+            generate_value(code, left, ctx);
+            code.push_spaces(1);
+            code.push_ident(op.symbol());
+            code.push_spaces(1);
+            generate_value(code, right, ctx);
+            code.push_paren_close();
+        }
+        Value::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } if ctx.program.value_type(ctx.function, operand) == Some(ir::Type::Financial) => {
+            code.push_ident("financial_negate");
+            code.push_paren_open();
+            generate_value(code, operand, ctx);
+            code.push_paren_close();
+        }
+        Value::Unary { op, operand } => {
+            code.push_paren_open();
+            code.push_ident(op.symbol());
+            generate_value(code, operand, ctx);
+            code.push_paren_close();
+        }
+        Value::Call { function, args } => generate_call(code, function, args, ctx),
+        Value::Convert { value, .. } if value_class_of(value, ctx).is_some() => {
+            let class = value_class_of(value, ctx).unwrap();
+            code.push_ident(&ClassFunction::ToString.name(class));
+            code.push_paren_open();
+            generate_value(code, value, ctx);
+            code.push_paren_close();
+        }
+        Value::Convert { value, to } => {
+            let c_type = require_c_type(code, *to, ctx.program);
+            code.push_paren_open();
+            code.push_paren_open();
+            code.push_ident(&c_type);
+            code.push_paren_close();
+            code.push_spaces(1);
+            generate_value(code, value, ctx);
+            code.push_paren_close();
+        }
+        Value::ToDyn { value, from } => {
+            code.require_include(Include::Runtime("dyn.h"));
+            if let Value::Const(Constant::String(string)) = value.as_ref() {
+                code.push_ident("dyn_StringFromCstr");
+                code.push_paren_open();
+                code.push_string_literal(string);
+                code.push_paren_close();
+                return;
+            }
+            code.push_ident(dyn_constructor(*from));
+            code.push_paren_open();
+            generate_value(code, value, ctx);
+            code.push_paren_close();
+        }
+        Value::New { class, args } => {
+            code.push_ident(&ClassFunction::Constructor.name(ctx.program.class(*class)));
+            generate_args(code, args, ctx);
+        }
+        Value::Field { object, field } => {
+            code.push_paren_open();
+            generate_field(code, object, *field, ctx);
+            code.push_paren_close();
+        }
+        Value::Array { array, items } => {
+            let element = ctx.program.array_element(*array);
+            let c_type = require_c_type(code, element, ctx.program);
+            code.require_include(Include::Runtime("array.h"));
+            code.push_ident(if items.is_empty() { "array_new" } else { "array_of" });
+            code.push_paren_open();
+            code.push_ident(&format!("sizeof({})", c_type));
+            if !items.is_empty() {
+                code.push_sep();
+                code.push_ident(&items.len().to_string());
+                code.push_sep();
+                generate_array_items(code, element, items, ctx);
+            }
+            code.push_paren_close();
+        }
+        Value::Index { array, index } => {
+            code.push_paren_open();
+            code.push_ident("*");
+            generate_element_pointer(code, array, index, ctx);
+            code.push_paren_close();
+        }
+        Value::Len { array } => {
+            let c_type = require_c_type(code, ir::Type::Int, ctx.program);
+            code.push_paren_open();
+            code.push_paren_open();
+            code.push_ident(&c_type);
+            code.push_paren_close();
+            code.push_spaces(1);
+            generate_value(code, array, ctx);
+            code.push_ident("->len");
+            code.push_paren_close();
+        }
+    }
+}
+
+/// Type of the elements of the array value.
+fn array_element(array: &Value, ctx: &FunctionContext) -> ir::Type {
+    match ctx.program.value_type(ctx.function, array) {
+        Some(ir::Type::Array(array)) => ctx.program.array_element(array),
+        _ => unreachable!("only arrays have elements"),
+    }
+}
+
+/// Generates the pointer to the element of the array: `(T*) array_at(array, index)`, which aborts
+/// the program if the index is out of bounds.
+fn generate_element_pointer(code: &mut CodeBuf, array: &Value, index: &Value, ctx: &FunctionContext) {
+    let c_type = require_c_type(code, array_element(array, ctx), ctx.program);
+    code.push_paren_open();
+    code.push_ident(&c_type);
+    code.push_ident("*");
+    code.push_paren_close();
+    code.push_spaces(1);
+    code.push_ident("array_at");
+    code.push_paren_open();
+    generate_value(code, array, ctx);
+    code.push_sep();
+    generate_value(code, index, ctx);
+    code.push_paren_close();
+}
+
+/// Generates the items as a C compound literal `(T[]){a, b}`, whose elements the runtime copies.
+fn generate_array_items(code: &mut CodeBuf, element: ir::Type, items: &[Value], ctx: &FunctionContext) {
+    let c_type = require_c_type(code, element, ctx.program);
+    code.push_paren_open();
+    code.push_ident(&c_type);
+    code.push_ident("[]");
+    code.push_paren_close();
+    code.push_ident("{");
+    for (index, item) in items.iter().enumerate() {
+        if index != 0 {
+            code.push_sep();
+        }
+        generate_value(code, item, ctx);
+    }
+    code.push_ident("}");
+}
+
+/// Class of the value if it is a value of a value class.
+/// Financial values are structs, so arithmetic is done by the runtime and comparisons on their units.
+fn generate_financial_binary(code: &mut CodeBuf, op: BinaryOp, left: &Value, right: &Value, ctx: &FunctionContext) {
+    let function = match op {
+        BinaryOp::Add => "financial_add",
+        BinaryOp::Sub => "financial_subtract",
+        BinaryOp::Mul => "financial_multiply",
+        BinaryOp::Div => "financial_divide",
+        _ => {
+            code.push_paren_open();
+            generate_value(code, left, ctx);
+            code.push_ident(".units ");
+            code.push_ident(op.symbol());
+            code.push_spaces(1);
+            generate_value(code, right, ctx);
+            code.push_ident(".units");
+            code.push_paren_close();
+            return;
+        }
+    };
+    code.push_ident(function);
+    code.push_paren_open();
+    generate_value(code, left, ctx);
+    code.push_sep();
+    generate_value(code, right, ctx);
+    code.push_paren_close();
+}
+
+/// Strings are compared by their bytes, like fields of value classes.
+fn generate_string_comparison(code: &mut CodeBuf, op: BinaryOp, left: &Value, right: &Value, ctx: &FunctionContext) {
+    code.require_include(Include::Runtime("string.h"));
+    let equality = matches!(op, BinaryOp::Equal | BinaryOp::NotEqual);
+    code.push_paren_open();
+    if op == BinaryOp::NotEqual {
+        code.push_ident("!");
+    }
+    code.push_ident(if equality { "string_equals" } else { "string_compare" });
+    code.push_paren_open();
+    generate_value(code, left, ctx);
+    code.push_sep();
+    generate_value(code, right, ctx);
+    code.push_paren_close();
+    if !equality {
+        code.push_spaces(1);
+        code.push_ident(op.symbol());
+        code.push_ident(" 0");
+    }
+    code.push_paren_close();
+}
+
+fn value_class_of<'p>(value: &Value, ctx: &FunctionContext<'p>) -> Option<&'p ir::Class> {
+    value_class(ctx.program.value_type(ctx.function, value)?, ctx.program)
+}
+
+/// Generates the field of the object without parens around it.
+fn generate_field(code: &mut CodeBuf, object: &Value, field: usize, ctx: &FunctionContext) {
+    let class = match ctx.program.value_type(ctx.function, object) {
+        Some(ir::Type::Class(class)) => ctx.program.class(class),
+        _ => unreachable!("only objects have fields"),
+    };
+    generate_value(code, object, ctx);
+    code.push_ident(if class.value { "." } else { "->" });
+    code.push_ident(&mangle_var_name(&class.fields[field].name));
+}
+
+/// Pushes the integer, negative ones are not parenthesised.
+fn push_integer(code: &mut CodeBuf, value: i128) {
+    let literal = if value == i64::MIN as i128 {
+        // C has no negative literals, and the minimum can't be written as a negated literal,
+        // as `9223372036854775808` doesn't fit into `long long`
+        format!("{}LL - 1", i64::MIN + 1)
+    } else if value > i64::MAX as i128 {
+        // literals which don't fit into `long long` are unsigned
+        format!("{}ull", value)
+    } else {
+        value.to_string()
+    };
+    code.push_ident(literal.as_str());
+}
+
+fn generate_constant(code: &mut CodeBuf, constant: &Constant, program: &ir::Program) {
+    match constant {
+        Constant::Integer { value, .. } if *value < 0 => {
+            code.push_paren_open();
+            push_integer(code, *value);
+            code.push_paren_close();
+        }
+        Constant::Integer { value, .. } => push_integer(code, *value),
+        // `{:?}` always keeps the dot or exponent, so the literal stays a C double
+        Constant::Double(value) => {
+            debug_assert!(value.is_finite(), "double literals are checked to be finite");
+            code.push_ident(format!("{:?}", value).as_str());
+        }
+        Constant::Financial(units) => {
+            require_c_type(code, ir::Type::Financial, program);
+            code.push_ident("financial_fromUnits");
+            code.push_paren_open();
+            push_integer(code, *units as i128);
+            code.push_paren_close();
+        }
+        Constant::String(string) => {
+            require_c_type(code, ir::Type::String, program);
+            code.push_ident("string_new");
+            code.push_paren_open();
+            code.push_string_literal(string);
+            code.push_paren_close();
+        }
+        Constant::Rune(value) => push_integer(code, *value as i128),
+        Constant::Bool(value) => {
+            require_c_type(code, ir::Type::Bool, program);
+            code.push_ident(if *value { "true" } else { "false" });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_c_code;
+    use crate::diagnostics::Level;
+    use crate::error::Result;
+    use crate::ir;
+    use crate::lexer::{Lexer, Span};
+    use crate::parsing;
+    use crate::sema;
+
+    /// Generates code of the source, or returns the first error found by any pass.
+    fn generate(source: &str) -> Result<String> {
+        let (ast, errors) = parsing::build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (analysis, problems) = sema::analyze(&ast);
+        if let Some(err) = problems
+            .into_iter()
+            .find(|problem| problem.diagnostic().level() == Level::Error)
+        {
+            return Err(err);
+        }
+        let program = ir::lower_program(&ast, &analysis)?;
+        Ok(generate_c_code(&program))
+    }
+
+    #[test]
+    fn reports_missing_main() {
+        let err = generate("void foo() {\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "`void main()` function not found");
+        assert_eq!(err.span(), None);
+    }
+
+    #[test]
+    fn generates_variables() {
+        let code = generate("void main() {\n    int x = 42\n    bool b = true\n}\n").unwrap();
+        assert!(code.starts_with("#include <stdbool.h>\n"), "{}", code);
+        assert!(code.contains("    int32_t hlv_x = 42;\n    bool hlv_b = true;\n"), "{}", code);
+    }
+
+    #[test]
+    fn maps_primitive_types() {
+        let cases = [
+            ("byte", "uint8_t"),
+            ("short", "int16_t"),
+            ("ushort", "uint16_t"),
+            ("int", "int32_t"),
+            ("uint", "uint32_t"),
+            ("long", "int64_t"),
+            ("ulong", "uint64_t"),
+        ];
+        for (type_, c_type) in cases {
+            let code = generate(&format!("void main() {{\n    {} x = 1\n}}\n", type_)).unwrap();
+            assert!(code.contains(&format!("    {} hlv_x = 1;\n", c_type)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn includes_only_used_headers() {
+        let code = generate("void main() {\n    byte b = 1\n}\n").unwrap();
+        assert!(code.starts_with("#include <stdint.h>\n#include <stdio.h>\n\n"), "{}", code);
+        let code = generate("void main() {\n    print(\"hi\")\n}\n").unwrap();
+        assert!(code.starts_with("#include <stdio.h>\n\nvoid hl_main(void);\n"), "{}", code);
+    }
+
+    #[test]
+    fn parenthesises_expressions() {
+        let cases = [
+            ("int", "1 + 2 * -3", "int32_t", "(1 + (2 * (-3)))"),
+            ("int", "10 - 4 - 3", "int32_t", "((10 - 4) - 3)"),
+            ("int", "(1 + 2) * 3", "int32_t", "((1 + 2) * 3)"),
+            (
+                "bool",
+                "1 < 2 == !false || true && false",
+                "bool",
+                "(((1 < 2) == (!false)) || (true && false))",
+            ),
+            ("int", "(1 +\n    2)", "int32_t", "(1 + 2)"),
+        ];
+        for (type_, expr, c_type, c_expr) in cases {
+            let code = generate(&format!("void main() {{\n    {} x = {}\n}}\n", type_, expr)).unwrap();
+            assert!(code.contains(&format!("    {} hlv_x = {};\n", c_type, c_expr)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn converts_initialisers_to_runtime_values() {
+        let cases = [
+            (
+                "financial f = -12.5financial",
+                "financial_t hlv_f = financial_fromUnits(-125000);",
+            ),
+            ("financial f = 3", "financial_t hlv_f = financial_fromUnits(30000);"),
+            ("string s = \"hi\"", "string_t hlv_s = string_new(\"hi\");"),
+            ("dyn d = 300", "dyn_t hlv_d = dyn_newInt(300);"),
+            ("dyn d = 5byte", "dyn_t hlv_d = dyn_newByte(5);"),
+            ("dyn d = \"hi\"", "dyn_t hlv_d = dyn_StringFromCstr(\"hi\");"),
+            ("double x = 1.0 / 3", "double hlv_x = (1.0 / 3.0);"),
+        ];
+        for (decl, c_decl) in cases {
+            let code = generate(&format!("void main() {{\n    {}\n}}\n", decl)).unwrap();
+            assert!(code.contains(c_decl), "{}", code);
+        }
+    }
+
+    #[test]
+    fn emits_integer_boundaries() {
+        let code = generate("void main() {\n    long min = -9223372036854775808\n    long max = 9223372036854775807\n    ulong big = 18446744073709551615\n    financial f = -922337203685477.5808financial\n}\n").unwrap();
+        assert!(code.contains("int64_t hlv_min = (-9223372036854775807LL - 1);"), "{}", code);
+        assert!(code.contains("int64_t hlv_max = 9223372036854775807;"), "{}", code);
+        assert!(code.contains("uint64_t hlv_big = 18446744073709551615ull;"), "{}", code);
+        assert!(code.contains("financial_fromUnits(-9223372036854775807LL - 1)"), "{}", code);
+    }
+
+    #[test]
+    fn reports_variable_errors() {
+        let err = generate("void main() {\n    int x = y\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "cannot find variable `y`");
+        assert_eq!(err.span(), Some(Span::new(26, 1, 2, 13)));
+
+        let err = generate("void main() {\n    int x = x\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "cannot find variable `x`");
+
+        let err = generate("void main() {\n    int x = 1\n    bool x = true\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "variable `x` is already defined");
+        assert_eq!(err.span(), Some(Span::new(37, 1, 3, 10)));
+    }
+
+    #[test]
+    fn generates_functions_with_prototypes() {
+        let code = generate("void main() {\n    greet(2, \"x\")\n}\n\nvoid greet(int char, string name) {\n}\n").unwrap();
+        assert!(
+            code.contains("void hl_main(void);\nvoid hl_greet(int32_t hlv_char, string_t hlv_name);\n"),
+            "{}",
+            code
+        );
+        assert!(code.contains("    hl_greet(2, string_new(\"x\"));\n"), "{}", code);
+        assert!(
+            code.ends_with("int main(void) {\n    hl_main();\n    return 0;\n}\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn reports_function_errors() {
+        let cases = [
+            ("void main() {\n}\nvoid main() {\n}\n", "function `main` is already defined"),
+            ("void main() {\n}\nvoid print() {\n}\n", "function `print` is already defined"),
+            ("void main() {\n    foo()\n}\n", "cannot find function `foo`"),
+            (
+                "void main() {\n    foo(1, 2)\n}\nvoid foo(int a) {\n}\n",
+                "function `foo` takes 1 argument, but 2 were given",
+            ),
+            (
+                "void main() {\n    int x = foo()\n}\nvoid foo() {\n}\n",
+                "function `foo` doesn't return a value",
+            ),
+        ];
+        for (source, message) in cases {
+            assert_eq!(generate(source).unwrap_err().to_string(), message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn generates_returns() {
+        let code = generate("void main() {\n    long x = twice(2)\n    return\n}\n\nlong twice(long x) {\n    return x * 2\n}\n")
+            .unwrap();
+        assert!(code.contains("    int64_t hlv_x = hl_twice(2);\n    return;\n"), "{}", code);
+        assert!(
+            code.contains("int64_t hl_twice(int64_t hlv_x) {\n    return (hlv_x * 2);\n}\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn reports_return_errors() {
+        let err = generate("void main() {\n}\n\nint foo() {\n    print(\"a\")\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "function `foo` doesn't return a value on every path");
+        assert_eq!(err.span(), Some(Span::new(17, 28, 4, 1)));
+
+        let err = generate("void main() {\n    return 1\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "`void` function can't return a value");
+        assert_eq!(err.span(), Some(Span::new(25, 1, 2, 12)));
+
+        let err = generate("void main() {\n}\n\nint foo() {\n    return\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "`return` must have a value of type `int`");
+        assert_eq!(err.span(), Some(Span::new(33, 6, 5, 5)));
+    }
+
+    #[test]
+    fn generates_control_flow() {
+        let source = "void main() {
+    int x = 0
+    if (x < 1) {
+        print(\"a\")
+    } else if x == 3 {
+        print(\"b\")
+    } else {
+        print(\"c\")
+    }
+    while (x < 10) {
+        if (x == 5) {
+            continue
+        }
+        break
+    }
+    for (int i = 0; i < 3; tick()) {
+        {
+            int y = i
+        }
+    }
+    for (;;) {
+        break
+    }
+}
+
+void tick() {
+}
+";
+        let code = generate(source).unwrap();
+        let expected = "    if (hlv_x < 1) {
+        printf(\"%s\", \"a\");
+    } else if (hlv_x == 3) {
+        printf(\"%s\", \"b\");
+    } else {
+        printf(\"%s\", \"c\");
+    }
+    while (hlv_x < 10) {
+        if (hlv_x == 5) {
+            continue;
+        }
+        break;
+    }
+    {
+        int32_t hlv_i = 0;
+        for (; (hlv_i < 3); hl_tick()) {
+            {
+                int32_t hlv_y = hlv_i;
+            }
+        }
+    }
+    while (true) {
+        break;
+    }
+";
+        assert!(code.contains(expected), "{}", code);
+    }
+
+    #[test]
+    fn reports_control_flow_errors() {
+        let err = generate("void main() {\n    break\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "`break` outside of a loop");
+        assert_eq!(err.span(), Some(Span::new(18, 5, 2, 5)));
+
+        // variables of blocks and loop headers are not visible after them
+        let err = generate("void main() {\n    for (int i = 0; i < 3; ) {\n    }\n    int j = i\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "cannot find variable `i`");
+        let err = generate("void main() {\n    {\n        int i = 0\n    }\n    int j = i\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "cannot find variable `i`");
+    }
+
+    #[test]
+    fn checks_that_every_path_returns() {
+        let returning = "void main() {\n}\n\nint sign(int x) {\n    if (x < 0) {\n        return -1\n    } else if (x > 0) {\n        return 1\n    } else {\n        {\n            return 0\n        }\n    }\n}\n";
+        assert!(generate(returning).is_ok());
+
+        let err = generate("void main() {\n}\n\nint sign(int x) {\n    if (x < 0) {\n        return -1\n    }\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "function `sign` doesn't return a value on every path");
+        assert_eq!(err.span(), Some(Span::new(17, 60, 4, 1)));
+
+        // the loop may run zero times, so it is pointed at
+        let err = generate("void main() {\n}\n\nint first() {\n    while (true) {\n        return 1\n    }\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "function `first` doesn't return a value on every path");
+        assert_eq!(err.span(), Some(Span::new(35, 37, 5, 5)));
+    }
+
+    #[test]
+    fn generates_assignments() {
+        let source = "void main() {\n    int x = 1\n    x = 2\n    x += 3\n    x %= 2\n    double d = 1.0\n    d /= 2\n    for (int i = 0; i < 3; i += 1) {\n    }\n}\n";
+        let code = generate(source).unwrap();
+        assert!(
+            code.contains("    hlv_x = 2;\n    hlv_x = (hlv_x + 3);\n    hlv_x = (hlv_x % 2);\n    double hlv_d = 1.0;\n    hlv_d = (hlv_d / 2.0);\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("        int32_t hlv_i = 0;\n        for (; (hlv_i < 3); hlv_i = (hlv_i + 1)) {"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn reports_assignment_errors() {
+        let err = generate("void main() {\n    double d = 1.0\n    d %= 2\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "operator `%=` can't be applied to type `double`");
+        assert_eq!(err.span(), Some(Span::new(37, 6, 3, 5)));
+
+        let err = generate("void main() {\n    bool b = true\n    b += true\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "operator `+=` can't be applied to type `bool`");
+
+        let err = generate("void main() {\n    y = 1\n}\n").unwrap_err();
+        assert_eq!(err.to_string(), "cannot find variable `y`");
+    }
+
+    #[test]
+    fn emits_doc_comments() {
+        let code = generate("/// Prints */ twice.\n///\nvoid main() {\n    print(\"hi\")\n}\n").unwrap();
+        assert!(
+            code.contains("/**\n * Prints * / twice.\n *\n */\nvoid hl_main(void) {\n"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let code = generate("void main() {\n    print(\"100% \\\"q\\\"\\n\u{e9}??\")\n}\n").unwrap();
+        assert!(code.contains(r#"printf("%s", "100% \"q\"\n\303\251\?\?");"#), "{}", code);
+    }
+
+    #[test]
+    fn calls_runtime_builtins() {
+        let source = "void main() {\n    string s = longToString(5)\n    dyn d = 1\n    string t = dynTypeName(d)\n}\n";
+        let code = generate(source).unwrap();
+        assert!(code.contains("#include \"dyn.h\"\n#include \"strconv.h\"\n"), "{}", code);
+        assert!(code.contains("    string_t hlv_s = strconv_longToString(5);\n"), "{}", code);
+        assert!(code.contains("    string_t hlv_t = dyn_typeName(hlv_d);\n"), "{}", code);
+
+        let err = generate("void main() {\n    string s = longToString(\"5\")\n}\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mismatched types: expected value of type `long`, but found `string`"
+        );
+    }
+
+    #[test]
+    fn prints_values_of_every_type() {
+        let source =
+            "void main() {\n    int i = 1\n    print(\"a\", i, 0.5)\n    println(true, 'x', 5financial)\n    println()\n}\n";
+        let code = generate(source).unwrap();
+        assert!(code.contains("#include \"printer.h\"\n"), "{}", code);
+        let expected = "    printf(\"%s\", \"a\");
+    printLong(hlv_i);
+    printDouble(0.5);
+    printBool(true);
+    printRune(120);
+    printFinancial(financial_fromUnits(50000));
+    printf(\"\\n\");
+    printf(\"\\n\");
+";
+        assert!(code.contains(expected), "{}", code);
+
+        // the line break is merged into the last string literal
+        let code = generate("void main() {\n    println(1, \"a\")\n}\n").unwrap();
+        assert!(
+            code.contains("    printLong(1);\n    printf(\"%s\\n\", \"a\");\n}"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn generates_classes() {
+        let source = "/// Point on a plane.
+class Point { int x, int y }
+
+void main() {
+    Point p = Point(1, 2)
+    p.x = p.y
+    move(p).y += 3
+}
+
+Point move(Point p) {
+    return p
+}
+";
+        let code = generate(source).unwrap();
+        let expected = "typedef struct hlt_Point hlt_Point;
+
+/**
+ * Point on a plane.
+ */
+struct hlt_Point {
+    int32_t hlv_x;
+    int32_t hlv_y;
+};
+
+hlt_Point* hlnew_Point(int32_t hlv_x, int32_t hlv_y);
+void hl_main(void);
+hlt_Point* hl_move(hlt_Point* hlv_p);
+
+hlt_Point* hlnew_Point(int32_t hlv_x, int32_t hlv_y) {
+    hlt_Point* object = memAlloc(sizeof(hlt_Point));
+    object->hlv_x = hlv_x;
+    object->hlv_y = hlv_y;
+    return object;
+}
+";
+        assert!(code.contains(expected), "{}", code);
+        assert!(code.contains("#include \"mem.h\"\n"), "{}", code);
+        // the object of a compound assignment is evaluated once
+        let expected = "    hlv_p->hlv_x = (hlv_p->hlv_y);
+    {
+        hlt_Point* hlv_object_3 = hl_move(hlv_p);
+        hlv_object_3->hlv_y = ((hlv_object_3->hlv_y) + 3);
+    }
+";
+        assert!(code.contains(expected), "{}", code);
+
+        let err = generate("void main() {\n    Point(1)\n}\n\nclass Point { int x }\n").unwrap_err();
+        assert_eq!(err.to_string(), "object of class `Point` is constructed but never used");
+        assert_eq!(err.span(), Some(Span::new(18, 8, 2, 5)));
+    }
+
+    #[test]
+    fn generates_value_classes() {
+        let source = "value class Price { Money money, int count }
+
+value class Money { string currency, financial amount }
+
+void main() {
+    Price a = Price(Money(\"EUR\", 1.5), 2)
+    bool less = a < a
+    bool same = a != a
+    string s = string(a)
+    println(a.money)
+}
+";
+        let code = generate(source).unwrap();
+        // values are embedded, so the struct of `Money` goes first
+        let expected = "struct hlt_Money {
+    string_t hlv_currency;
+    financial_t hlv_amount;
+};
+
+struct hlt_Price {
+    hlt_Money hlv_money;
+    int32_t hlv_count;
+};
+";
+        assert!(code.contains(expected), "{}", code);
+        let expected = "hlt_Money hlnew_Money(string_t hlv_currency, financial_t hlv_amount) {
+    hlt_Money object;
+    object.hlv_currency = hlv_currency;
+    object.hlv_amount = hlv_amount;
+    return object;
+}
+";
+        assert!(code.contains(expected), "{}", code);
+        let expected = "int hlcmp_Price(hlt_Price left, hlt_Price right) {
+    int result;
+    result = hlcmp_Money(left.hlv_money, right.hlv_money);
+    if (result != 0) {
+        return result;
+    }
+    if (left.hlv_count != right.hlv_count) {
+        return left.hlv_count < right.hlv_count ? -1 : 1;
+    }
+    return 0;
+}
+";
+        assert!(code.contains(expected), "{}", code);
+        assert!(
+            code.contains("    hash = hash_combine(hash, string_hash(value.hlv_currency));\n"),
+            "{}",
+            code
+        );
+        let expected = "    bool hlv_less = (hlcmp_Price(hlv_a, hlv_a) < 0);
+    bool hlv_same = (!hleq_Price(hlv_a, hlv_a));
+    string_t hlv_s = hltostring_Price(hlv_a);
+    printOwnedString(hltostring_Money((hlv_a.hlv_money)));
+";
+        assert!(code.contains(expected), "{}", code);
+    }
+
+    #[test]
+    fn generates_financial_and_string_operators() {
+        let source = "void main() {
+    financial f = 1.5
+    f *= -f + 0.25
+    string s = \"a\"
+    println(f >= 1, s == \"b\", s != s, s < s)
+}
+";
+        let code = generate(source).unwrap();
+        let expected =
+            "    hlv_f = financial_multiply(hlv_f, financial_add(financial_negate(hlv_f), financial_fromUnits(2500)));\n";
+        assert!(code.contains(expected), "{}", code);
+        let expected = "(hlv_f.units >= financial_fromUnits(10000).units)";
+        assert!(code.contains(expected), "{}", code);
+        assert!(code.contains("(string_equals(hlv_s, "), "{}", code);
+        assert!(code.contains("(!string_equals(hlv_s, hlv_s))"), "{}", code);
+        assert!(code.contains("(string_compare(hlv_s, hlv_s) < 0)"), "{}", code);
+    }
+
+    #[test]
+    fn generates_arrays() {
+        let source = "void main() {
+    []int xs = [1, 2]
+    xs[0] += xs[1]
+    append(xs, 3)
+    for (int x : xs) {
+        println(x, xs.len)
+    }
+}
+";
+        let code = generate(source).unwrap();
+        assert!(code.contains("#include \"array.h\"\n"), "{}", code);
+        let expected = "    array_t* hlv_xs = array_of(sizeof(int32_t), 2, (int32_t[]){1, 2});
+    *(int32_t*) array_at(hlv_xs, 0) = ((*(int32_t*) array_at(hlv_xs, 0)) + (*(int32_t*) array_at(hlv_xs, 1)));
+    array_append(hlv_xs, (int32_t[]){3});
+";
+        assert!(code.contains(expected), "{}", code);
+        // the array is evaluated once, before the loop
+        let expected = "    {
+        array_t* hlv_array_4 = hlv_xs;
+        int32_t hlv_index_5 = 0;
+        for (; (hlv_index_5 < ((int32_t) hlv_array_4->len)); hlv_index_5 = (hlv_index_5 + 1)) {
+            int32_t hlv_x = (*(int32_t*) array_at(hlv_array_4, hlv_index_5));
+            printLong(hlv_x);
+            printLong(((int32_t) hlv_xs->len));
+";
+        assert!(code.contains(expected), "{}", code);
+
+        // the array and the index of a compound assignment are evaluated once
+        let source =
+            "void main() {\n    make()[index()] += 1\n}\n\n[]int make() {\n    return [1]\n}\n\nint index() {\n    return 0\n}\n";
+        let code = generate(source).unwrap();
+        let expected = "    {
+        array_t* hlv_array_1 = hl_make();
+        int32_t hlv_index_2 = hl_index();
+        *(int32_t*) array_at(hlv_array_1, hlv_index_2) = ((*(int32_t*) array_at(hlv_array_1, hlv_index_2)) + 1);
+    }
+";
+        assert!(code.contains(expected), "{}", code);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::lexer::Span;
+use std::fmt::Write;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    const fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+/// Message about a problem in the source code which is shown to the user.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    level: Level,
+    message: String,
+    span: Span,
+    help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.into(),
+            span,
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Level::Error, message, span)
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help.push(help.into());
+        self
+    }
+
+    /// Renders diagnostic with the snippet of `source` it points to:
+    ///
+    /// ```text
+    /// error: expected `)`, but found line break
+    ///  --> main.hl:2:15
+    ///   |
+    /// 2 |     print("foo"
+    ///   |                ^
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let line_number = self.span.line().to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}: {}", self.level.name(), self.message);
+        if self.span.line() == 0 {
+            let _ = writeln!(out, "{}--> {}", gutter, file_name);
+        } else {
+            let _ = writeln!(out, "{}--> {}:{}:{}", gutter, file_name, self.span.line(), self.span.column());
+        }
+
+        if let Some(line) = source_line(source, self.span) {
+            let _ = writeln!(out, "{} |", gutter);
+            let _ = writeln!(out, "{} | {}", line_number, line);
+            let _ = writeln!(out, "{} | {}", gutter, underline(line, self.span));
+        }
+        for help in &self.help {
+            let _ = writeln!(out, "{} = help: {}", gutter, help);
+        }
+        out
+    }
+}
+
+fn source_line(source: &str, span: Span) -> Option<&str> {
+    if span.line() == 0 {
+        return None;
+    }
+    source
+        .split('\n')
+        .nth(span.line() - 1)
+        .map(|line| line.trim_end_matches('\r'))
+}
+
+fn underline(line: &str, span: Span) -> String {
+    let mut chars = line.chars();
+    let mut result = String::new();
+
+    // keep tabs so that carets line up with the source line
+    for char in chars.by_ref().take(span.column().saturating_sub(1)) {
+        result.push(if char == '\t' { '\t' } else { ' ' });
+    }
+    let underlined = source_len_in_chars(chars.as_str(), span.len());
+    result.extend((0..underlined.max(1)).map(|_| '^'));
+    result
+}
+
+fn source_len_in_chars(rest_of_line: &str, len: usize) -> usize {
+    rest_of_line.char_indices().take_while(|(offset, _)| *offset < len).count()
+}
+
+/// Prints all diagnostics to stderr.
+pub fn emit_all<'d, I>(diagnostics: I, file_name: &str, source: &str)
+where
+    I: IntoIterator<Item = &'d Diagnostic>,
+{
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.render(file_name, source));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Diagnostic;
+    use crate::lexer::Span;
+
+    #[test]
+    fn renders_source_snippet() {
+        let source = "void main() {\n    print(\"foo\"\n}\n";
+        let diagnostic =
+            Diagnostic::error("expected `)`, but found line break", Span::new(30, 1, 2, 16)).with_help("close the argument list");
+        assert_eq!(
+            diagnostic.render("main.hl", source),
+            "error: expected `)`, but found line break
+ --> main.hl:2:16
+  |
+2 |     print(\"foo\"
+  |                ^
+  = help: close the argument list
+"
+        );
+    }
+
+    #[test]
+    fn underlines_whole_span() {
+        // tabs are kept and the underline is counted in chars
+        let source = "\tint ключ = 1";
+        let diagnostic = Diagnostic::error("bad name", Span::new(5, 8, 1, 6));
+        assert_eq!(
+            diagnostic.render("main.hl", source),
+            "error: bad name\n --> main.hl:1:6\n  |\n1 | \tint ключ = 1\n  | \t    ^^^^\n"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::diagnostics::Diagnostic;
+use crate::ir::{IrError, LoweringError};
+use crate::lexer::{LexError, Span};
+use crate::parsing::ParsingError;
+use crate::sema::SemaError;
+use std::error;
+use std::fmt;
+
+/// Any error that can happen while compiling user's code.
+#[derive(Debug)]
+pub enum CompileError {
+    Lex(LexError),
+    Parse(ParsingError),
+    Sema(SemaError),
+    Lowering(LoweringError),
+    Ir(IrError),
+}
+
+impl CompileError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CompileError::Lex(err) => Some(err.span()),
+            CompileError::Parse(err) => Some(err.span()),
+            CompileError::Sema(err) => Some(err.span()),
+            CompileError::Lowering(err) => err.span(),
+            CompileError::Ir(err) => err.span(),
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            CompileError::Parse(err) => err.diagnostic(),
+            CompileError::Sema(err) => err.diagnostic(),
+            _ => Diagnostic::error(self.to_string(), self.span().unwrap_or_default()),
+        }
+    }
+}
+
+impl From<LexError> for CompileError {
+    fn from(err: LexError) -> Self {
+        CompileError::Lex(err)
+    }
+}
+
+impl From<ParsingError> for CompileError {
+    fn from(err: ParsingError) -> Self {
+        CompileError::Parse(err)
+    }
+}
+
+impl From<SemaError> for CompileError {
+    fn from(err: SemaError) -> Self {
+        CompileError::Sema(err)
+    }
+}
+
+impl From<LoweringError> for CompileError {
+    fn from(err: LoweringError) -> Self {
+        CompileError::Lowering(err)
+    }
+}
+
+impl From<IrError> for CompileError {
+    fn from(err: IrError) -> Self {
+        CompileError::Ir(err)
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Lex(err) => err.fmt(f),
+            CompileError::Parse(err) => err.fmt(f),
+            CompileError::Sema(err) => err.fmt(f),
+            CompileError::Lowering(err) => err.fmt(f),
+            CompileError::Ir(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for CompileError {}
+
+pub type Result<T> = std::result::Result<T, CompileError>;
@@ -0,0 +1,208 @@
+use crate::lexer::Span;
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LoweringError {
+    MainNotFound,
+}
+
+impl LoweringError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LoweringError::MainNotFound => None,
+        }
+    }
+}
+
+impl fmt::Display for LoweringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoweringError::MainNotFound => f.write_str("`void main()` function not found"),
+        }
+    }
+}
+
+impl error::Error for LoweringError {}
+
+/// Problem of the program read from the IR source, which is found before generating code from it.
+#[derive(Debug)]
+pub enum IrError {
+    MainNotFound,
+    AlreadyDefined {
+        what: &'static str,
+        name: String,
+        span: Span,
+    },
+    Undefined {
+        what: &'static str,
+        name: String,
+        span: Span,
+    },
+    InvalidName {
+        name: String,
+        span: Span,
+    },
+    /// `AnyType` outside of the params of builtins.
+    AnyType {
+        span: Span,
+    },
+    InvalidFieldType {
+        class: String,
+        type_name: String,
+        span: Span,
+    },
+    RecursiveValueClass {
+        class: String,
+        span: Span,
+    },
+    BuiltinSignature {
+        name: String,
+        span: Span,
+    },
+    MismatchedTypes {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    ConstantOutOfRange {
+        type_name: String,
+        span: Span,
+    },
+    NoValue {
+        function: String,
+        span: Span,
+    },
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    InvalidOperator {
+        operator: &'static str,
+        type_name: String,
+        span: Span,
+    },
+    InvalidConversion {
+        from: String,
+        to: String,
+        span: Span,
+    },
+    NoField {
+        type_name: String,
+        field: usize,
+        span: Span,
+    },
+    ImmutableField {
+        class: String,
+        span: Span,
+    },
+    NotArray {
+        type_name: String,
+        span: Span,
+    },
+    InvalidIndex {
+        type_name: String,
+        span: Span,
+    },
+    NotPrintable {
+        type_name: String,
+        span: Span,
+    },
+    OutsideOfLoop {
+        what: &'static str,
+        span: Span,
+    },
+}
+
+impl IrError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            IrError::MainNotFound => None,
+            IrError::AlreadyDefined { span, .. }
+            | IrError::Undefined { span, .. }
+            | IrError::InvalidName { span, .. }
+            | IrError::AnyType { span }
+            | IrError::InvalidFieldType { span, .. }
+            | IrError::RecursiveValueClass { span, .. }
+            | IrError::BuiltinSignature { span, .. }
+            | IrError::MismatchedTypes { span, .. }
+            | IrError::ConstantOutOfRange { span, .. }
+            | IrError::NoValue { span, .. }
+            | IrError::WrongArgumentCount { span, .. }
+            | IrError::InvalidOperator { span, .. }
+            | IrError::InvalidConversion { span, .. }
+            | IrError::NoField { span, .. }
+            | IrError::ImmutableField { span, .. }
+            | IrError::NotArray { span, .. }
+            | IrError::InvalidIndex { span, .. }
+            | IrError::NotPrintable { span, .. }
+            | IrError::OutsideOfLoop { span, .. } => Some(*span),
+        }
+    }
+}
+
+impl fmt::Display for IrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IrError::MainNotFound => f.write_str("`void main()` function not found"),
+            IrError::AlreadyDefined { what, name, .. } => f.write_fmt(format_args!("{} `{}` is already defined", what, name)),
+            IrError::Undefined { what, name, .. } => f.write_fmt(format_args!("cannot find {} `{}`", what, name)),
+            IrError::InvalidName { name, .. } => f.write_fmt(format_args!("`{}` is not a valid name", name)),
+            IrError::AnyType { .. } => f.write_str("`AnyType` can only be the type of params of builtins"),
+            IrError::InvalidFieldType { class, type_name, .. } => f.write_fmt(format_args!(
+                "value class `{}` can't have a field of type `{}`",
+                class, type_name
+            )),
+            IrError::RecursiveValueClass { class, .. } => f.write_fmt(format_args!("value class `{}` contains itself", class)),
+            IrError::BuiltinSignature { name, .. } => f.write_fmt(format_args!(
+                "declaration of builtin `{}` doesn't match the builtin registry",
+                name
+            )),
+            IrError::MismatchedTypes { expected, found, .. } => {
+                f.write_fmt(format_args!("mismatched types: expected `{}`, found `{}`", expected, found))
+            }
+            IrError::ConstantOutOfRange { type_name, .. } => {
+                f.write_fmt(format_args!("constant is out of range of type `{}`", type_name))
+            }
+            IrError::NoValue { function, .. } => f.write_fmt(format_args!("function `{}` doesn't return a value", function)),
+            IrError::WrongArgumentCount {
+                function,
+                expected,
+                found,
+                ..
+            } => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                let verb = if *found == 1 { "was" } else { "were" };
+                f.write_fmt(format_args!(
+                    "`{}` takes {} argument{}, but {} {} passed",
+                    function, expected, plural, found, verb
+                ))
+            }
+            IrError::InvalidOperator { operator, type_name, .. } => f.write_fmt(format_args!(
+                "operator `{}` can't be applied to values of type `{}`",
+                operator, type_name
+            )),
+            IrError::InvalidConversion { from, to, .. } => {
+                f.write_fmt(format_args!("values of type `{}` can't be converted to `{}`", from, to))
+            }
+            IrError::NoField { type_name, field, .. } => {
+                f.write_fmt(format_args!("type `{}` has no field with index {}", type_name, field))
+            }
+            IrError::ImmutableField { class, .. } => {
+                f.write_fmt(format_args!("fields of objects of value class `{}` can't be assigned", class))
+            }
+            IrError::NotArray { type_name, .. } => f.write_fmt(format_args!("expected an array, found `{}`", type_name)),
+            IrError::InvalidIndex { type_name, .. } => {
+                f.write_fmt(format_args!("arrays can't be indexed with values of type `{}`", type_name))
+            }
+            IrError::NotPrintable { type_name, .. } => {
+                f.write_fmt(format_args!("values of type `{}` can't be printed", type_name))
+            }
+            IrError::OutsideOfLoop { what, .. } => f.write_fmt(format_args!("`{}` outside of a loop body", what)),
+        }
+    }
+}
+
+impl error::Error for IrError {}
@@ -0,0 +1,682 @@
+use super::{
+    intern_array, Arg, Block, Class, ClassId, Constant, Field, Function, Instr, InstrId, InstrKind, LoweringError, Marker, Place,
+    Program, Type, Value,
+};
+use crate::builtins::{self, Builtin, ParamType};
+use crate::error::Result;
+use crate::parsing::ast::expr::BinaryOp;
+use crate::parsing::ast::expr::{Expr, ExprKind};
+use crate::parsing::ast::stmt::{self, Statement};
+use crate::parsing::ast::{self, LiteralKind};
+use crate::sema::{as_constant, financial_units, Analysis, SymbolId, SymbolKind};
+use std::collections::{HashMap, HashSet};
+
+/// Class ids by the names of the classes.
+type ClassIds<'a> = HashMap<&'a str, ClassId>;
+
+/// Lowers the type, array types are added to `arrays` when they are met for the first time.
+/// `elements` are the element types of the array types of the AST.
+fn lower_type(type_: ast::Type, class_ids: &ClassIds, elements: &[ast::Type], arrays: &mut Vec<Type>) -> Type {
+    match type_ {
+        ast::Type::Byte => Type::Byte,
+        ast::Type::Short => Type::Short,
+        ast::Type::UShort => Type::UShort,
+        ast::Type::Int => Type::Int,
+        ast::Type::UInt => Type::UInt,
+        ast::Type::Long => Type::Long,
+        ast::Type::ULong => Type::ULong,
+        ast::Type::Double => Type::Double,
+        ast::Type::Financial => Type::Financial,
+        ast::Type::Bool => Type::Bool,
+        ast::Type::String => Type::String,
+        ast::Type::Rune => Type::Rune,
+        ast::Type::Dyn => Type::Dyn,
+        ast::Type::Custom { name } => Type::Class(
+            *class_ids
+                .get(name)
+                .unwrap_or_else(|| unreachable!("type `{}` is not resolved to a class", name)),
+        ),
+        ast::Type::Array(array) => {
+            let element = lower_type(elements[array.0], class_ids, elements, arrays);
+            Type::Array(intern_array(arrays, element))
+        }
+    }
+}
+
+/// Lowers the checked AST into IR. Builtins which are called by the program are declared after
+/// its functions, in the order of the registry.
+pub fn lower_program(tree: &ast::AST, analysis: &Analysis) -> Result<Program> {
+    let has_main = tree
+        .functions()
+        .any(|decl| decl.name() == "main" && decl.return_type() == ast::TypeOrVoid::Void && decl.args().is_empty());
+    if !has_main {
+        return Err(LoweringError::MainNotFound.into());
+    }
+
+    let class_ids: ClassIds = tree
+        .classes()
+        .enumerate()
+        .map(|(index, class)| (class.name().name, ClassId(index)))
+        .collect();
+    let mut arrays = Vec::new();
+    let classes = tree
+        .classes()
+        .map(|class| lower_class(class, &class_ids, analysis.arrays(), &mut arrays))
+        .collect();
+
+    let mut functions = Vec::new();
+    let mut used_builtins = HashSet::new();
+    for decl in tree.functions() {
+        functions.push(lower_function(decl, analysis, &class_ids, &mut arrays, &mut used_builtins)?);
+    }
+    for builtin in &builtins::BUILTINS {
+        if used_builtins.contains(builtin.name) {
+            functions.push(lower_builtin(builtin));
+        }
+    }
+    Ok(Program {
+        classes,
+        arrays,
+        functions,
+    })
+}
+
+fn lower_class(class: &ast::ClassDeclaration, class_ids: &ClassIds, elements: &[ast::Type], arrays: &mut Vec<Type>) -> Class {
+    let fields = class
+        .fields()
+        .iter()
+        .map(|field| Field {
+            name: field.name().name.to_string(),
+            type_: lower_type(field.field_type(), class_ids, elements, arrays),
+        })
+        .collect();
+    Class {
+        name: class.name().name.to_string(),
+        fields,
+        docs: class.docs().iter().map(|line| line.to_string()).collect(),
+        value: class.is_value(),
+    }
+}
+
+pub(super) fn lower_builtin(builtin: &Builtin) -> Function {
+    // builtins don't use classes and arrays
+    let class_ids = ClassIds::new();
+    let args = builtin
+        .params
+        .iter()
+        .map(|param| Arg {
+            name: param.name.to_string(),
+            type_: match param.type_ {
+                ParamType::Any | ParamType::Array | ParamType::Element => Type::Any,
+                ParamType::Exact(type_) => lower_type(type_, &class_ids, &[], &mut Vec::new()),
+            },
+            markers: vec![Marker::In],
+        })
+        .collect();
+    let returns = match builtin.returns {
+        ast::TypeOrVoid::Void => None,
+        ast::TypeOrVoid::Type(type_) => Some(lower_type(type_, &class_ids, &[], &mut Vec::new())),
+    };
+    Function {
+        name: builtin.name.to_string(),
+        args,
+        returns,
+        body: None,
+        docs: Vec::new(),
+    }
+}
+
+fn lower_function(
+    decl: &ast::FunctionDeclaration,
+    analysis: &Analysis,
+    class_ids: &ClassIds,
+    arrays: &mut Vec<Type>,
+    used_builtins: &mut HashSet<&'static str>,
+) -> Result<Function> {
+    let mut lowerer = Lowerer {
+        analysis,
+        class_ids,
+        arrays,
+        used_builtins,
+        return_type: decl.return_type(),
+        places: HashMap::new(),
+        next_id: 0,
+    };
+    let mut args = Vec::with_capacity(decl.args().len());
+    for (index, arg) in decl.args().iter().enumerate() {
+        lowerer.places.insert(lowerer.symbol(arg.name()), Place::Param(index));
+        args.push(Arg {
+            name: arg.name().name.to_string(),
+            type_: lowerer.lower_type(arg.arg_type()),
+            markers: vec![Marker::In],
+        });
+    }
+    let returns = match decl.return_type() {
+        ast::TypeOrVoid::Void => None,
+        ast::TypeOrVoid::Type(type_) => Some(lowerer.lower_type(type_)),
+    };
+    let body = lowerer.lower_block(decl.stmts())?;
+    Ok(Function {
+        name: decl.name().name.to_string(),
+        args,
+        returns,
+        body: Some(body),
+        docs: decl.docs().iter().map(|line| line.to_string()).collect(),
+    })
+}
+
+struct Lowerer<'t, 'a> {
+    analysis: &'t Analysis<'a>,
+    class_ids: &'t ClassIds<'t>,
+    /// Element types of the array types of the program.
+    arrays: &'t mut Vec<Type>,
+    return_type: ast::TypeOrVoid<'a>,
+    /// Places of the variables and params by their symbols.
+    places: HashMap<SymbolId, Place>,
+    /// Names of the builtins called in the program.
+    used_builtins: &'t mut HashSet<&'static str>,
+    next_id: usize,
+}
+
+impl<'t, 'a> Lowerer<'t, 'a> {
+    fn symbol(&self, ident: ast::Ident) -> SymbolId {
+        self.analysis
+            .resolve(ident)
+            .unwrap_or_else(|| unreachable!("`{}` is not resolved", ident.name))
+    }
+
+    fn place(&self, ident: ast::Ident) -> Place {
+        self.places[&self.symbol(ident)]
+    }
+
+    /// Index of the field in the class of `object`.
+    fn field_index(&self, object: &Expr, field: &str) -> usize {
+        let ast::Type::Custom { name } = self.expr_type(object) else {
+            unreachable!("only objects have fields");
+        };
+        let fields = self.analysis.class_fields(name).expect("type of an object is a class");
+        fields
+            .iter()
+            .position(|(name, _)| *name == field)
+            .unwrap_or_else(|| unreachable!("class `{}` has no field `{}`", name, field))
+    }
+
+    fn lower_type(&mut self, type_: ast::Type) -> Type {
+        lower_type(type_, self.class_ids, self.analysis.arrays(), self.arrays)
+    }
+
+    fn expr_type(&self, expr: &Expr) -> ast::Type<'a> {
+        self.analysis
+            .expr_type(expr)
+            .expect("types of expressions are inferred by the type checker")
+    }
+
+    /// Instructions are numbered in the order they appear in the source, so instructions
+    /// containing blocks take their number before the instructions of the blocks.
+    fn new_id(&mut self) -> InstrId {
+        let id = InstrId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn lower_block(&mut self, stmts: &[Statement]) -> Result<Block> {
+        let mut block = Block::default();
+        for stmt in stmts {
+            self.lower_stmt(stmt, &mut block)?;
+        }
+        Ok(block)
+    }
+
+    fn lower_stmt(&mut self, stmt: &Statement, block: &mut Block) -> Result<()> {
+        let id = self.new_id();
+        let kind = match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                let value = self.lower_value(var_decl.value(), var_decl.var_type())?;
+                // the variable becomes visible after its initialiser
+                self.places.insert(self.symbol(var_decl.name()), Place::Var(id));
+                InstrKind::VarDecl {
+                    name: var_decl.name().name.to_string(),
+                    type_: self.lower_type(var_decl.var_type()),
+                    value,
+                }
+            }
+            Statement::FunctionCall(func_call) => InstrKind::Call {
+                function: func_call.name().name.to_string(),
+                args: self.lower_args(func_call)?,
+            },
+            Statement::Assignment(assignment) => self.lower_assignment(assignment)?,
+            Statement::Return(return_) => {
+                let value = match (self.return_type, return_.value()) {
+                    (ast::TypeOrVoid::Type(return_type), Some(value)) => Some(self.lower_value(value, return_type)?),
+                    _ => None,
+                };
+                InstrKind::Return { value }
+            }
+            Statement::If(if_) => self.lower_if(if_)?,
+            Statement::While(while_) => InstrKind::Loop {
+                condition: self.lower_value(while_.condition(), ast::Type::Bool)?,
+                body: self.lower_block(while_.body().stmts())?,
+                step: Block::default(),
+            },
+            Statement::For(for_) => {
+                // the block keeps the variable declared in the header visible only in the loop
+                let mut outer = Block::default();
+                if let Some(init) = for_.init() {
+                    self.lower_stmt(init, &mut outer)?;
+                }
+                let loop_id = if outer.instrs.is_empty() { id } else { self.new_id() };
+                let condition = match for_.condition() {
+                    Some(condition) => self.lower_value(condition, ast::Type::Bool)?,
+                    None => Value::Const(Constant::Bool(true)),
+                };
+                let body = self.lower_block(for_.body().stmts())?;
+                let mut step = Block::default();
+                if let Some(stmt) = for_.step() {
+                    self.lower_stmt(stmt, &mut step)?;
+                }
+                let loop_ = InstrKind::Loop { condition, body, step };
+                if outer.instrs.is_empty() {
+                    loop_
+                } else {
+                    outer.instrs.push(Instr {
+                        id: loop_id,
+                        kind: loop_,
+                    });
+                    InstrKind::Block(outer)
+                }
+            }
+            Statement::ForEach(for_each) => self.lower_for_each(for_each)?,
+            Statement::Break(_) => InstrKind::Break,
+            Statement::Continue(_) => InstrKind::Continue,
+            Statement::Block(inner) => InstrKind::Block(self.lower_block(inner.stmts())?),
+        };
+        block.instrs.push(Instr { id, kind });
+        Ok(())
+    }
+
+    fn lower_if(&mut self, if_: &stmt::If) -> Result<InstrKind> {
+        let condition = self.lower_value(if_.condition(), ast::Type::Bool)?;
+        let then = self.lower_block(if_.then_block().stmts())?;
+        let otherwise = match if_.else_branch() {
+            Some(stmt::Else::If(else_if)) => {
+                let id = self.new_id();
+                let kind = self.lower_if(else_if)?;
+                Block {
+                    instrs: vec![Instr { id, kind }],
+                }
+            }
+            Some(stmt::Else::Block(block)) => self.lower_block(block.stmts())?,
+            None => Block::default(),
+        };
+        Ok(InstrKind::If {
+            condition,
+            then,
+            otherwise,
+        })
+    }
+
+    /// Lowers `for (T item : array)` to a loop over the indices of the array, which is evaluated once.
+    /// The array and the index are kept in hidden variables named after their numbers.
+    fn lower_for_each(&mut self, for_each: &stmt::ForEach) -> Result<InstrKind> {
+        let array_type = self.expr_type(for_each.iterable());
+        let array_id = self.new_id();
+        let array_decl = InstrKind::VarDecl {
+            name: format!("array_{}", array_id.0),
+            type_: self.lower_type(array_type),
+            value: self.lower_expr(for_each.iterable())?,
+        };
+        let index_id = self.new_id();
+        let index_decl = InstrKind::VarDecl {
+            name: format!("index_{}", index_id.0),
+            type_: Type::Int,
+            value: Value::Const(Constant::Integer {
+                value: 0,
+                type_: Type::Int,
+            }),
+        };
+        let array = Value::Place(Place::Var(array_id));
+        let index = Value::Place(Place::Var(index_id));
+
+        let loop_id = self.new_id();
+        let condition = Value::Binary {
+            op: BinaryOp::Less,
+            left: Box::new(index.clone()),
+            right: Box::new(Value::Len {
+                array: Box::new(array.clone()),
+            }),
+        };
+        let item_id = self.new_id();
+        let item_decl = InstrKind::VarDecl {
+            name: for_each.item().name.to_string(),
+            type_: self.lower_type(for_each.item_type()),
+            value: Value::Index {
+                array: Box::new(array),
+                index: Box::new(index.clone()),
+            },
+        };
+        self.places.insert(self.symbol(for_each.item()), Place::Var(item_id));
+        let mut body = Block {
+            instrs: vec![Instr {
+                id: item_id,
+                kind: item_decl,
+            }],
+        };
+        for stmt in for_each.body().stmts() {
+            self.lower_stmt(stmt, &mut body)?;
+        }
+        let increment = InstrKind::Assign {
+            target: Place::Var(index_id),
+            value: Value::Binary {
+                op: BinaryOp::Add,
+                left: Box::new(index),
+                right: Box::new(Value::Const(Constant::Integer {
+                    value: 1,
+                    type_: Type::Int,
+                })),
+            },
+        };
+        let step = Block {
+            instrs: vec![Instr {
+                id: self.new_id(),
+                kind: increment,
+            }],
+        };
+        let instrs = vec![
+            Instr {
+                id: array_id,
+                kind: array_decl,
+            },
+            Instr {
+                id: index_id,
+                kind: index_decl,
+            },
+            Instr {
+                id: loop_id,
+                kind: InstrKind::Loop { condition, body, step },
+            },
+        ];
+        Ok(InstrKind::Block(Block { instrs }))
+    }
+
+    /// Keeps the value in a hidden variable declared in `block`, so that it is evaluated once when
+    /// it is used several times. Variables and constants are used as they are.
+    fn evaluate_once(&mut self, value: Value, type_: ast::Type, name: &str, block: &mut Block) -> Value {
+        if matches!(value, Value::Place(_) | Value::Const(_)) {
+            return value;
+        }
+        let id = self.new_id();
+        let kind = InstrKind::VarDecl {
+            name: format!("{}_{}", name, id.0),
+            type_: self.lower_type(type_),
+            value,
+        };
+        block.instrs.push(Instr { id, kind });
+        Value::Place(Place::Var(id))
+    }
+
+    /// Compound assignments are lowered to plain ones: `x += 1` becomes `x = x + 1`. Parts of the
+    /// target which are read and written are evaluated once, so the assignment may become a block
+    /// declaring them first.
+    fn lower_assignment(&mut self, assignment: &stmt::Assignment) -> Result<InstrKind> {
+        let target_type = self.expr_type(assignment.target());
+        let mut value = self.lower_value(assignment.value(), target_type)?;
+        let mut block = Block::default();
+        match assignment.target().kind() {
+            ExprKind::Ident(ident) => {
+                let target = self.place(*ident);
+                if let Some(op) = assignment.op().binary_op() {
+                    value = Value::Binary {
+                        op,
+                        left: Box::new(Value::Place(target)),
+                        right: Box::new(value),
+                    };
+                }
+                Ok(InstrKind::Assign { target, value })
+            }
+            ExprKind::Field { object, field } => {
+                let field = self.field_index(object, field.name);
+                let object_type = self.expr_type(object);
+                let mut object = self.lower_expr(object)?;
+                if let Some(op) = assignment.op().binary_op() {
+                    object = self.evaluate_once(object, object_type, "object", &mut block);
+                    value = Value::Binary {
+                        op,
+                        left: Box::new(Value::Field {
+                            object: Box::new(object.clone()),
+                            field,
+                        }),
+                        right: Box::new(value),
+                    };
+                }
+                Ok(self.with_hidden_vars(block, InstrKind::SetField { object, field, value }))
+            }
+            ExprKind::Index { object, index } => {
+                let (array_type, index_type) = (self.expr_type(object), self.expr_type(index));
+                let mut array = self.lower_expr(object)?;
+                let mut index = self.lower_expr(index)?;
+                if let Some(op) = assignment.op().binary_op() {
+                    array = self.evaluate_once(array, array_type, "array", &mut block);
+                    index = self.evaluate_once(index, index_type, "index", &mut block);
+                    value = Value::Binary {
+                        op,
+                        left: Box::new(Value::Index {
+                            array: Box::new(array.clone()),
+                            index: Box::new(index.clone()),
+                        }),
+                        right: Box::new(value),
+                    };
+                }
+                Ok(self.with_hidden_vars(block, InstrKind::SetIndex { array, index, value }))
+            }
+            _ => unreachable!("the parser allows only variables, fields and array elements to be assigned"),
+        }
+    }
+
+    /// Puts the instruction after the hidden variables, if there are any.
+    fn with_hidden_vars(&mut self, mut block: Block, kind: InstrKind) -> InstrKind {
+        if block.instrs.is_empty() {
+            return kind;
+        }
+        let id = self.new_id();
+        block.instrs.push(Instr { id, kind });
+        InstrKind::Block(block)
+    }
+
+    /// Lowers the arguments of the call converted to the types of the params. Arguments of params
+    /// of any type keep their own type.
+    fn lower_args(&mut self, func_call: &stmt::FunctionCall) -> Result<Vec<Value>> {
+        let params: Vec<_> = match self.analysis.resolved_symbol(func_call.name()).map(|symbol| symbol.kind()) {
+            Some(SymbolKind::Function { params, .. }) => params.iter().copied().map(ParamType::Exact).collect(),
+            Some(SymbolKind::Class { fields, .. }) => fields.iter().map(|(_, type_)| ParamType::Exact(*type_)).collect(),
+            Some(SymbolKind::Builtin(builtin)) => {
+                self.used_builtins.insert(builtin.name);
+                builtin.param_types(func_call.args().len())
+            }
+            _ => unreachable!("`{}` is not resolved to a function", func_call.name().name),
+        };
+        let mut args = Vec::with_capacity(params.len());
+        let mut element = None;
+        for (arg, param) in func_call.args().iter().zip(params) {
+            let value = match param {
+                ParamType::Exact(param) => self.lower_value(arg, param)?,
+                ParamType::Array => {
+                    element = self.analysis.element(self.expr_type(arg));
+                    self.lower_expr(arg)?
+                }
+                ParamType::Element => {
+                    let element = element.expect("the array param comes before the element param");
+                    self.lower_value(arg, element)?
+                }
+                ParamType::Any => self.lower_expr(arg)?,
+            };
+            args.push(value);
+        }
+        Ok(args)
+    }
+
+    /// Lowers `expr` used where a value of `target` type is expected.
+    fn lower_value(&mut self, expr: &Expr, target: ast::Type) -> Result<Value> {
+        let value_type = self.expr_type(expr);
+        let value = self.lower_expr(expr)?;
+        if target == ast::Type::Dyn && value_type != ast::Type::Dyn {
+            return Ok(Value::ToDyn {
+                value: Box::new(value),
+                from: self.lower_type(value_type),
+            });
+        }
+        Ok(value)
+    }
+
+    fn lower_expr(&mut self, expr: &Expr) -> Result<Value> {
+        if let Some((literal, negative)) = as_constant(expr) {
+            let constant = lower_constant(literal, negative, self.expr_type(expr));
+            return Ok(Value::Const(constant));
+        }
+        let value = match expr.kind() {
+            ExprKind::Ident(ident) => Value::Place(self.place(*ident)),
+            ExprKind::Binary { op, left, right } => Value::Binary {
+                op: *op,
+                left: Box::new(self.lower_expr(left)?),
+                right: Box::new(self.lower_expr(right)?),
+            },
+            ExprKind::Unary { op, operand } => Value::Unary {
+                op: *op,
+                operand: Box::new(self.lower_expr(operand)?),
+            },
+            ExprKind::Call(func_call) => match self.class_ids.get(func_call.name().name) {
+                Some(&class) => Value::New {
+                    class,
+                    args: self.lower_args(func_call)?,
+                },
+                None => Value::Call {
+                    function: func_call.name().name.to_string(),
+                    args: self.lower_args(func_call)?,
+                },
+            },
+            ExprKind::Group(inner) => self.lower_expr(inner)?,
+            ExprKind::Conversion { target, value } => {
+                if *target == ast::Type::Dyn || self.expr_type(value) == *target {
+                    return self.lower_value(value, *target);
+                }
+                Value::Convert {
+                    value: Box::new(self.lower_expr(value)?),
+                    to: self.lower_type(*target),
+                }
+            }
+            ExprKind::Field { object, .. } if matches!(self.expr_type(object), ast::Type::Array { .. }) => Value::Len {
+                array: Box::new(self.lower_expr(object)?),
+            },
+            ExprKind::Field { object, field } => Value::Field {
+                field: self.field_index(object, field.name),
+                object: Box::new(self.lower_expr(object)?),
+            },
+            ExprKind::Index { object, index } => Value::Index {
+                array: Box::new(self.lower_expr(object)?),
+                index: Box::new(self.lower_expr(index)?),
+            },
+            ExprKind::Array(items) => {
+                let element = self
+                    .analysis
+                    .element(self.expr_type(expr))
+                    .expect("array literals have array types");
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.lower_value(item, element)?);
+                }
+                let Type::Array(array) = self.lower_type(self.expr_type(expr)) else {
+                    unreachable!("array types are lowered to arrays");
+                };
+                Value::Array { array, items: values }
+            }
+            ExprKind::Literal(_) => unreachable!("literals are constants"),
+        };
+        Ok(value)
+    }
+}
+
+/// Lowers literal implicitly cast to `type_`, which is already checked to be able to represent it.
+fn lower_constant(literal: &LiteralKind, negative: bool, type_: ast::Type) -> Constant {
+    let sign = if negative { -1 } else { 1 };
+    let constant = match (literal, type_) {
+        (_, ast::Type::Financial) => {
+            Constant::Financial(financial_units(literal, negative).expect("the checker makes sure that the value fits"))
+        }
+        (LiteralKind::Integer { value, .. }, ast::Type::Double) => Constant::Double((sign * *value as i128) as f64),
+        (LiteralKind::Integer { value, .. }, _) => Constant::Integer {
+            value: sign * *value as i128,
+            type_: lower_type(type_, &ClassIds::new(), &[], &mut Vec::new()),
+        },
+        (LiteralKind::Double(value), _) => Constant::Double(sign as f64 * value),
+        (LiteralKind::String(string), _) => Constant::String(string.clone()),
+        (LiteralKind::Rune(rune), _) => Constant::Rune(*rune),
+        (LiteralKind::Bool(value), _) => Constant::Bool(*value),
+        (LiteralKind::Decimal { .. }, _) => unreachable!("decimal literals are always `financial`"),
+    };
+    constant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lower_program;
+    use crate::ir::{Constant, InstrId, InstrKind, Place, Program, Type, Value};
+    use crate::lexer::Lexer;
+    use crate::parsing::ast::expr::BinaryOp;
+    use crate::parsing::build_ast;
+    use crate::sema;
+
+    fn lower(source: &str) -> Program {
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (analysis, problems) = sema::analyze(&ast);
+        assert!(problems.is_empty(), "{:?}", problems);
+        lower_program(&ast, &analysis).unwrap()
+    }
+
+    #[test]
+    fn refers_to_variables_by_their_declarations() {
+        let program = lower("void main() {\n    print(\"a\")\n}\n\nvoid count(int n) {\n    int x = n\n    x = 2\n}\n");
+        let count = program.function("count").unwrap();
+        let instrs = &count.body.as_ref().unwrap().instrs;
+        match &instrs[0].kind {
+            InstrKind::VarDecl { name, type_, value } => {
+                assert_eq!((name.as_str(), *type_), ("x", Type::Int));
+                assert!(matches!(value, Value::Place(Place::Param(0))), "{:?}", value);
+            }
+            kind => panic!("{:?}", kind),
+        }
+        match &instrs[1].kind {
+            InstrKind::Assign { target, .. } => assert_eq!(*target, Place::Var(instrs[0].id)),
+            kind => panic!("{:?}", kind),
+        }
+        assert_ne!(instrs[0].id, instrs[1].id);
+        // used builtins are declared after the functions of the program
+        assert!(program.functions.last().unwrap().is_builtin());
+    }
+
+    #[test]
+    fn expands_compound_assignments_with_typed_constants() {
+        let program = lower("void main() {\n    long x = 1\n    x += 2\n}\n");
+        let instrs = &program.function("main").unwrap().body.as_ref().unwrap().instrs;
+        let long = |value| {
+            Value::Const(Constant::Integer {
+                value,
+                type_: Type::Long,
+            })
+        };
+        match &instrs[1].kind {
+            InstrKind::Assign {
+                target: Place::Var(InstrId(0)),
+                value:
+                    Value::Binary {
+                        op: BinaryOp::Add,
+                        left,
+                        right,
+                    },
+            } => {
+                assert!(matches!(**left, Value::Place(Place::Var(InstrId(0)))), "{:?}", left);
+                assert_eq!(format!("{:?}", right), format!("{:?}", long(2)));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+}
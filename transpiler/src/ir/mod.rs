@@ -1 +1,334 @@
+mod error;
+mod lower;
+mod printer;
+mod reader;
+mod validate;
 
+pub use error::{IrError, LoweringError};
+pub use lower::lower_program;
+pub use printer::print_program;
+pub use reader::read_program;
+
+use crate::parsing::ast::expr::{BinaryOp, UnaryOp};
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Type {
+    Byte,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    Double,
+    Financial,
+    Bool,
+    String,
+    Rune,
+    Dyn,
+    /// Reference to an object of the class, or the object itself for value classes.
+    Class(ClassId),
+    /// Reference to a growable array.
+    Array(ArrayId),
+    /// Accepts values of every type, used only for params of builtins.
+    Any,
+}
+
+impl Type {
+    pub const fn is_integer(self) -> bool {
+        matches!(
+            self,
+            Type::Byte | Type::Short | Type::UShort | Type::Int | Type::UInt | Type::Long | Type::ULong
+        )
+    }
+}
+
+/// How a param is passed to a function.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Marker {
+    /// Value is only read by the function.
+    In,
+}
+
+#[derive(Clone, Debug)]
+pub struct Arg {
+    pub name: String,
+    pub type_: Type,
+    pub markers: Vec<Marker>,
+}
+
+/// Index of the class in [`Program::classes`].
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub struct ClassId(pub usize);
+
+/// Index of the element type of the array type in [`Program::arrays`].
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub struct ArrayId(pub usize);
+
+/// Returns the id of the array type with elements of `element`, adding the type if it is new.
+fn intern_array(arrays: &mut Vec<Type>, element: Type) -> ArrayId {
+    let index = arrays.iter().position(|type_| *type_ == element).unwrap_or_else(|| {
+        arrays.push(element);
+        arrays.len() - 1
+    });
+    ArrayId(index)
+}
+
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    pub type_: Type,
+}
+
+#[derive(Clone, Debug)]
+pub struct Class {
+    pub name: String,
+    /// Fields in the order of the constructor params.
+    pub fields: Vec<Field>,
+    pub docs: Vec<String>,
+    /// Objects of value classes are immutable and copied instead of being shared, they can be
+    /// compared, hashed and formatted.
+    pub value: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub args: Vec<Arg>,
+    /// `None` for `void` functions.
+    pub returns: Option<Type>,
+    /// `None` for builtins, which are provided by the backend.
+    pub body: Option<Block>,
+    pub docs: Vec<String>,
+}
+
+impl Function {
+    pub fn is_builtin(&self) -> bool {
+        self.body.is_none()
+    }
+
+    /// Type of the variable declared by the instruction, `None` if it doesn't declare one.
+    pub fn var_type(&self, id: InstrId) -> Option<Type> {
+        self.body.as_ref().and_then(|body| body.var_type(id))
+    }
+}
+
+/// Typed intermediate representation of the program, which backends generate code from.
+///
+/// Every instruction of a function body has a number, unique in the function. Variables are
+/// referred to by the number of the `VarDecl` which declared them, function params by their
+/// index. Control flow stays structured: `if` and loops contain nested blocks.
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    pub classes: Vec<Class>,
+    /// Element types of the array types used by the program.
+    pub arrays: Vec<Type>,
+    pub functions: Vec<Function>,
+}
+
+impl Program {
+    pub fn class(&self, id: ClassId) -> &Class {
+        &self.classes[id.0]
+    }
+
+    pub fn array_element(&self, id: ArrayId) -> Type {
+        self.arrays[id.0]
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Static type of the value in the body of `function`, `None` for calls of `void` functions.
+    pub fn value_type(&self, function: &Function, value: &Value) -> Option<Type> {
+        match value {
+            Value::Const(constant) => Some(constant.type_()),
+            Value::Place(Place::Var(id)) => function.var_type(*id),
+            Value::Place(Place::Param(index)) => Some(function.args[*index].type_),
+            Value::Binary { op, left, .. } => match op {
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => self.value_type(function, left),
+                _ => Some(Type::Bool),
+            },
+            Value::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => self.value_type(function, operand),
+            Value::Unary { op: UnaryOp::Not, .. } => Some(Type::Bool),
+            Value::Call { function: name, .. } => self.function(name).and_then(|function| function.returns),
+            Value::Convert { to, .. } => Some(*to),
+            Value::ToDyn { .. } => Some(Type::Dyn),
+            Value::New { class, .. } => Some(Type::Class(*class)),
+            Value::Field { object, field } => match self.value_type(function, object)? {
+                Type::Class(class) => Some(self.class(class).fields[*field].type_),
+                _ => None,
+            },
+            Value::Array { array, .. } => Some(Type::Array(*array)),
+            Value::Index { array, .. } => match self.value_type(function, array)? {
+                Type::Array(array) => Some(self.array_element(array)),
+                _ => None,
+            },
+            Value::Len { .. } => Some(Type::Int),
+        }
+    }
+}
+
+/// Number of an instruction in the function body.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
+pub struct InstrId(pub usize);
+
+#[derive(Clone, Debug, Default)]
+pub struct Block {
+    pub instrs: Vec<Instr>,
+}
+
+impl Block {
+    fn var_type(&self, id: InstrId) -> Option<Type> {
+        self.instrs.iter().find_map(|instr| match &instr.kind {
+            InstrKind::VarDecl { type_, .. } if instr.id == id => Some(*type_),
+            InstrKind::If { then, otherwise, .. } => then.var_type(id).or_else(|| otherwise.var_type(id)),
+            InstrKind::Loop { body, step, .. } => body.var_type(id).or_else(|| step.var_type(id)),
+            InstrKind::Block(block) => block.var_type(id),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Instr {
+    pub id: InstrId,
+    pub kind: InstrKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum InstrKind {
+    VarDecl {
+        name: String,
+        type_: Type,
+        value: Value,
+    },
+    Call {
+        function: String,
+        args: Vec<Value>,
+    },
+    Assign {
+        target: Place,
+        value: Value,
+    },
+    /// Assigns the field with the index `field` of the object.
+    SetField {
+        object: Value,
+        field: usize,
+        value: Value,
+    },
+    /// Assigns the element of the array, aborting if the index is out of bounds.
+    SetIndex {
+        array: Value,
+        index: Value,
+        value: Value,
+    },
+    Return {
+        value: Option<Value>,
+    },
+    If {
+        condition: Value,
+        then: Block,
+        otherwise: Block,
+    },
+    /// Runs `body` and then `step` while `condition` holds. `continue` goes to `step`.
+    Loop {
+        condition: Value,
+        body: Block,
+        step: Block,
+    },
+    Break,
+    Continue,
+    Block(Block),
+}
+
+/// Location which can be assigned to.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Place {
+    Var(InstrId),
+    Param(usize),
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Const(Constant),
+    Place(Place),
+    Binary {
+        op: BinaryOp,
+        left: Box<Value>,
+        right: Box<Value>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Value>,
+    },
+    Call {
+        function: String,
+        args: Vec<Value>,
+    },
+    /// Conversion between number types and runes.
+    Convert {
+        value: Box<Value>,
+        to: Type,
+    },
+    /// Wraps the value of the static type `from` into `dyn`.
+    ToDyn {
+        value: Box<Value>,
+        from: Type,
+    },
+    /// Constructs an object of the class from the values of all of its fields.
+    New {
+        class: ClassId,
+        args: Vec<Value>,
+    },
+    /// Reads the field with the index `field` of the object.
+    Field {
+        object: Box<Value>,
+        field: usize,
+    },
+    /// Creates a new array of the items.
+    Array {
+        array: ArrayId,
+        items: Vec<Value>,
+    },
+    /// Reads the element of the array, aborting if the index is out of bounds.
+    Index {
+        array: Box<Value>,
+        index: Box<Value>,
+    },
+    /// Number of the elements of the array, as `int`.
+    Len {
+        array: Box<Value>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constant {
+    /// Value of any integer type.
+    Integer {
+        value: i128,
+        type_: Type,
+    },
+    Double(f64),
+    /// Value in units of `FINANCIAL_SCALE`.
+    Financial(i64),
+    String(String),
+    Rune(char),
+    Bool(bool),
+}
+
+impl Constant {
+    pub fn type_(&self) -> Type {
+        match self {
+            Constant::Integer { type_, .. } => *type_,
+            Constant::Double(_) => Type::Double,
+            Constant::Financial(_) => Type::Financial,
+            Constant::String(_) => Type::String,
+            Constant::Rune(_) => Type::Rune,
+            Constant::Bool(_) => Type::Bool,
+        }
+    }
+}
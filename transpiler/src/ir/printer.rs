@@ -0,0 +1,365 @@
+use super::{Arg, Block, Class, Constant, Function, InstrKind, Marker, Place, Program, Type, Value};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+const INDENT_WIDTH: usize = 4;
+
+/// Name of the type, classes are named by their declarations in `classes`, arrays are named
+/// `[]T` after their element types in `arrays`.
+pub fn type_name<'p>(type_: Type, classes: &'p [Class], arrays: &[Type]) -> Cow<'p, str> {
+    let name = match type_ {
+        Type::Byte => "byte",
+        Type::Short => "short",
+        Type::UShort => "ushort",
+        Type::Int => "int",
+        Type::UInt => "uint",
+        Type::Long => "long",
+        Type::ULong => "ulong",
+        Type::Double => "double",
+        Type::Financial => "financial",
+        Type::Bool => "bool",
+        Type::String => "string",
+        Type::Rune => "rune",
+        Type::Dyn => "dyn",
+        Type::Class(id) => &classes[id.0].name,
+        Type::Array(id) => return Cow::Owned(format!("[]{}", type_name(arrays[id.0], classes, arrays))),
+        Type::Any => "AnyType",
+    };
+    Cow::Borrowed(name)
+}
+
+/// Name of the constant kind of an integer type, e.g. `IntValue`.
+pub const fn integer_constant_name(type_: Type) -> &'static str {
+    match type_ {
+        Type::Byte => "ByteValue",
+        Type::Short => "ShortValue",
+        Type::UShort => "UShortValue",
+        Type::Int => "IntValue",
+        Type::UInt => "UIntValue",
+        Type::Long => "LongValue",
+        Type::ULong => "ULongValue",
+        _ => panic!("type is not an integer type"),
+    }
+}
+
+/// Prints the program in the textual IR syntax, which [`super::read_program`] reads back.
+pub fn print_program(program: &Program) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        classes: &program.classes,
+        arrays: &program.arrays,
+    };
+    for class in &program.classes {
+        printer.print_class(class);
+        printer.out.push('\n');
+    }
+    for (index, function) in program.functions.iter().enumerate() {
+        if index != 0 {
+            printer.out.push('\n');
+        }
+        printer.print_function(function);
+    }
+    printer.out
+}
+
+struct Printer<'p> {
+    out: String,
+    classes: &'p [Class],
+    arrays: &'p [Type],
+}
+
+impl Printer<'_> {
+    fn indent(&mut self, depth: usize) {
+        self.out.extend((0..depth * INDENT_WIDTH).map(|_| ' '));
+    }
+
+    fn print_docs(&mut self, docs: &[String]) {
+        if docs.is_empty() {
+            return;
+        }
+        self.indent(1);
+        self.out.push_str("docs: (");
+        for (index, line) in docs.iter().enumerate() {
+            if index != 0 {
+                self.out.push_str(", ");
+            }
+            write!(self.out, "{:?}", line).unwrap();
+        }
+        self.out.push_str(");\n");
+    }
+
+    fn print_class(&mut self, class: &Class) {
+        self.out.push_str(if class.value {
+            "declare value class "
+        } else {
+            "declare class "
+        });
+        self.out.push_str(&class.name);
+        self.out.push_str(" {\n");
+        self.print_docs(&class.docs);
+
+        self.indent(1);
+        self.out.push_str("fields: (");
+        if !class.fields.is_empty() {
+            self.out.push('\n');
+            for (index, field) in class.fields.iter().enumerate() {
+                self.indent(2);
+                let type_name = type_name(field.type_, self.classes, self.arrays);
+                write!(self.out, "Field {{ name: {:?}; type: {} }}", field.name, type_name).unwrap();
+                if index + 1 != class.fields.len() {
+                    self.out.push(',');
+                }
+                self.out.push('\n');
+            }
+            self.indent(1);
+        }
+        self.out.push_str(")\n}\n");
+    }
+
+    fn print_function(&mut self, function: &Function) {
+        self.out.push_str("declare function ");
+        self.out.push_str(&function.name);
+        self.out.push_str(" {\n");
+        self.print_docs(&function.docs);
+
+        self.indent(1);
+        self.out.push_str("args: (");
+        if !function.args.is_empty() {
+            self.out.push('\n');
+            for (index, arg) in function.args.iter().enumerate() {
+                self.indent(2);
+                self.print_arg(arg);
+                if index + 1 != function.args.len() {
+                    self.out.push(',');
+                }
+                self.out.push('\n');
+            }
+            self.indent(1);
+        }
+        self.out.push_str(");\n");
+
+        self.indent(1);
+        self.out.push_str("returns: ");
+        let returns = function
+            .returns
+            .map_or(Cow::Borrowed("void"), |type_| type_name(type_, self.classes, self.arrays));
+        self.out.push_str(&returns);
+        self.out.push_str(";\n");
+
+        self.indent(1);
+        match &function.body {
+            Some(body) => {
+                self.out.push_str("body: ");
+                self.print_block(body, 1);
+            }
+            None => self.out.push_str("builtin: true"),
+        }
+        self.out.push_str("\n}\n");
+    }
+
+    fn print_arg(&mut self, arg: &Arg) {
+        write!(
+            self.out,
+            "Arg {{ name: {:?}; type: {}; marker: (",
+            arg.name,
+            type_name(arg.type_, self.classes, self.arrays)
+        )
+        .unwrap();
+        for (index, marker) in arg.markers.iter().enumerate() {
+            self.out.push_str(if index == 0 { " " } else { ", " });
+            match marker {
+                Marker::In => self.out.push_str("In"),
+            }
+        }
+        self.out.push_str(" ) }");
+    }
+
+    /// Prints block whose closing brace is indented to `depth`.
+    fn print_block(&mut self, block: &Block, depth: usize) {
+        if block.instrs.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        for (index, instr) in block.instrs.iter().enumerate() {
+            self.indent(depth + 1);
+            write!(self.out, "{}: ", instr.id.0).unwrap();
+            self.print_instr(&instr.kind, depth + 1);
+            if index + 1 != block.instrs.len() {
+                self.out.push(';');
+            }
+            self.out.push('\n');
+        }
+        self.indent(depth);
+        self.out.push('}');
+    }
+
+    fn print_instr(&mut self, kind: &InstrKind, depth: usize) {
+        match kind {
+            InstrKind::VarDecl { name, type_, value } => {
+                let type_name = type_name(*type_, self.classes, self.arrays);
+                write!(self.out, "VarDecl {{ name: {:?}; type: {}; value: ", name, type_name).unwrap();
+                self.print_value(value);
+                self.out.push_str(" }");
+            }
+            InstrKind::Call { function, args } => self.print_call(function, args),
+            InstrKind::Assign { target, value } => {
+                self.out.push_str("Assign { target: ");
+                self.print_place(*target);
+                self.out.push_str("; value: ");
+                self.print_value(value);
+                self.out.push_str(" }");
+            }
+            InstrKind::SetField { object, field, value } => {
+                self.out.push_str("SetField { object: ");
+                self.print_value(object);
+                write!(self.out, "; field: {}; value: ", field).unwrap();
+                self.print_value(value);
+                self.out.push_str(" }");
+            }
+            InstrKind::SetIndex { array, index, value } => {
+                self.out.push_str("SetIndex { array: ");
+                self.print_value(array);
+                self.out.push_str("; index: ");
+                self.print_value(index);
+                self.out.push_str("; value: ");
+                self.print_value(value);
+                self.out.push_str(" }");
+            }
+            InstrKind::Return { value: None } => self.out.push_str("Return"),
+            InstrKind::Return { value: Some(value) } => {
+                self.out.push_str("Return { value: ");
+                self.print_value(value);
+                self.out.push_str(" }");
+            }
+            InstrKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.out.push_str("If { condition: ");
+                self.print_value(condition);
+                self.out.push_str("; then: ");
+                self.print_block(then, depth);
+                self.out.push_str("; otherwise: ");
+                self.print_block(otherwise, depth);
+                self.out.push_str(" }");
+            }
+            InstrKind::Loop { condition, body, step } => {
+                self.out.push_str("Loop { condition: ");
+                self.print_value(condition);
+                self.out.push_str("; body: ");
+                self.print_block(body, depth);
+                self.out.push_str("; step: ");
+                self.print_block(step, depth);
+                self.out.push_str(" }");
+            }
+            InstrKind::Break => self.out.push_str("Break"),
+            InstrKind::Continue => self.out.push_str("Continue"),
+            InstrKind::Block(block) => {
+                self.out.push_str("Block ");
+                self.print_block(block, depth);
+            }
+        }
+    }
+
+    fn print_call(&mut self, function: &str, args: &[Value]) {
+        write!(self.out, "Call {{ function: {}; args: (", function).unwrap();
+        for (index, arg) in args.iter().enumerate() {
+            if index != 0 {
+                self.out.push_str(", ");
+            }
+            self.print_value(arg);
+        }
+        self.out.push_str(") }");
+    }
+
+    fn print_place(&mut self, place: Place) {
+        match place {
+            Place::Var(id) => write!(self.out, "{}", id.0).unwrap(),
+            Place::Param(index) => write!(self.out, "Param({})", index).unwrap(),
+        }
+    }
+
+    fn print_value(&mut self, value: &Value) {
+        match value {
+            Value::Const(constant) => self.print_constant(constant),
+            Value::Place(place) => self.print_place(*place),
+            Value::Binary { op, left, right } => {
+                write!(self.out, "Binary {{ op: {}; left: ", op.symbol()).unwrap();
+                self.print_value(left);
+                self.out.push_str("; right: ");
+                self.print_value(right);
+                self.out.push_str(" }");
+            }
+            Value::Unary { op, operand } => {
+                write!(self.out, "Unary {{ op: {}; operand: ", op.symbol()).unwrap();
+                self.print_value(operand);
+                self.out.push_str(" }");
+            }
+            Value::Call { function, args } => self.print_call(function, args),
+            Value::Convert { value, to } => {
+                self.out.push_str("Convert { value: ");
+                self.print_value(value);
+                write!(self.out, "; to: {} }}", type_name(*to, self.classes, self.arrays)).unwrap();
+            }
+            Value::ToDyn { value, from } => {
+                self.out.push_str("ToDyn { value: ");
+                self.print_value(value);
+                write!(self.out, "; from: {} }}", type_name(*from, self.classes, self.arrays)).unwrap();
+            }
+            Value::New { class, args } => {
+                write!(self.out, "New {{ class: {}; args: (", self.classes[class.0].name).unwrap();
+                for (index, arg) in args.iter().enumerate() {
+                    if index != 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.print_value(arg);
+                }
+                self.out.push_str(") }");
+            }
+            Value::Field { object, field } => {
+                self.out.push_str("Field { object: ");
+                self.print_value(object);
+                write!(self.out, "; field: {} }}", field).unwrap();
+            }
+            Value::Array { array, items } => {
+                let type_name = type_name(Type::Array(*array), self.classes, self.arrays);
+                write!(self.out, "Array {{ type: {}; items: (", type_name).unwrap();
+                for (index, item) in items.iter().enumerate() {
+                    if index != 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.print_value(item);
+                }
+                self.out.push_str(") }");
+            }
+            Value::Index { array, index } => {
+                self.out.push_str("Index { array: ");
+                self.print_value(array);
+                self.out.push_str("; index: ");
+                self.print_value(index);
+                self.out.push_str(" }");
+            }
+            Value::Len { array } => {
+                self.out.push_str("Len { array: ");
+                self.print_value(array);
+                self.out.push_str(" }");
+            }
+        }
+    }
+
+    fn print_constant(&mut self, constant: &Constant) {
+        // `{:?}` escapes strings and runes the way the language does and prints the shortest
+        // representation of doubles which is parsed back to the same value
+        match constant {
+            Constant::Integer { value, type_ } => write!(self.out, "{}({})", integer_constant_name(*type_), value),
+            Constant::Double(value) => write!(self.out, "DoubleValue({:?})", value),
+            Constant::Financial(units) => write!(self.out, "FinancialValue({})", units),
+            Constant::String(string) => write!(self.out, "StringValue({:?})", string),
+            Constant::Rune(rune) => write!(self.out, "RuneValue({:?})", rune),
+            Constant::Bool(value) => write!(self.out, "BoolValue({})", value),
+        }
+        .unwrap();
+    }
+}
@@ -0,0 +1,937 @@
+use super::printer::{integer_constant_name, type_name};
+use super::validate::{validate_program, SourceMap};
+use super::{
+    intern_array, Arg, Block, Class, ClassId, Constant, Field, Function, Instr, InstrId, InstrKind, Marker, Place, Program, Type,
+    Value,
+};
+use crate::builtins;
+use crate::error::{CompileError, Result};
+use crate::lexer::{Lexer, Span, TokenKind};
+use crate::parsing::ast::expr::{BinaryOp, UnaryOp};
+use crate::parsing::ast::LiteralKind;
+use crate::parsing::seq::TokenSeq;
+use crate::parsing::ParsingError;
+use std::collections::{HashMap, HashSet};
+
+const TYPES: [Type; 14] = [
+    Type::Byte,
+    Type::Short,
+    Type::UShort,
+    Type::Int,
+    Type::UInt,
+    Type::Long,
+    Type::ULong,
+    Type::Double,
+    Type::Financial,
+    Type::Bool,
+    Type::String,
+    Type::Rune,
+    Type::Dyn,
+    Type::Any,
+];
+
+/// Reads the program in the textual IR syntax printed by [`super::print_program`], returning it
+/// together with the problems found by validating it.
+pub fn read_program(source: &str) -> Result<(Program, Vec<CompileError>)> {
+    let (classes, class_spans) = declared_classes(source)?;
+    let mut reader = Reader {
+        seq: TokenSeq::new(Lexer::new(source)),
+        vars: HashSet::new(),
+        classes,
+        arrays: Vec::new(),
+        source_map: SourceMap {
+            classes: class_spans,
+            ..SourceMap::default()
+        },
+    };
+    let mut functions = Vec::new();
+    let mut class_count = 0;
+    while reader.seq.solid_token()?.kind() != TokenKind::Eof {
+        if reader.seq.peek_solid_token(1)?.string() == "function" {
+            functions.push(reader.read_function()?);
+        } else {
+            // `declare class` or `declare value class`
+            let (fields, docs) = reader.read_class()?;
+            let class = &mut reader.classes[class_count];
+            class.fields = fields;
+            class.docs = docs;
+            class_count += 1;
+        }
+    }
+    let program = Program {
+        classes: reader.classes,
+        arrays: reader.arrays,
+        functions,
+    };
+    let problems = validate_program(&program, &reader.source_map);
+    Ok((program, problems))
+}
+
+/// Classes declared in the source with their names only, so that types can refer to classes
+/// which are declared later in the file, and the spans of the names.
+fn declared_classes(source: &str) -> Result<(Vec<Class>, Vec<Span>)> {
+    let mut seq = TokenSeq::new(Lexer::new(source));
+    let mut classes = Vec::new();
+    let mut spans = Vec::new();
+    loop {
+        let token = seq.next_solid_token()?;
+        match token.kind() {
+            TokenKind::Eof => return Ok((classes, spans)),
+            TokenKind::Ident if token.string() == "declare" => {
+                let value = seq.solid_token()?.string() == "value";
+                if seq.peek_solid_token(value as usize)?.kind() != TokenKind::KeywordClass {
+                    continue;
+                }
+                seq.next_solid_token()?;
+                if value {
+                    seq.next_solid_token()?;
+                }
+                let name = seq.expect_ident()?;
+                spans.push(name.span);
+                classes.push(Class {
+                    name: name.name.to_string(),
+                    fields: Vec::new(),
+                    docs: Vec::new(),
+                    value,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Reader<'c> {
+    seq: TokenSeq<'c>,
+    /// Numbers of the variable declarations read so far in the current function.
+    vars: HashSet<InstrId>,
+    classes: Vec<Class>,
+    /// Element types of the array types read so far.
+    arrays: Vec<Type>,
+    source_map: SourceMap,
+}
+
+impl<'c> Reader<'c> {
+    /// Checks if the current token is the word, which may also be a keyword, e.g. `class`.
+    fn is_word(&mut self, word: &str) -> Result<bool> {
+        Ok(self.seq.solid_token()?.string() == word)
+    }
+
+    fn expect_word(&mut self, word: &'static str) -> Result<()> {
+        let token = self.seq.solid_token()?;
+        if !self.is_word(word)? {
+            return Err(ParsingError::expected_word(token, word).into());
+        }
+        self.seq.go_next();
+        Ok(())
+    }
+
+    /// Reads the name of a field followed by a colon: `name:`.
+    fn expect_field(&mut self, name: &'static str) -> Result<()> {
+        self.expect_word(name)?;
+        self.seq.expect_token(TokenKind::Colon)?;
+        Ok(())
+    }
+
+    /// Reads `{` followed by the name of the first field.
+    fn expect_fields_start(&mut self, first: &'static str) -> Result<()> {
+        self.seq.expect_token(TokenKind::BraceOpen)?;
+        self.expect_field(first)
+    }
+
+    /// Reads `;` followed by the name of the next field.
+    fn expect_next_field(&mut self, name: &'static str) -> Result<()> {
+        self.seq.expect_token(TokenKind::Semicolon)?;
+        self.expect_field(name)
+    }
+
+    fn expect_fields_end(&mut self) -> Result<()> {
+        self.seq.expect_token(TokenKind::BraceClose)?;
+        Ok(())
+    }
+
+    /// Reads comma separated items in parens.
+    fn read_list<T>(&mut self, mut read_item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.seq.expect_token(TokenKind::ParenOpen)?;
+        let mut items = Vec::new();
+        if self.seq.solid_token()?.kind() != TokenKind::ParenClose {
+            loop {
+                items.push(read_item(self)?);
+                if self.seq.solid_token()?.kind() != TokenKind::Sep {
+                    break;
+                }
+                self.seq.go_next();
+            }
+        }
+        self.seq.expect_token(TokenKind::ParenClose)?;
+        Ok(items)
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let token = self.seq.solid_token()?;
+        match self.seq.expect_literal()?.kind() {
+            LiteralKind::String(string) => Ok(string.clone()),
+            _ => Err(ParsingError::expected(token, "a string").into()),
+        }
+    }
+
+    fn read_integer(&mut self) -> Result<i128> {
+        let negative = self.seq.solid_token()?.kind() == TokenKind::Minus;
+        if negative {
+            self.seq.go_next();
+        }
+        let token = self.seq.solid_token()?;
+        match self.seq.expect_literal()?.kind() {
+            LiteralKind::Integer { value, suffix: None } => Ok(if negative { -(*value as i128) } else { *value as i128 }),
+            _ => Err(ParsingError::expected(token, "an integer").into()),
+        }
+    }
+
+    fn read_index(&mut self) -> Result<usize> {
+        let token = self.seq.solid_token()?;
+        usize::try_from(self.read_integer()?).map_err(|_| ParsingError::expected(token, "an index").into())
+    }
+
+    fn read_type(&mut self) -> Result<Type> {
+        let token = self.seq.solid_token()?;
+        if token.kind() == TokenKind::BracketOpen {
+            self.seq.go_next();
+            self.seq.expect_token(TokenKind::BracketClose)?;
+            let element = self.read_type()?;
+            return Ok(Type::Array(intern_array(&mut self.arrays, element)));
+        }
+        let classes = (0..self.classes.len()).map(|index| Type::Class(ClassId(index)));
+        match TYPES
+            .into_iter()
+            .chain(classes)
+            .find(|type_| type_name(*type_, &self.classes, &self.arrays) == token.string())
+        {
+            Some(type_) => {
+                self.seq.go_next();
+                Ok(type_)
+            }
+            None => Err(ParsingError::expected(token, "a type").into()),
+        }
+    }
+
+    fn read_docs(&mut self) -> Result<Vec<String>> {
+        let mut docs = Vec::new();
+        if self.is_word("docs")? {
+            self.expect_field("docs")?;
+            docs = self.read_list(|reader| reader.read_string())?;
+            self.seq.expect_token(TokenKind::Semicolon)?;
+        }
+        Ok(docs)
+    }
+
+    /// Reads the class declaration, whose name is already known from [`declared_classes`].
+    fn read_class(&mut self) -> Result<(Vec<Field>, Vec<String>)> {
+        self.expect_word("declare")?;
+        if self.is_word("value")? {
+            self.seq.go_next();
+        }
+        self.expect_word("class")?;
+        self.seq.expect_ident()?;
+        self.seq.expect_token(TokenKind::BraceOpen)?;
+        let docs = self.read_docs()?;
+        self.expect_field("fields")?;
+        let fields = self.read_list(|reader| {
+            reader.expect_word("Field")?;
+            reader.expect_fields_start("name")?;
+            let name = reader.read_string()?;
+            reader.expect_next_field("type")?;
+            let type_ = reader.read_type()?;
+            reader.expect_fields_end()?;
+            Ok(Field { name, type_ })
+        })?;
+        self.expect_fields_end()?;
+        Ok((fields, docs))
+    }
+
+    fn read_function(&mut self) -> Result<Function> {
+        self.expect_word("declare")?;
+        self.expect_word("function")?;
+        let name_ident = self.seq.expect_ident()?;
+        let name = name_ident.name.to_string();
+        self.seq.expect_token(TokenKind::BraceOpen)?;
+        self.vars.clear();
+        self.source_map.functions.push(name_ident.span);
+        self.source_map.instrs.push(HashMap::new());
+
+        let docs = self.read_docs()?;
+        self.expect_field("args")?;
+        let args = self.read_list(|reader| reader.read_arg())?;
+        self.expect_next_field("returns")?;
+        let returns = if self.seq.solid_token()?.kind() == TokenKind::KeywordVoid {
+            self.seq.go_next();
+            None
+        } else {
+            Some(self.read_type()?)
+        };
+        self.seq.expect_token(TokenKind::Semicolon)?;
+        let body = if self.is_word("builtin")? {
+            self.expect_field("builtin")?;
+            self.seq.expect_token(TokenKind::KeywordTrue)?;
+            if builtins::find(&name).is_none() {
+                return Err(ParsingError::invalid("function is not in the builtin registry", name_ident.span).into());
+            }
+            None
+        } else {
+            self.expect_field("body")?;
+            Some(self.read_block()?)
+        };
+        self.expect_fields_end()?;
+        Ok(Function {
+            name,
+            args,
+            returns,
+            body,
+            docs,
+        })
+    }
+
+    fn read_arg(&mut self) -> Result<Arg> {
+        self.expect_word("Arg")?;
+        self.expect_fields_start("name")?;
+        let name = self.read_string()?;
+        self.expect_next_field("type")?;
+        let type_ = self.read_type()?;
+        self.expect_next_field("marker")?;
+        let markers = self.read_list(|reader| {
+            reader.expect_word("In")?;
+            Ok(Marker::In)
+        })?;
+        self.expect_fields_end()?;
+        Ok(Arg { name, type_, markers })
+    }
+
+    fn read_block(&mut self) -> Result<Block> {
+        self.seq.expect_token(TokenKind::BraceOpen)?;
+        let mut instrs = Vec::new();
+        if self.seq.solid_token()?.kind() != TokenKind::BraceClose {
+            loop {
+                instrs.push(self.read_instr()?);
+                if self.seq.solid_token()?.kind() != TokenKind::Semicolon {
+                    break;
+                }
+                self.seq.go_next();
+            }
+        }
+        self.seq.expect_token(TokenKind::BraceClose)?;
+        Ok(Block { instrs })
+    }
+
+    fn read_instr(&mut self) -> Result<Instr> {
+        let start = self.seq.solid_token()?.span();
+        let id = InstrId(self.read_index()?);
+        self.seq.expect_token(TokenKind::Colon)?;
+        let token = self.seq.solid_token()?;
+        // problems of the instruction point to its number and kind
+        let span = start.to(token.span());
+        self.source_map
+            .instrs
+            .last_mut()
+            .expect("instructions are read only inside of functions")
+            .insert(id, span);
+        let word = if token.kind() == TokenKind::Ident {
+            token.string()
+        } else {
+            ""
+        };
+        self.seq.go_next();
+        let kind = match word {
+            "VarDecl" => {
+                self.expect_fields_start("name")?;
+                let name = self.read_string()?;
+                self.seq.expect_token(TokenKind::Semicolon)?;
+                // the type may be omitted when the value is a constant
+                let mut type_ = None;
+                if self.is_word("type")? {
+                    self.expect_field("type")?;
+                    type_ = Some(self.read_type()?);
+                    self.seq.expect_token(TokenKind::Semicolon)?;
+                }
+                self.expect_field("value")?;
+                let value_token = self.seq.solid_token()?;
+                let value = self.read_value()?;
+                let type_ = match (type_, &value) {
+                    (Some(type_), _) => type_,
+                    (None, Value::Const(constant)) => constant.type_(),
+                    (None, _) => {
+                        let message = "type of the variable must be written, as its value is not a constant";
+                        return Err(ParsingError::invalid(message, value_token.span()).into());
+                    }
+                };
+                self.expect_fields_end()?;
+                self.vars.insert(id);
+                InstrKind::VarDecl { name, type_, value }
+            }
+            "Call" => {
+                let (function, args) = self.read_call()?;
+                InstrKind::Call { function, args }
+            }
+            "Assign" => {
+                self.expect_fields_start("target")?;
+                let target = self.read_place()?;
+                self.expect_next_field("value")?;
+                let value = self.read_value()?;
+                self.expect_fields_end()?;
+                InstrKind::Assign { target, value }
+            }
+            "SetField" => {
+                self.expect_fields_start("object")?;
+                let object = self.read_value()?;
+                self.expect_next_field("field")?;
+                let field = self.read_index()?;
+                self.expect_next_field("value")?;
+                let value = self.read_value()?;
+                self.expect_fields_end()?;
+                InstrKind::SetField { object, field, value }
+            }
+            "SetIndex" => {
+                self.expect_fields_start("array")?;
+                let array = self.read_value()?;
+                self.expect_next_field("index")?;
+                let index = self.read_value()?;
+                self.expect_next_field("value")?;
+                let value = self.read_value()?;
+                self.expect_fields_end()?;
+                InstrKind::SetIndex { array, index, value }
+            }
+            "Return" => {
+                let mut value = None;
+                if self.seq.solid_token()?.kind() == TokenKind::BraceOpen {
+                    self.expect_fields_start("value")?;
+                    value = Some(self.read_value()?);
+                    self.expect_fields_end()?;
+                }
+                InstrKind::Return { value }
+            }
+            "If" => {
+                self.expect_fields_start("condition")?;
+                let condition = self.read_value()?;
+                self.expect_next_field("then")?;
+                let then = self.read_block()?;
+                self.expect_next_field("otherwise")?;
+                let otherwise = self.read_block()?;
+                self.expect_fields_end()?;
+                InstrKind::If {
+                    condition,
+                    then,
+                    otherwise,
+                }
+            }
+            "Loop" => {
+                self.expect_fields_start("condition")?;
+                let condition = self.read_value()?;
+                self.expect_next_field("body")?;
+                let body = self.read_block()?;
+                self.expect_next_field("step")?;
+                let step = self.read_block()?;
+                self.expect_fields_end()?;
+                InstrKind::Loop { condition, body, step }
+            }
+            "Break" => InstrKind::Break,
+            "Continue" => InstrKind::Continue,
+            "Block" => InstrKind::Block(self.read_block()?),
+            _ => return Err(ParsingError::expected(token, "an instruction").into()),
+        };
+        Ok(Instr { id, kind })
+    }
+
+    /// Reads the fields of `Call` after its name.
+    fn read_call(&mut self) -> Result<(String, Vec<Value>)> {
+        self.expect_fields_start("function")?;
+        let function = self.seq.expect_ident()?.name.to_string();
+        self.expect_next_field("args")?;
+        let args = self.read_list(|reader| reader.read_value())?;
+        self.expect_fields_end()?;
+        Ok((function, args))
+    }
+
+    fn read_place(&mut self) -> Result<Place> {
+        let token = self.seq.solid_token()?;
+        if self.is_word("Param")? {
+            self.seq.go_next();
+            self.seq.expect_token(TokenKind::ParenOpen)?;
+            let index = self.read_index()?;
+            self.seq.expect_token(TokenKind::ParenClose)?;
+            return Ok(Place::Param(index));
+        }
+        let id = InstrId(self.read_index()?);
+        if !self.vars.contains(&id) {
+            return Err(ParsingError::invalid("instruction with this number doesn't declare a variable", token.span()).into());
+        }
+        Ok(Place::Var(id))
+    }
+
+    fn read_value(&mut self) -> Result<Value> {
+        let token = self.seq.solid_token()?;
+        let word = match token.kind() {
+            TokenKind::NumberLiteral => return Ok(Value::Place(self.read_place()?)),
+            TokenKind::Ident => token.string(),
+            _ => return Err(ParsingError::expected(token, "a value").into()),
+        };
+        if word == "Param" {
+            return Ok(Value::Place(self.read_place()?));
+        }
+        self.seq.go_next();
+        let value = match word {
+            "Binary" => {
+                self.expect_fields_start("op")?;
+                let op_token = self.seq.next_solid_token()?;
+                let op =
+                    BinaryOp::from_token(op_token.kind()).ok_or_else(|| ParsingError::expected(op_token, "a binary operator"))?;
+                self.expect_next_field("left")?;
+                let left = self.read_value()?;
+                self.expect_next_field("right")?;
+                let right = self.read_value()?;
+                self.expect_fields_end()?;
+                Value::Binary {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                }
+            }
+            "Unary" => {
+                self.expect_fields_start("op")?;
+                let op_token = self.seq.next_solid_token()?;
+                let op =
+                    UnaryOp::from_token(op_token.kind()).ok_or_else(|| ParsingError::expected(op_token, "a unary operator"))?;
+                self.expect_next_field("operand")?;
+                let operand = self.read_value()?;
+                self.expect_fields_end()?;
+                Value::Unary {
+                    op,
+                    operand: Box::new(operand),
+                }
+            }
+            "Call" => {
+                let (function, args) = self.read_call()?;
+                Value::Call { function, args }
+            }
+            "Convert" => {
+                self.expect_fields_start("value")?;
+                let value = self.read_value()?;
+                self.expect_next_field("to")?;
+                let to = self.read_type()?;
+                self.expect_fields_end()?;
+                Value::Convert {
+                    value: Box::new(value),
+                    to,
+                }
+            }
+            "ToDyn" => {
+                self.expect_fields_start("value")?;
+                let value = self.read_value()?;
+                self.expect_next_field("from")?;
+                let from = self.read_type()?;
+                self.expect_fields_end()?;
+                Value::ToDyn {
+                    value: Box::new(value),
+                    from,
+                }
+            }
+            "New" => {
+                self.expect_fields_start("class")?;
+                let class_token = self.seq.solid_token()?;
+                let class = match self.read_type()? {
+                    Type::Class(class) => class,
+                    _ => return Err(ParsingError::expected(class_token, "a class").into()),
+                };
+                self.expect_next_field("args")?;
+                let args = self.read_list(|reader| reader.read_value())?;
+                self.expect_fields_end()?;
+                Value::New { class, args }
+            }
+            "Field" => {
+                self.expect_fields_start("object")?;
+                let object = self.read_value()?;
+                self.expect_next_field("field")?;
+                let field = self.read_index()?;
+                self.expect_fields_end()?;
+                Value::Field {
+                    object: Box::new(object),
+                    field,
+                }
+            }
+            "Array" => {
+                self.expect_fields_start("type")?;
+                let type_token = self.seq.solid_token()?;
+                let array = match self.read_type()? {
+                    Type::Array(array) => array,
+                    _ => return Err(ParsingError::expected(type_token, "an array type").into()),
+                };
+                self.expect_next_field("items")?;
+                let items = self.read_list(|reader| reader.read_value())?;
+                self.expect_fields_end()?;
+                Value::Array { array, items }
+            }
+            "Index" => {
+                self.expect_fields_start("array")?;
+                let array = self.read_value()?;
+                self.expect_next_field("index")?;
+                let index = self.read_value()?;
+                self.expect_fields_end()?;
+                Value::Index {
+                    array: Box::new(array),
+                    index: Box::new(index),
+                }
+            }
+            "Len" => {
+                self.expect_fields_start("array")?;
+                let array = self.read_value()?;
+                self.expect_fields_end()?;
+                Value::Len { array: Box::new(array) }
+            }
+            _ => {
+                self.seq.expect_token(TokenKind::ParenOpen)?;
+                let constant = self
+                    .read_constant(word)
+                    .ok_or_else(|| ParsingError::expected(token, "a value"))??;
+                self.seq.expect_token(TokenKind::ParenClose)?;
+                Value::Const(constant)
+            }
+        };
+        Ok(value)
+    }
+
+    /// Reads the value of the constant in parens, `None` if `kind` is not a constant kind.
+    fn read_constant(&mut self, kind: &str) -> Option<Result<Constant>> {
+        let integer_type = TYPES
+            .into_iter()
+            .find(|type_| type_.is_integer() && integer_constant_name(*type_) == kind);
+        let token = match self.seq.solid_token() {
+            Ok(token) => token,
+            Err(err) => return Some(Err(err)),
+        };
+        if let Some(type_) = integer_type {
+            return Some(self.read_integer().map(|value| Constant::Integer { value, type_ }));
+        }
+        let constant = match kind {
+            "DoubleValue" => self.read_double().map(Constant::Double),
+            "FinancialValue" => self.read_integer().and_then(|units| {
+                i64::try_from(units)
+                    .map(Constant::Financial)
+                    .map_err(|_| ParsingError::expected(token, "units of `financial`").into())
+            }),
+            "StringValue" => self.read_string().map(Constant::String),
+            "RuneValue" => self.seq.expect_literal().and_then(|literal| match literal.kind() {
+                LiteralKind::Rune(rune) => Ok(Constant::Rune(*rune)),
+                _ => Err(ParsingError::expected(token, "a rune").into()),
+            }),
+            "BoolValue" => self.seq.expect_literal().and_then(|literal| match literal.kind() {
+                LiteralKind::Bool(value) => Ok(Constant::Bool(*value)),
+                _ => Err(ParsingError::expected(token, "`true` or `false`").into()),
+            }),
+            _ => return None,
+        };
+        Some(constant)
+    }
+
+    fn read_double(&mut self) -> Result<f64> {
+        let negative = self.seq.solid_token()?.kind() == TokenKind::Minus;
+        if negative {
+            self.seq.go_next();
+        }
+        let token = self.seq.solid_token()?;
+        match self.seq.expect_literal()?.kind() {
+            LiteralKind::Double(value) => Ok(if negative { -value } else { *value }),
+            _ => Err(ParsingError::expected(token, "a floating point number").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_program;
+    use crate::ir::{lower_program, print_program};
+    use crate::lexer::{Lexer, Span};
+    use crate::{parsing, sema};
+
+    fn lower(source: &str) -> String {
+        let (ast, errors) = parsing::build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (analysis, problems) = sema::analyze(&ast);
+        assert!(problems.is_empty(), "{:?}", problems);
+        print_program(&lower_program(&ast, &analysis).unwrap())
+    }
+
+    /// Printing the program read from the printed IR gives the same text.
+    fn assert_round_trip(source: &str) {
+        let printed = lower(source);
+        let (program, problems) = read_program(&printed).unwrap();
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(print_program(&program), printed);
+    }
+
+    /// Reads the IR and returns message and span of every problem found by validating it.
+    fn problems(source: &str) -> Vec<(String, Option<Span>)> {
+        let (_, problems) = read_program(source).unwrap();
+        problems.iter().map(|problem| (problem.to_string(), problem.span())).collect()
+    }
+
+    #[test]
+    fn round_trips_functions_and_control_flow() {
+        assert_round_trip(
+            r#"
+/// Counts things.
+int loops(int n) {
+    int total = 0
+    for (int i = 0; i < n; i += 1) {
+        if i % 2 == 0 {
+            continue
+        } else if i > 7 {
+            break
+        } else {
+            total += i
+        }
+    }
+    while total > 100 {
+        total -= 1
+    }
+    return total
+}
+
+void main() {
+    byte small = 200
+    long big = -9223372036854775808
+    double d = double(small) / 2 + 0.5
+    financial money = -12.5
+    dyn any = loops(10) + 1
+    rune r = '\u{1F600}'
+    bool flag = small > 3 && !(r == 'b')
+    string text = "text\t\"quoted\"\n"
+    print(text)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn validates_programs() {
+        let main = "declare function main {\n    args: ();\n    returns: void;\n    body: {\n";
+        let end = "\n    }\n}\n";
+        let read = |body: &str| problems(&format!("{}{}{}", main, body, end));
+
+        assert_eq!(
+            read("        0: Break"),
+            [("`Break` outside of a loop body".to_string(), Some(Span::new(77, 8, 5, 9)))]
+        );
+        assert_eq!(
+            read("        0: VarDecl { name: \"x\"; type: int; value: BoolValue(true) }"),
+            [(
+                "mismatched types: expected `int`, found `bool`".to_string(),
+                Some(Span::new(77, 10, 5, 9))
+            )]
+        );
+        assert_eq!(
+            read("        0: VarDecl { name: \"x\"; value: ByteValue(256) }"),
+            [(
+                "constant is out of range of type `byte`".to_string(),
+                Some(Span::new(77, 10, 5, 9))
+            )]
+        );
+        assert_eq!(
+            read("        0: Call { function: print; args: () }"),
+            [("cannot find function `print`".to_string(), Some(Span::new(77, 7, 5, 9)))]
+        );
+        assert_eq!(
+            read(
+                "        0: VarDecl { name: \"x\"; value: IntValue(1) };\n        0: VarDecl { name: \"y\"; value: IntValue(2) }"
+            ),
+            [(
+                "instruction `0` is already defined".to_string(),
+                Some(Span::new(131, 10, 6, 9))
+            )]
+        );
+        assert_eq!(
+            problems("declare function foo {\n    args: ();\n    returns: void;\n    body: {}\n}\n"),
+            [("`void main()` function not found".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn checks_builtins_against_the_registry() {
+        let source = "declare function main {\n    args: ();\n    returns: void;\n    body: {\n        0: Call { function: print; args: (IntValue(1), BoolValue(true)) }\n    }\n}\n\ndeclare function print {\n    args: (\n        Arg { name: \"value\"; type: string; marker: ( In ) }\n    );\n    returns: void;\n    builtin: true\n}\n";
+        // the arguments are fine for the variadic `print` of the registry, but not its declaration
+        assert_eq!(
+            problems(source),
+            [(
+                "declaration of builtin `print` doesn't match the builtin registry".to_string(),
+                Some(Span::new(169, 5, 9, 18))
+            )]
+        );
+    }
+
+    #[test]
+    fn round_trips_classes() {
+        assert_round_trip(
+            r#"
+/// Point on a plane.
+class Point { int x, int y }
+
+value class Money { string currency, financial amount }
+
+void main() {
+    Point p = Point(1, 2)
+    move(p).x += p.y
+    Money m = Money("EUR", 1.5)
+    bool less = m < m
+    string s = string(m)
+    println(m)
+}
+
+Point move(Point p) {
+    return p
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn validates_classes() {
+        let class = "declare class Point {\n    fields: (\n        Field { name: \"x\"; type: int }\n    )\n}\n\n";
+        let main = "declare function main {\n    args: ();\n    returns: void;\n    body: {\n";
+        let end = "\n    }\n}\n";
+        let read = |body: &str| problems(&format!("{}{}{}{}", class, main, body, end));
+
+        assert_eq!(
+            read("        0: VarDecl { name: \"p\"; type: Point; value: New { class: Point; args: (BoolValue(true)) } }"),
+            [(
+                "mismatched types: expected `int`, found `bool`".to_string(),
+                Some(Span::new(161, 10, 11, 9))
+            )]
+        );
+        assert_eq!(
+            read("        0: VarDecl { name: \"p\"; type: Point; value: New { class: Point; args: () } }"),
+            [(
+                "`Point` takes 1 argument, but 0 were passed".to_string(),
+                Some(Span::new(161, 10, 11, 9))
+            )]
+        );
+        assert_eq!(
+            read("        0: VarDecl { name: \"p\"; type: Point; value: New { class: Point; args: (IntValue(1)) } };\n        1: SetField { object: 0; field: 1; value: IntValue(2) }"),
+            [(
+                "type `Point` has no field with index 1".to_string(),
+                Some(Span::new(258, 11, 12, 9))
+            )]
+        );
+        assert_eq!(
+            read("        0: VarDecl { name: \"x\"; value: IntValue(1) };\n        1: VarDecl { name: \"y\"; type: int; value: Field { object: 0; field: 0 } }"),
+            [(
+                "type `int` has no field with index 0".to_string(),
+                Some(Span::new(215, 10, 12, 9))
+            )]
+        );
+        assert_eq!(
+            problems("declare class Point {\n    fields: (\n        Field { name: \"x\"; type: int },\n        Field { name: \"x\"; type: bool }\n    )\n}\n\ndeclare function main {\n    args: ();\n    returns: void;\n    body: {}\n}\n"),
+            [(
+                "field `x` is already defined".to_string(),
+                Some(Span::new(14, 5, 1, 15))
+            )]
+        );
+    }
+
+    #[test]
+    fn validates_value_classes() {
+        let classes = "declare value class Money {\n    fields: (\n        Field { name: \"units\"; type: int }\n    )\n}\n\n";
+        let main = "declare function main {\n    args: ();\n    returns: void;\n    body: {\n        0: VarDecl { name: \"m\"; type: Money; value: New { class: Money; args: (IntValue(1)) } };\n";
+        let end = "\n    }\n}\n";
+        let read = |body: &str| problems(&format!("{}{}{}{}", classes, main, body, end));
+
+        assert_eq!(
+            read("        1: VarDecl { name: \"b\"; type: bool; value: Binary { op: <; left: 0; right: 0 } };\n        2: VarDecl { name: \"s\"; type: string; value: Convert { value: 0; to: string } }"),
+            []
+        );
+        assert_eq!(
+            read("        1: SetField { object: 0; field: 0; value: IntValue(2) }"),
+            [(
+                "fields of objects of value class `Money` can't be assigned".to_string(),
+                Some(Span::new(268, 11, 12, 9))
+            )]
+        );
+        assert_eq!(
+            read("        1: VarDecl { name: \"n\"; type: Money; value: Binary { op: +; left: 0; right: 0 } }"),
+            [(
+                "operator `+` can't be applied to values of type `Money`".to_string(),
+                Some(Span::new(268, 10, 12, 9))
+            )]
+        );
+
+        let source = "declare value class Node {\n    fields: (\n        Field { name: \"edge\"; type: Edge }\n    )\n}\n\ndeclare value class Edge {\n    fields: (\n        Field { name: \"to\"; type: Node },\n        Field { name: \"any\"; type: dyn }\n    )\n}\n\ndeclare function main {\n    args: ();\n    returns: void;\n    body: {}\n}\n";
+        assert_eq!(
+            problems(source),
+            [
+                (
+                    "value class `Node` contains itself".to_string(),
+                    Some(Span::new(20, 4, 1, 21))
+                ),
+                (
+                    "value class `Edge` can't have a field of type `dyn`".to_string(),
+                    Some(Span::new(113, 4, 7, 21))
+                ),
+                (
+                    "value class `Edge` contains itself".to_string(),
+                    Some(Span::new(113, 4, 7, 21))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_arrays() {
+        assert_round_trip(
+            r#"
+value class Money { int units }
+
+void main() {
+    [][]Money grid = [[Money(1)], []]
+    grid[0][0] = Money(2)
+    append(grid, [Money(3)])
+    for ([]Money row : grid) {
+        println(row.len, row[0])
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn validates_arrays() {
+        let main = "declare function main {\n    args: ();\n    returns: void;\n    body: {\n        0: VarDecl { name: \"xs\"; type: []int; value: Array { type: []int; items: (IntValue(1)) } };\n";
+        let end = "\n    }\n}\n";
+        let read = |body: &str| problems(&format!("{}{}{}", main, body, end));
+
+        assert_eq!(
+            read("        1: SetIndex { array: 0; index: IntValue(0); value: Len { array: 0 } }"),
+            []
+        );
+        assert_eq!(
+            read("        1: VarDecl { name: \"x\"; type: int; value: Index { array: 0; index: BoolValue(true) } }"),
+            [(
+                "arrays can't be indexed with values of type `bool`".to_string(),
+                Some(Span::new(177, 10, 6, 9))
+            )]
+        );
+        assert_eq!(
+            read("        1: VarDecl { name: \"x\"; type: int; value: Len { array: IntValue(1) } }"),
+            [("expected an array, found `int`".to_string(), Some(Span::new(177, 10, 6, 9)))]
+        );
+        assert_eq!(
+            read("        1: VarDecl { name: \"ys\"; type: []int; value: Array { type: []int; items: (StringValue(\"a\")) } }"),
+            [(
+                "mismatched types: expected `int`, found `string`".to_string(),
+                Some(Span::new(177, 10, 6, 9))
+            )]
+        );
+        let print = "\ndeclare function print {\n    args: (\n        Arg { name: \"value\"; type: AnyType; marker: ( In ) }\n    );\n    returns: void;\n    builtin: true\n}\n";
+        let call = "        1: Call { function: print; args: (0) }";
+        assert_eq!(
+            problems(&format!("{}{}{}{}", main, call, end, print)),
+            [(
+                "values of type `[]int` can't be printed".to_string(),
+                Some(Span::new(177, 7, 6, 9))
+            )]
+        );
+    }
+}
@@ -0,0 +1,662 @@
+use super::lower::lower_builtin;
+use super::printer::type_name;
+use super::{ArrayId, Block, ClassId, Constant, Function, Instr, InstrId, InstrKind, IrError, Place, Program, Type, Value};
+use crate::builtins::{self, Builtin, ParamType};
+use crate::error::CompileError;
+use crate::lexer::Span;
+use crate::parsing::ast::expr::{BinaryOp, UnaryOp};
+use std::collections::{HashMap, HashSet};
+
+/// Spans of the declarations and instructions in the IR source, which problems point to.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    pub classes: Vec<Span>,
+    pub functions: Vec<Span>,
+    /// Spans of the instructions of every function by their numbers.
+    pub instrs: Vec<HashMap<InstrId, Span>>,
+}
+
+/// Checks the program read from the IR source, so that backends can rely on it being as
+/// well-formed as the lowered programs are.
+pub fn validate_program(program: &Program, source_map: &SourceMap) -> Vec<CompileError> {
+    let mut validator = Validator {
+        program,
+        source_map,
+        problems: Vec::new(),
+        function: 0,
+        scopes: Vec::new(),
+        ids: HashSet::new(),
+        loop_depth: 0,
+        span: Span::default(),
+    };
+    validator.check_classes();
+    validator.check_functions();
+    validator.problems
+}
+
+/// Variables declared in a block, which become invisible after its end.
+#[derive(Default)]
+struct Scope<'p> {
+    vars: HashMap<InstrId, Type>,
+    /// Names of the variables, which must be unique in C blocks.
+    names: HashSet<&'p str>,
+}
+
+struct Validator<'p> {
+    program: &'p Program,
+    source_map: &'p SourceMap,
+    problems: Vec<CompileError>,
+    /// Index of the function whose body is being checked.
+    function: usize,
+    scopes: Vec<Scope<'p>>,
+    /// Numbers of the instructions of the function seen so far.
+    ids: HashSet<InstrId>,
+    loop_depth: usize,
+    /// Span of the instruction being checked, which also is the span of the problems of its values.
+    span: Span,
+}
+
+impl<'p> Validator<'p> {
+    fn report(&mut self, err: IrError) {
+        self.problems.push(err.into());
+    }
+
+    fn type_name(&self, type_: Type) -> String {
+        type_name(type_, &self.program.classes, &self.program.arrays).into_owned()
+    }
+
+    fn check_classes(&mut self) {
+        let mut names = HashSet::new();
+        for (index, class) in self.program.classes.iter().enumerate() {
+            let span = self.source_map.classes[index];
+            if !names.insert(class.name.as_str()) {
+                self.report(IrError::AlreadyDefined {
+                    what: "class",
+                    name: class.name.clone(),
+                    span,
+                });
+            }
+            let mut fields = HashSet::new();
+            for field in &class.fields {
+                self.check_name(&field.name, span);
+                if !fields.insert(field.name.as_str()) {
+                    self.report(IrError::AlreadyDefined {
+                        what: "field",
+                        name: field.name.clone(),
+                        span,
+                    });
+                }
+                self.check_type(field.type_, span);
+                if class.value && !self.is_value(field.type_) {
+                    self.report(IrError::InvalidFieldType {
+                        class: class.name.clone(),
+                        type_name: self.type_name(field.type_),
+                        span,
+                    });
+                }
+            }
+            if class.value && self.contains_class(ClassId(index), ClassId(index), &mut HashSet::new()) {
+                self.report(IrError::RecursiveValueClass {
+                    class: class.name.clone(),
+                    span,
+                });
+            }
+        }
+    }
+
+    /// Values are copied, so they can't refer to anything shared.
+    fn is_value(&self, type_: Type) -> bool {
+        match type_ {
+            Type::Class(class) => self.program.class(class).value,
+            Type::Dyn | Type::Array(_) | Type::Any => false,
+            _ => true,
+        }
+    }
+
+    /// Checks whether objects of the value class `outer` contain objects of `class` in their fields.
+    fn contains_class(&self, outer: ClassId, class: ClassId, visited: &mut HashSet<ClassId>) -> bool {
+        if !visited.insert(outer) {
+            return false;
+        }
+        self.program.class(outer).fields.iter().any(|field| match field.type_ {
+            Type::Class(inner) if self.program.class(inner).value => inner == class || self.contains_class(inner, class, visited),
+            _ => false,
+        })
+    }
+
+    fn check_functions(&mut self) {
+        let mut names = HashSet::new();
+        for (index, function) in self.program.functions.iter().enumerate() {
+            let span = self.source_map.functions[index];
+            if !names.insert(function.name.as_str()) {
+                self.report(IrError::AlreadyDefined {
+                    what: "function",
+                    name: function.name.clone(),
+                    span,
+                });
+            }
+            let mut args = HashSet::new();
+            for arg in &function.args {
+                self.check_name(&arg.name, span);
+                if !args.insert(arg.name.as_str()) {
+                    self.report(IrError::AlreadyDefined {
+                        what: "param",
+                        name: arg.name.clone(),
+                        span,
+                    });
+                }
+            }
+            match &function.body {
+                Some(body) => {
+                    for type_ in function.args.iter().map(|arg| arg.type_).chain(function.returns) {
+                        self.check_type(type_, span);
+                    }
+                    self.check_body(index, function, body);
+                }
+                None => {
+                    let builtin = builtins::find(&function.name).map(lower_builtin);
+                    let matches = builtin.is_some_and(|builtin| {
+                        builtin.returns == function.returns
+                            && builtin.args.len() == function.args.len()
+                            && builtin
+                                .args
+                                .iter()
+                                .zip(&function.args)
+                                .all(|(left, right)| left.type_ == right.type_)
+                    });
+                    if !matches {
+                        self.report(IrError::BuiltinSignature {
+                            name: function.name.clone(),
+                            span,
+                        });
+                    }
+                }
+            }
+        }
+        let has_main = self.program.functions.iter().any(|function| {
+            function.name == "main" && function.args.is_empty() && function.returns.is_none() && !function.is_builtin()
+        });
+        if !has_main {
+            self.report(IrError::MainNotFound);
+        }
+    }
+
+    /// Names become C identifiers after mangling.
+    fn check_name(&mut self, name: &str, span: Span) {
+        let valid = !name.is_empty() && name.chars().all(|char| char.is_ascii_alphanumeric() || char == '_');
+        if !valid {
+            self.report(IrError::InvalidName {
+                name: name.to_string(),
+                span,
+            });
+        }
+    }
+
+    fn check_type(&mut self, type_: Type, span: Span) {
+        match type_ {
+            Type::Any => self.report(IrError::AnyType { span }),
+            Type::Array(array) => self.check_type(self.program.array_element(array), span),
+            _ => {}
+        }
+    }
+
+    fn check_body(&mut self, index: usize, function: &'p Function, body: &'p Block) {
+        self.function = index;
+        self.ids.clear();
+        self.loop_depth = 0;
+        // params are in the same C scope as the variables of the outermost block
+        let names = function.args.iter().map(|arg| arg.name.as_str()).collect();
+        self.scopes = vec![Scope {
+            vars: HashMap::new(),
+            names,
+        }];
+        self.check_instrs(body);
+    }
+
+    fn check_block(&mut self, block: &'p Block) {
+        self.scopes.push(Scope::default());
+        self.check_instrs(block);
+        self.scopes.pop();
+    }
+
+    fn check_instrs(&mut self, block: &'p Block) {
+        for instr in &block.instrs {
+            self.check_instr(instr);
+        }
+    }
+
+    fn check_instr(&mut self, instr: &'p Instr) {
+        self.span = self.source_map.instrs[self.function]
+            .get(&instr.id)
+            .copied()
+            .unwrap_or_default();
+        if !self.ids.insert(instr.id) {
+            self.report(IrError::AlreadyDefined {
+                what: "instruction",
+                name: instr.id.0.to_string(),
+                span: self.span,
+            });
+        }
+        match &instr.kind {
+            InstrKind::VarDecl { name, type_, value } => {
+                self.check_name(name, self.span);
+                self.check_type(*type_, self.span);
+                self.expect_value(*type_, value);
+                // the variable becomes visible after its initialiser
+                let scope = self.scopes.last_mut().expect("function bodies have a scope");
+                scope.vars.insert(instr.id, *type_);
+                if !scope.names.insert(name) {
+                    self.report(IrError::AlreadyDefined {
+                        what: "variable",
+                        name: name.clone(),
+                        span: self.span,
+                    });
+                }
+            }
+            InstrKind::Call { function, args } => {
+                self.check_call(function, args);
+            }
+            InstrKind::Assign { target, value } => {
+                if let Some(type_) = self.check_place(*target) {
+                    self.expect_value(type_, value);
+                }
+            }
+            InstrKind::SetField { object, field, value } => {
+                if let Some(type_) = self.check_field(object, *field, true) {
+                    self.expect_value(type_, value);
+                }
+            }
+            InstrKind::SetIndex { array, index, value } => {
+                if let Some(type_) = self.check_index(array, index) {
+                    self.expect_value(type_, value);
+                }
+            }
+            InstrKind::Return { value } => {
+                let returns = self.program.functions[self.function].returns;
+                match (returns, value) {
+                    (Some(type_), Some(value)) => self.expect_value(type_, value),
+                    (None, Some(value)) => {
+                        if let Some(found) = self.check_value(value) {
+                            self.report(IrError::MismatchedTypes {
+                                expected: "void".to_string(),
+                                found: self.type_name(found),
+                                span: self.span,
+                            });
+                        }
+                    }
+                    (Some(type_), None) => self.report(IrError::MismatchedTypes {
+                        expected: self.type_name(type_),
+                        found: "void".to_string(),
+                        span: self.span,
+                    }),
+                    (None, None) => {}
+                }
+            }
+            InstrKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expect_value(Type::Bool, condition);
+                self.check_block(then);
+                self.check_block(otherwise);
+            }
+            InstrKind::Loop { condition, body, step } => {
+                self.expect_value(Type::Bool, condition);
+                self.loop_depth += 1;
+                self.scopes.push(Scope::default());
+                self.check_instrs(body);
+                self.loop_depth -= 1;
+                // steps are generated at the end of the C block of the body, but they don't see its
+                // variables, and they are repeated before `continue`, so they can't contain one
+                let names = self.scopes.pop().expect("scope of the loop body").names;
+                let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                self.scopes.push(Scope {
+                    vars: HashMap::new(),
+                    names,
+                });
+                self.check_instrs(step);
+                self.scopes.pop();
+                self.loop_depth = loop_depth;
+            }
+            InstrKind::Break => self.check_inside_of_loop("Break"),
+            InstrKind::Continue => self.check_inside_of_loop("Continue"),
+            InstrKind::Block(block) => self.check_block(block),
+        }
+    }
+
+    fn check_inside_of_loop(&mut self, what: &'static str) {
+        if self.loop_depth == 0 {
+            self.report(IrError::OutsideOfLoop { what, span: self.span });
+        }
+    }
+
+    fn expect_value(&mut self, expected: Type, value: &'p Value) {
+        if let Some(found) = self.check_value(value) {
+            self.expect_type(expected, found);
+        }
+    }
+
+    fn expect_type(&mut self, expected: Type, found: Type) {
+        if expected != found {
+            self.report(IrError::MismatchedTypes {
+                expected: self.type_name(expected),
+                found: self.type_name(found),
+                span: self.span,
+            });
+        }
+    }
+
+    fn check_place(&mut self, place: Place) -> Option<Type> {
+        let type_ = match place {
+            Place::Var(id) => self.scopes.iter().rev().find_map(|scope| scope.vars.get(&id).copied()),
+            Place::Param(index) => self.program.functions[self.function].args.get(index).map(|arg| arg.type_),
+        };
+        if type_.is_none() {
+            let (what, name) = match place {
+                Place::Var(id) => ("variable", id.0.to_string()),
+                Place::Param(index) => ("param", format!("Param({})", index)),
+            };
+            self.report(IrError::Undefined {
+                what,
+                name,
+                span: self.span,
+            });
+        }
+        type_
+    }
+
+    /// Returns the type of the value, `None` if it is invalid, which is already reported.
+    fn check_value(&mut self, value: &'p Value) -> Option<Type> {
+        match value {
+            Value::Const(constant) => {
+                if let Constant::Integer { value, type_ } = constant {
+                    if !integer_fits(*value, *type_) {
+                        self.report(IrError::ConstantOutOfRange {
+                            type_name: self.type_name(*type_),
+                            span: self.span,
+                        });
+                    }
+                }
+                Some(constant.type_())
+            }
+            Value::Place(place) => self.check_place(*place),
+            Value::Binary { op, left, right } => {
+                let left = self.check_value(left);
+                let right = self.check_value(right);
+                let (left, right) = (left?, right?);
+                if left != right {
+                    self.expect_type(left, right);
+                    return None;
+                }
+                if !self.operator_applies(*op, left) {
+                    self.report(IrError::InvalidOperator {
+                        operator: op.symbol(),
+                        type_name: self.type_name(left),
+                        span: self.span,
+                    });
+                    return None;
+                }
+                match op {
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => Some(left),
+                    _ => Some(Type::Bool),
+                }
+            }
+            Value::Unary { op, operand } => {
+                let type_ = self.check_value(operand)?;
+                let applies = match op {
+                    UnaryOp::Neg => matches!(type_, Type::Short | Type::Int | Type::Long | Type::Double | Type::Financial),
+                    UnaryOp::Not => type_ == Type::Bool,
+                };
+                if !applies {
+                    self.report(IrError::InvalidOperator {
+                        operator: op.symbol(),
+                        type_name: self.type_name(type_),
+                        span: self.span,
+                    });
+                    return None;
+                }
+                Some(type_)
+            }
+            Value::Call { function, args } => {
+                let returns = self.check_call(function, args)?;
+                if returns.is_none() {
+                    self.report(IrError::NoValue {
+                        function: function.clone(),
+                        span: self.span,
+                    });
+                }
+                returns
+            }
+            Value::Convert { value, to } => {
+                let from = self.check_value(value)?;
+                // values are converted to strings by formatting them
+                let allowed = match from {
+                    Type::Class(class) if self.program.class(class).value => *to == Type::String,
+                    _ => is_convertible(from) && is_convertible(*to),
+                };
+                if !allowed {
+                    self.report(IrError::InvalidConversion {
+                        from: self.type_name(from),
+                        to: self.type_name(*to),
+                        span: self.span,
+                    });
+                }
+                Some(*to)
+            }
+            Value::ToDyn { value, from } => {
+                self.expect_value(*from, value);
+                if matches!(from, Type::Dyn | Type::Class(_) | Type::Array(_) | Type::Any) {
+                    self.report(IrError::InvalidConversion {
+                        from: self.type_name(*from),
+                        to: "dyn".to_string(),
+                        span: self.span,
+                    });
+                }
+                Some(Type::Dyn)
+            }
+            Value::New { class, args } => {
+                let fields = &self.program.class(*class).fields;
+                if args.len() != fields.len() {
+                    self.report(IrError::WrongArgumentCount {
+                        function: self.program.class(*class).name.clone(),
+                        expected: fields.len(),
+                        found: args.len(),
+                        span: self.span,
+                    });
+                }
+                for (field, arg) in fields.iter().zip(args) {
+                    self.expect_value(field.type_, arg);
+                }
+                Some(Type::Class(*class))
+            }
+            Value::Field { object, field } => self.check_field(object, *field, false),
+            Value::Array { array, items } => {
+                let element = self.program.array_element(*array);
+                self.check_type(element, self.span);
+                for item in items {
+                    self.expect_value(element, item);
+                }
+                Some(Type::Array(*array))
+            }
+            Value::Index { array, index } => self.check_index(array, index),
+            Value::Len { array } => {
+                self.check_array(array)?;
+                Some(Type::Int)
+            }
+        }
+    }
+
+    fn operator_applies(&self, op: BinaryOp, type_: Type) -> bool {
+        let value_class = matches!(type_, Type::Class(class) if self.program.class(class).value);
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                type_.is_integer() || matches!(type_, Type::Double | Type::Financial)
+            }
+            BinaryOp::Rem => type_.is_integer(),
+            BinaryOp::Less | BinaryOp::LessOrEqual | BinaryOp::Greater | BinaryOp::GreaterOrEqual => {
+                is_convertible(type_) || matches!(type_, Type::Financial | Type::String) || value_class
+            }
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                is_convertible(type_) || matches!(type_, Type::Bool | Type::Financial | Type::String) || value_class
+            }
+            BinaryOp::And | BinaryOp::Or => type_ == Type::Bool,
+        }
+    }
+
+    /// Returns what the function returns, `None` if the call is invalid.
+    fn check_call(&mut self, name: &str, args: &'p [Value]) -> Option<Option<Type>> {
+        let arg_types: Vec<_> = args.iter().map(|arg| self.check_value(arg)).collect();
+        let Some(function) = self.program.function(name) else {
+            self.report(IrError::Undefined {
+                what: "function",
+                name: name.to_string(),
+                span: self.span,
+            });
+            return None;
+        };
+        let builtin = builtins::find(name).filter(|_| function.is_builtin());
+        let count_matches = match builtin {
+            Some(builtin) if builtin.variadic => args.len() + 1 >= function.args.len(),
+            _ => args.len() == function.args.len(),
+        };
+        if !count_matches {
+            self.report(IrError::WrongArgumentCount {
+                function: name.to_string(),
+                expected: function.args.len(),
+                found: args.len(),
+                span: self.span,
+            });
+            return None;
+        }
+        match builtin {
+            Some(builtin) => self.check_builtin_args(builtin, function, arg_types),
+            None => {
+                for (arg, type_) in function.args.iter().zip(arg_types) {
+                    if let Some(type_) = type_ {
+                        self.expect_type(arg.type_, type_);
+                    }
+                }
+            }
+        }
+        Some(function.returns)
+    }
+
+    fn check_builtin_args(&mut self, builtin: &Builtin, function: &Function, arg_types: Vec<Option<Type>>) {
+        // element type of the array passed to `ParamType::Array`
+        let mut element = None;
+        for (index, (param, type_)) in builtin.param_types(arg_types.len()).into_iter().zip(arg_types).enumerate() {
+            let Some(type_) = type_ else {
+                continue;
+            };
+            match param {
+                ParamType::Exact(_) => {
+                    let declared = function.args[index.min(function.args.len() - 1)].type_;
+                    self.expect_type(declared, type_);
+                }
+                ParamType::Any => {
+                    let printable = match type_ {
+                        Type::Class(class) => self.program.class(class).value,
+                        Type::Array(_) | Type::Any => false,
+                        _ => true,
+                    };
+                    if !printable {
+                        self.report(IrError::NotPrintable {
+                            type_name: self.type_name(type_),
+                            span: self.span,
+                        });
+                    }
+                }
+                ParamType::Array => match type_ {
+                    Type::Array(array) => element = Some(self.program.array_element(array)),
+                    _ => self.report(IrError::NotArray {
+                        type_name: self.type_name(type_),
+                        span: self.span,
+                    }),
+                },
+                ParamType::Element => {
+                    if let Some(element) = element {
+                        self.expect_type(element, type_);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_field(&mut self, object: &'p Value, field: usize, assigned: bool) -> Option<Type> {
+        let type_ = self.check_value(object)?;
+        let class = match type_ {
+            Type::Class(class) => self.program.class(class),
+            _ => {
+                self.report(IrError::NoField {
+                    type_name: self.type_name(type_),
+                    field,
+                    span: self.span,
+                });
+                return None;
+            }
+        };
+        if assigned && class.value {
+            self.report(IrError::ImmutableField {
+                class: class.name.clone(),
+                span: self.span,
+            });
+        }
+        match class.fields.get(field) {
+            Some(field) => Some(field.type_),
+            None => {
+                self.report(IrError::NoField {
+                    type_name: class.name.clone(),
+                    field,
+                    span: self.span,
+                });
+                None
+            }
+        }
+    }
+
+    fn check_array(&mut self, array: &'p Value) -> Option<ArrayId> {
+        match self.check_value(array)? {
+            Type::Array(array) => Some(array),
+            type_ => {
+                self.report(IrError::NotArray {
+                    type_name: self.type_name(type_),
+                    span: self.span,
+                });
+                None
+            }
+        }
+    }
+
+    /// Returns the type of the element.
+    fn check_index(&mut self, array: &'p Value, index: &'p Value) -> Option<Type> {
+        let array = self.check_array(array);
+        let index = self.check_value(index);
+        if let Some(index) = index.filter(|index| !index.is_integer()) {
+            self.report(IrError::InvalidIndex {
+                type_name: self.type_name(index),
+                span: self.span,
+            });
+        }
+        array.map(|array| self.program.array_element(array))
+    }
+}
+
+/// Types between which conversions are generated as C casts.
+fn is_convertible(type_: Type) -> bool {
+    type_.is_integer() || matches!(type_, Type::Double | Type::Rune)
+}
+
+fn integer_fits(value: i128, type_: Type) -> bool {
+    let (min, max) = match type_ {
+        Type::Byte => (u8::MIN as i128, u8::MAX as i128),
+        Type::Short => (i16::MIN as i128, i16::MAX as i128),
+        Type::UShort => (u16::MIN as i128, u16::MAX as i128),
+        Type::Int => (i32::MIN as i128, i32::MAX as i128),
+        Type::UInt => (u32::MIN as i128, u32::MAX as i128),
+        Type::Long => (i64::MIN as i128, i64::MAX as i128),
+        Type::ULong => (u64::MIN as i128, u64::MAX as i128),
+        _ => return false,
+    };
+    (min..=max).contains(&value)
+}
@@ -0,0 +1,52 @@
+use super::Span;
+use std::error;
+use std::fmt;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum LexErrorKind {
+    UnknownCharacter(char),
+    UnterminatedString,
+    UnterminatedRune,
+    UnterminatedComment,
+    InvalidEscape,
+    NulCharacter,
+    InvalidRune,
+    InvalidNumber,
+    IntegerOutOfRange,
+    DoubleOutOfRange,
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct LexError {
+    kind: LexErrorKind,
+    span: Span,
+}
+
+impl LexError {
+    pub const fn new(kind: LexErrorKind, span: Span) -> LexError {
+        LexError { kind, span }
+    }
+
+    pub const fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LexErrorKind::UnknownCharacter(char) => f.write_fmt(format_args!("unknown character {:?}", char)),
+            LexErrorKind::UnterminatedString => f.write_str("unterminated string literal"),
+            LexErrorKind::UnterminatedRune => f.write_str("unterminated rune literal"),
+            LexErrorKind::UnterminatedComment => f.write_str("unterminated block comment"),
+            LexErrorKind::InvalidEscape => f.write_str("invalid escape sequence"),
+            LexErrorKind::NulCharacter => f.write_str("string and rune literals can't contain NUL characters"),
+            LexErrorKind::InvalidRune => f.write_str("rune literal must contain exactly one character"),
+            LexErrorKind::InvalidNumber => f.write_str("invalid number literal"),
+            LexErrorKind::IntegerOutOfRange => f.write_str("integer literal is out of range"),
+            LexErrorKind::DoubleOutOfRange => f.write_str("floating point literal is out of range"),
+        }
+    }
+}
+
+impl error::Error for LexError {}
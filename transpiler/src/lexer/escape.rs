@@ -0,0 +1,122 @@
+use super::{LexError, LexErrorKind, Span, Token};
+
+/// Checks whether `buf` is a quoted literal whose closing `quote` is not escaped.
+pub fn is_closed_quoted(buf: &str, quote: char) -> bool {
+    let body = match buf.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+        Some(body) => body,
+        None => return false,
+    };
+    let trailing_backslashes = body.chars().rev().take_while(|char| *char == '\\').count();
+    trailing_backslashes % 2 == 0
+}
+
+fn char_span(token: Span, body_offset: usize, body: &str, from: usize, to: usize) -> Span {
+    // string and rune literals can't contain line breaks, so the column is counted from the token
+    let column = token.column() + 1 + body[..from].chars().count();
+    Span::new(token.offset() + body_offset + from, to - from, token.line(), column)
+}
+
+/// Replaces escape sequences in the body of a quoted literal token with the chars they denote.
+///
+/// Supported escapes are `\n`, `\r`, `\t`, `\\`, `\"`, `\'` and `\u{...}`
+/// with 1 to 6 hex digits. NUL chars are rejected, as strings are passed to C as NUL-terminated.
+pub fn unescape(token: Token) -> Result<String, LexError> {
+    let string = token.string();
+    // quotes are ASCII, so slicing them off is safe
+    let body = &string[1..(string.len() - 1)];
+
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, char)) = chars.next() {
+        let nul = |end: usize| LexError::new(LexErrorKind::NulCharacter, char_span(token.span(), 1, body, start, end));
+        if char == '\0' {
+            return Err(nul(start + 1));
+        }
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+
+        let invalid = |end: usize| LexError::new(LexErrorKind::InvalidEscape, char_span(token.span(), 1, body, start, end));
+        let (escape_start, escape) = chars.next().ok_or_else(|| invalid(body.len()))?;
+        let escape_end = escape_start + escape.len_utf8();
+        let unescaped = match escape {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => return Err(nul(escape_end)),
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'u' => {
+                if chars.next().map(|(_, char)| char) != Some('{') {
+                    return Err(invalid(escape_end));
+                }
+                let mut code = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((offset, '}')) => break offset + 1,
+                        Some((_, char)) if char.is_ascii_hexdigit() && code.len() < 6 => code.push(char),
+                        Some((offset, char)) => return Err(invalid(offset + char.len_utf8())),
+                        None => return Err(invalid(body.len())),
+                    }
+                };
+                match u32::from_str_radix(code.as_str(), 16).ok().and_then(char::from_u32) {
+                    Some('\0') => return Err(nul(end)),
+                    Some(char) => char,
+                    None => return Err(invalid(end)),
+                }
+            }
+            _ => return Err(invalid(escape_end)),
+        };
+        result.push(unescaped);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+    use crate::lexer::{LexError, LexErrorKind, Lexer, Span};
+
+    fn unescape_literal(text: &str) -> Result<String, LexError> {
+        let token = Lexer::new(text).next().unwrap();
+        assert_eq!(token.string(), text, "the literal is lexed as one token");
+        unescape(token)
+    }
+
+    /// Error for the chars of a literal which starts at the beginning of the source.
+    fn error(kind: LexErrorKind, offset: usize, len: usize) -> Result<String, LexError> {
+        Err(LexError::new(kind, Span::new(offset, len, 1, offset + 1)))
+    }
+
+    #[test]
+    fn replaces_escapes() {
+        assert_eq!(
+            unescape_literal(r#""a\"b\\c\n\t\r\u{1F600}\u{e9}""#).unwrap(),
+            "a\"b\\c\n\t\r\u{1F600}\u{e9}"
+        );
+        assert_eq!(unescape_literal(r#""\\""#).unwrap(), "\\");
+        assert_eq!(unescape_literal(r"'\''").unwrap(), "'");
+        assert_eq!(unescape_literal("'ё'").unwrap(), "ё");
+    }
+
+    #[test]
+    fn reports_invalid_escapes() {
+        assert_eq!(unescape_literal(r#""ab\q""#), error(LexErrorKind::InvalidEscape, 3, 2));
+        assert_eq!(unescape_literal(r#""\u12""#), error(LexErrorKind::InvalidEscape, 1, 2));
+        assert_eq!(unescape_literal(r#""\u{110000}""#), error(LexErrorKind::InvalidEscape, 1, 10));
+        assert_eq!(
+            unescape_literal(r#""\u{1234567}""#),
+            error(LexErrorKind::InvalidEscape, 1, 10)
+        );
+        assert_eq!(unescape_literal(r#""\u{12""#), error(LexErrorKind::InvalidEscape, 1, 5));
+    }
+
+    #[test]
+    fn rejects_nul_characters() {
+        assert_eq!(unescape_literal(r#""a\0""#), error(LexErrorKind::NulCharacter, 2, 2));
+        assert_eq!(unescape_literal(r#""\u{0}b""#), error(LexErrorKind::NulCharacter, 1, 5));
+        assert_eq!(unescape_literal("'\0'"), error(LexErrorKind::NulCharacter, 1, 1));
+    }
+}
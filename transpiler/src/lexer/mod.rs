@@ -1,84 +1,303 @@
+mod error;
+mod escape;
+mod span;
 mod token;
 
+pub use error::*;
+pub use escape::unescape;
+pub use span::*;
 pub use token::*;
 
-#[derive(Debug)]
-pub struct TokenMatcherNotFound;
-
 pub struct Lexer<'c> {
-    left: &'c str,
-    buf_pos: usize,
-    char_count: usize,
-    matcher: TokenMatcher,
+    source: &'c str,
+    offset: usize,
+    line: usize,
+    column: usize,
 }
 
 impl<'c> Lexer<'c> {
-    pub fn new(left: &str) -> Lexer {
+    pub fn new(source: &'c str) -> Lexer<'c> {
         Lexer {
-            left,
-            buf_pos: 0,
-            char_count: 0,
-            matcher: TokenMatcher::Reset,
+            source,
+            offset: 0,
+            line: 1,
+            column: 1,
         }
     }
 
-    fn next_char(&mut self) -> Option<char> {
-        let mut chars = (&self.left[self.buf_pos..]).chars();
-        let char = chars.next()?;
-        return Some(char);
+    fn left(&self) -> &'c str {
+        &self.source[self.offset..]
     }
 
-    fn buf(&self) -> &'c str {
-        &self.left[..self.buf_pos]
+    fn span(&self, len: usize) -> Span {
+        Span::new(self.offset, len, self.line, self.column)
     }
 
-    fn inc_buf(&mut self, n: usize) {
-        self.buf_pos = (self.buf_pos + n).min(self.left.len());
-        self.char_count += 1;
+    fn advance(&mut self, len: usize) {
+        for char in self.left()[..len].chars() {
+            if char == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset += len;
     }
 
-    fn reset_buf(&mut self) {
-        self.left = &self.left[self.buf_pos..];
-        self.buf_pos = 0;
-        self.char_count = 0;
-    }
+    /// Emits the longest token that can be matched at the current position.
+    /// If several matchers produce tokens of the same length, the first one wins.
+    pub fn next(&mut self) -> Result<Token<'c>, LexError> {
+        let left = self.left();
+        let char = match left.chars().next() {
+            Some(char) => char,
+            None => return Ok(TOKEN_EOF.with_span(self.span(0))),
+        };
 
-    // looks better without collapsing
-    #[allow(clippy::collapsible_else_if)]
-    pub fn next(&mut self) -> Result<Token<'c>, TokenMatcherNotFound> {
-        while let Some(char) = self.next_char() {
-            if self.matcher.check(self.buf(), self.char_count, char) {
-                self.inc_buf(char.len_utf8());
-            } else {
-                if self.matcher != TokenMatcher::Reset {
-                    let token = self.matcher.emit(self.buf());
-                    self.matcher = TokenMatcher::Reset;
-                    return Ok(token);
-                } else {
-                    let mut matcher_found = false;
-                    for matcher in TokenMatcher::all() {
-                        if matcher.check("", 0, char) {
-                            self.matcher = matcher;
-                            matcher_found = true;
-                            break;
-                        }
-                    }
-                    if !matcher_found {
-                        return Err(TokenMatcherNotFound);
-                    }
-                    self.reset_buf();
-                    self.inc_buf(char.len_utf8());
+        let mut longest: Option<Token<'c>> = None;
+        for matcher in TokenMatcher::all() {
+            if let Some(token) = matcher.longest_match(left) {
+                if longest.is_none_or(|longest| token.string().len() > longest.string().len()) {
+                    longest = Some(token);
                 }
             }
         }
 
-        if self.matcher != TokenMatcher::Reset {
-            let token = self.matcher.emit(self.buf());
-            self.matcher = TokenMatcher::Reset;
+        let token = match longest {
+            Some(token) => token.with_span(self.span(token.string().len())),
+            None => {
+                // skip the character so that lexing can be continued after the error
+                let span = self.span(char.len_utf8());
+                self.advance(char.len_utf8());
+                return Err(LexError::new(LexErrorKind::UnknownCharacter(char), span));
+            }
+        };
+        self.advance(token.string().len());
 
-            Ok(token)
-        } else {
-            Ok(TOKEN_EOF)
+        match token.kind() {
+            TokenKind::StringLiteral if !escape::is_closed_quoted(token.string(), '"') => {
+                Err(LexError::new(LexErrorKind::UnterminatedString, token.span()))
+            }
+            TokenKind::RuneLiteral if !escape::is_closed_quoted(token.string(), '\'') => {
+                Err(LexError::new(LexErrorKind::UnterminatedRune, token.span()))
+            }
+            TokenKind::Comment if token.string().starts_with("/*") && !is_terminated_block_comment(token.string()) => {
+                Err(LexError::new(LexErrorKind::UnterminatedComment, token.span()))
+            }
+            _ => Ok(token),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LexError, LexErrorKind, Lexer, Span, Token, TokenKind};
+
+    fn lex(source: &str) -> Vec<Token<'_>> {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.next().unwrap();
+            if token.kind() == TokenKind::Eof {
+                tokens.push(token);
+                return tokens;
+            }
+            if token.kind() != TokenKind::Spaces {
+                tokens.push(token);
+            }
+        }
+    }
+
+    #[test]
+    fn attaches_spans_to_tokens() {
+        let tokens = lex("void main() {\n  print(\"ключ\")\n}");
+        let spans: Vec<_> = tokens.iter().map(|token| (token.kind(), token.span())).collect();
+        assert_eq!(
+            spans,
+            [
+                (TokenKind::KeywordVoid, Span::new(0, 4, 1, 1)),
+                (TokenKind::Ident, Span::new(5, 4, 1, 6)),
+                (TokenKind::ParenOpen, Span::new(9, 1, 1, 10)),
+                (TokenKind::ParenClose, Span::new(10, 1, 1, 11)),
+                (TokenKind::BraceOpen, Span::new(12, 1, 1, 13)),
+                (TokenKind::LineBreak, Span::new(13, 1, 1, 14)),
+                (TokenKind::Ident, Span::new(16, 5, 2, 3)),
+                (TokenKind::ParenOpen, Span::new(21, 1, 2, 8)),
+                // columns are counted in chars, not in bytes
+                (TokenKind::StringLiteral, Span::new(22, 10, 2, 9)),
+                (TokenKind::ParenClose, Span::new(32, 1, 2, 15)),
+                (TokenKind::LineBreak, Span::new(33, 1, 2, 16)),
+                (TokenKind::BraceClose, Span::new(34, 1, 3, 1)),
+                (TokenKind::Eof, Span::new(35, 0, 3, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_spans() {
+        let start = Span::new(3, 2, 1, 4);
+        let end = Span::new(10, 4, 2, 1);
+        assert_eq!(start.to(end), Span::new(3, 11, 1, 4));
+        // the result never ends before `self`
+        assert_eq!(start.to(Span::new(0, 1, 1, 1)), start);
+    }
+
+    #[test]
+    fn reports_unknown_characters() {
+        let mut lexer = Lexer::new("int x\n  # 1");
+        let errors: Vec<_> = (0..5).map(|_| lexer.next().err()).collect();
+        let expected = LexError::new(LexErrorKind::UnknownCharacter('#'), Span::new(8, 1, 2, 3));
+        assert_eq!(errors, [None; 5]);
+        assert_eq!(lexer.next(), Err(expected));
+    }
+
+    #[test]
+    fn reports_unterminated_strings() {
+        let mut lexer = Lexer::new("\"abc");
+        let expected = LexError::new(LexErrorKind::UnterminatedString, Span::new(0, 4, 1, 1));
+        assert_eq!(lexer.next(), Err(expected));
+    }
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        let mut kinds: Vec<_> = lex(source).iter().map(|token| token.kind()).collect();
+        assert_eq!(kinds.pop(), Some(TokenKind::Eof));
+        kinds
+    }
+
+    #[test]
+    fn matches_longest_operators() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("a<=b<c>=d>e==f!=g=!h&&i||j"),
+            [
+                Ident,
+                LessOrEqual,
+                Ident,
+                Less,
+                Ident,
+                GreaterOrEqual,
+                Ident,
+                Greater,
+                Ident,
+                Equal,
+                Ident,
+                NotEqual,
+                Ident,
+                Assign,
+                Not,
+                Ident,
+                And,
+                Ident,
+                Or,
+                Ident,
+            ]
+        );
+        assert_eq!(kinds("+-*/%"), [Plus, Minus, Star, Slash, Percent]);
+        assert_eq!(
+            kinds("[x]: ;@$.....,"),
+            [
+                BracketOpen,
+                Ident,
+                BracketClose,
+                Colon,
+                Semicolon,
+                At,
+                Dollar,
+                Ellipsis,
+                DotDot,
+                Sep
+            ]
+        );
+        let mut lexer = Lexer::new("a & b");
+        lexer.next().unwrap();
+        lexer.next().unwrap();
+        let expected = LexError::new(LexErrorKind::UnknownCharacter('&'), Span::new(2, 1, 1, 3));
+        assert_eq!(lexer.next(), Err(expected));
+    }
+
+    #[test]
+    fn lexes_comments() {
+        use TokenKind::*;
+        let tokens = lex("/// doc\n//// plain\nx // rest /* of line\n/* a /* nested */ comment */ y");
+        let tokens: Vec<_> = tokens.iter().map(|token| (token.kind(), token.string())).collect();
+        assert_eq!(
+            tokens,
+            [
+                (DocComment, "/// doc"),
+                (LineBreak, "\n"),
+                (Comment, "//// plain"),
+                (LineBreak, "\n"),
+                (Ident, "x"),
+                (Comment, "// rest /* of line"),
+                (LineBreak, "\n"),
+                (Comment, "/* a /* nested */ comment */"),
+                (Ident, "y"),
+                (Eof, ""),
+            ]
+        );
+        // `*` of the opening `/*` doesn't close the comment
+        assert_eq!(lex("/**/ x")[0].string(), "/**/");
+        assert_eq!(kinds("/*/ */"), [Comment]);
+    }
+
+    #[test]
+    fn reports_unterminated_block_comments() {
+        assert_eq!(kinds("/* /* */ x\n */"), [TokenKind::Comment]);
+        let expected = LexError::new(LexErrorKind::UnterminatedComment, Span::new(0, 13, 1, 1));
+        assert_eq!(Lexer::new("/* /* */ x\n *").next(), Err(expected));
+    }
+
+    #[test]
+    fn lexes_long_block_comments_in_linear_time() {
+        let comment = format!("/*{}*/", "a/*b*/".repeat(50_000));
+        let tokens = lex(&comment);
+        assert_eq!(tokens[0].string().len(), comment.len());
+    }
+
+    #[test]
+    fn lexes_quoted_literals() {
+        use TokenKind::*;
+        let tokens = lex(r#""a\"b" 'x' '\'' "\\""#);
+        let tokens: Vec<_> = tokens.iter().map(|token| (token.kind(), token.string())).collect();
+        assert_eq!(
+            tokens,
+            [
+                (StringLiteral, r#""a\"b""#),
+                (RuneLiteral, "'x'"),
+                (RuneLiteral, r"'\''"),
+                (StringLiteral, r#""\\""#),
+                (Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_number_literals() {
+        use TokenKind::*;
+        let tokens = lex("0x1F 1_000 1.5e-3+x 12.50financial 1..2");
+        let tokens: Vec<_> = tokens.iter().map(|token| (token.kind(), token.string())).collect();
+        assert_eq!(
+            tokens,
+            [
+                (NumberLiteral, "0x1F"),
+                (NumberLiteral, "1_000"),
+                (NumberLiteral, "1.5e-3"),
+                (Plus, "+"),
+                (Ident, "x"),
+                (NumberLiteral, "12.50financial"),
+                (NumberLiteral, "1"),
+                (DotDot, ".."),
+                (NumberLiteral, "2"),
+                (Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_quoted_literals() {
+        let expected = LexError::new(LexErrorKind::UnterminatedString, Span::new(0, 5, 1, 1));
+        assert_eq!(Lexer::new("\"ab\\\"\n\"").next(), Err(expected));
+        let expected = LexError::new(LexErrorKind::UnterminatedRune, Span::new(0, 2, 1, 1));
+        assert_eq!(Lexer::new("'a").next(), Err(expected));
+    }
+}
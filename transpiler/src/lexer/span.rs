@@ -0,0 +1,51 @@
+/// Location of a piece of source code.
+///
+/// `offset` and `len` are measured in bytes, `line` and `column` start from 1
+/// (column is counted in chars).
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct Span {
+    offset: usize,
+    len: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Span {
+    pub const fn new(offset: usize, len: usize, line: usize, column: usize) -> Span {
+        Span {
+            offset,
+            len,
+            line,
+            column,
+        }
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    pub const fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns span which starts at `self` and ends at the end of `other`.
+    pub const fn to(self, other: Span) -> Span {
+        let end = if other.end() > self.end() { other.end() } else { self.end() };
+        Span {
+            len: end - self.offset,
+            ..self
+        }
+    }
+}
@@ -1,9 +1,13 @@
+use super::escape::is_closed_quoted;
+use super::Span;
 use std::array;
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum TokenKind {
     Spaces,
     LineBreak,
+    Comment,
+    DocComment,
     KeywordVoid,
     KeywordByte,
     KeywordShort,
@@ -18,27 +22,150 @@ pub enum TokenKind {
     KeywordString,
     KeywordRune,
     KeywordDyn,
+    KeywordTrue,
+    KeywordFalse,
+    KeywordReturn,
+    KeywordIf,
+    KeywordElse,
+    KeywordWhile,
+    KeywordFor,
+    KeywordBreak,
+    KeywordContinue,
+    KeywordClass,
     Ident,
     Assign,
-    IntegerLiteral,
+    NumberLiteral,
     StringLiteral,
+    RuneLiteral,
     ParenOpen,
     ParenClose,
     BraceOpen,
     BraceClose,
     Sep,
+    BracketOpen,
+    BracketClose,
+    Colon,
+    Semicolon,
+    At,
+    Dollar,
+    Dot,
+    DotDot,
+    Ellipsis,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+    Not,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
     Eof,
 }
 
+impl TokenKind {
+    /// Human-readable name of the token kind used in diagnostics.
+    pub const fn describe(self) -> &'static str {
+        match self {
+            TokenKind::Spaces => "spaces",
+            TokenKind::LineBreak => "line break",
+            TokenKind::Comment => "comment",
+            TokenKind::DocComment => "doc comment",
+            TokenKind::KeywordVoid => "`void`",
+            TokenKind::KeywordByte => "`byte`",
+            TokenKind::KeywordShort => "`short`",
+            TokenKind::KeywordUnsignedShort => "`ushort`",
+            TokenKind::KeywordInt => "`int`",
+            TokenKind::KeywordUnsignedInt => "`uint`",
+            TokenKind::KeywordLong => "`long`",
+            TokenKind::KeywordUnsignedLong => "`ulong`",
+            TokenKind::KeywordDouble => "`double`",
+            TokenKind::KeywordFinancial => "`financial`",
+            TokenKind::KeywordBool => "`bool`",
+            TokenKind::KeywordString => "`string`",
+            TokenKind::KeywordRune => "`rune`",
+            TokenKind::KeywordDyn => "`dyn`",
+            TokenKind::KeywordTrue => "`true`",
+            TokenKind::KeywordFalse => "`false`",
+            TokenKind::KeywordReturn => "`return`",
+            TokenKind::KeywordIf => "`if`",
+            TokenKind::KeywordElse => "`else`",
+            TokenKind::KeywordWhile => "`while`",
+            TokenKind::KeywordFor => "`for`",
+            TokenKind::KeywordBreak => "`break`",
+            TokenKind::KeywordContinue => "`continue`",
+            TokenKind::KeywordClass => "`class`",
+            TokenKind::Ident => "identifier",
+            TokenKind::Assign => "`=`",
+            TokenKind::NumberLiteral => "number literal",
+            TokenKind::StringLiteral => "string literal",
+            TokenKind::RuneLiteral => "rune literal",
+            TokenKind::ParenOpen => "`(`",
+            TokenKind::ParenClose => "`)`",
+            TokenKind::BraceOpen => "`{`",
+            TokenKind::BraceClose => "`}`",
+            TokenKind::Sep => "`,`",
+            TokenKind::BracketOpen => "`[`",
+            TokenKind::BracketClose => "`]`",
+            TokenKind::Colon => "`:`",
+            TokenKind::Semicolon => "`;`",
+            TokenKind::At => "`@`",
+            TokenKind::Dollar => "`$`",
+            TokenKind::Dot => "`.`",
+            TokenKind::DotDot => "`..`",
+            TokenKind::Ellipsis => "`...`",
+            TokenKind::Plus => "`+`",
+            TokenKind::Minus => "`-`",
+            TokenKind::Star => "`*`",
+            TokenKind::Slash => "`/`",
+            TokenKind::Percent => "`%`",
+            TokenKind::Equal => "`==`",
+            TokenKind::NotEqual => "`!=`",
+            TokenKind::Less => "`<`",
+            TokenKind::LessOrEqual => "`<=`",
+            TokenKind::Greater => "`>`",
+            TokenKind::GreaterOrEqual => "`>=`",
+            TokenKind::And => "`&&`",
+            TokenKind::Or => "`||`",
+            TokenKind::Not => "`!`",
+            TokenKind::PlusAssign => "`+=`",
+            TokenKind::MinusAssign => "`-=`",
+            TokenKind::StarAssign => "`*=`",
+            TokenKind::SlashAssign => "`/=`",
+            TokenKind::PercentAssign => "`%=`",
+            TokenKind::Eof => "end of file",
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub struct Token<'c> {
     kind: TokenKind,
     string: &'c str,
+    span: Span,
 }
 
 impl<'c> Token<'c> {
-    pub const fn of(kind: TokenKind, string: &'c str) -> Token {
-        Token { kind, string }
+    pub const fn of(kind: TokenKind, string: &'c str) -> Token<'c> {
+        Token {
+            kind,
+            string,
+            span: Span::new(0, 0, 0, 0),
+        }
+    }
+
+    pub const fn with_span(self, span: Span) -> Token<'c> {
+        Token { span, ..self }
     }
 
     pub const fn kind(&self) -> TokenKind {
@@ -48,9 +175,13 @@ impl<'c> Token<'c> {
     pub const fn string(&self) -> &'c str {
         self.string
     }
+
+    pub const fn span(&self) -> Span {
+        self.span
+    }
 }
 
-pub const TOKEN_EOF: Token = Token::of(TokenKind::Eof, "");
+pub const TOKEN_EOF: Token<'static> = Token::of(TokenKind::Eof, "");
 
 macro_rules! count {
     () => (0usize);
@@ -60,13 +191,12 @@ macro_rules! count {
 macro_rules! token_matcher {
     ( $(
         $variant:ident => {
-            fn check($check_buf:tt : &str, $check_char_count:tt : usize, $check_character:tt : char) -> bool $check_body:block
-            fn emit($emit_buf:tt : &str) -> Token $emit_body:block
+            fn check($check_buf:tt : &str, $check_char_count:tt : usize, $check_character:tt : char, $check_state:tt : &mut MatchState) -> bool $check_body:block
+            fn emit($emit_buf:tt : &str) -> Option<Token> $emit_body:block
         }
        ),* $(,)? ) => {
         #[derive(Eq, PartialEq, Copy, Clone, Debug)]
         pub enum TokenMatcher {
-            Reset,
             $( $variant ),*
         }
 
@@ -79,21 +209,20 @@ macro_rules! token_matcher {
                 ].into_iter()
             }
 
-            pub fn check(self, buf: &str, char_count: usize, character: char) -> bool {
+            pub fn check(self, buf: &str, char_count: usize, character: char, state: &mut MatchState) -> bool {
                 match self {
-                    TokenMatcher::Reset => false,
                     $( TokenMatcher::$variant => {
-                        fn check_($check_buf: &str, $check_char_count: usize, $check_character: char) -> bool $check_body
-                        check_(buf, char_count, character)
+                        fn check_($check_buf: &str, $check_char_count: usize, $check_character: char, $check_state: &mut MatchState) -> bool $check_body
+                        check_(buf, char_count, character, state)
                     } ),*
                 }
             }
 
-            pub fn emit(self, buf: &str) -> Token {
+            /// Returns token for `buf` or `None` if `buf` is only a prefix of a valid token.
+            pub fn emit(self, buf: &str) -> Option<Token<'_>> {
                 match self {
-                    TokenMatcher::Reset => unreachable!(),
                     $( TokenMatcher::$variant => {
-                        fn emit_($emit_buf: &str) -> Token $emit_body
+                        fn emit_($emit_buf: &str) -> Option<Token<'_>> $emit_body
                         emit_(buf)
                     } ),*
                 }
@@ -102,36 +231,188 @@ macro_rules! token_matcher {
     };
 }
 
+/// What a matcher remembers about the chars it has accepted, so that checking the next char
+/// doesn't require scanning the whole buffer again.
+#[derive(Default)]
+pub struct MatchState {
+    /// Nesting level of block comments which are still open.
+    depth: usize,
+    /// Whether the last char ended a `/*` or `*/` pair, so it can't start another one.
+    pair_end: bool,
+}
+
+impl TokenMatcher {
+    /// Feeds chars of `input` into the matcher while they are accepted and emits the longest
+    /// valid token. Shorter prefixes are tried when the accepted part is not a complete token.
+    pub fn longest_match(self, input: &str) -> Option<Token<'_>> {
+        let mut len = 0;
+        let mut state = MatchState::default();
+        for (char_count, char) in input.chars().enumerate() {
+            if !self.check(&input[..len], char_count, char, &mut state) {
+                break;
+            }
+            len += char.len_utf8();
+        }
+
+        while len > 0 {
+            if let Some(token) = self.emit(&input[..len]) {
+                return Some(token);
+            }
+            len = input[..len].char_indices().next_back().map_or(0, |(offset, _)| offset);
+        }
+        None
+    }
+}
+
 const fn is_line_break(char: char) -> bool {
     char == '\n' || char == '\r'
 }
 
+const OPERATORS: [(&str, TokenKind); 22] = [
+    (".", TokenKind::Dot),
+    ("..", TokenKind::DotDot),
+    ("...", TokenKind::Ellipsis),
+    ("+", TokenKind::Plus),
+    ("-", TokenKind::Minus),
+    ("*", TokenKind::Star),
+    ("/", TokenKind::Slash),
+    ("%", TokenKind::Percent),
+    ("==", TokenKind::Equal),
+    ("!=", TokenKind::NotEqual),
+    ("<", TokenKind::Less),
+    ("<=", TokenKind::LessOrEqual),
+    (">", TokenKind::Greater),
+    (">=", TokenKind::GreaterOrEqual),
+    ("&&", TokenKind::And),
+    ("||", TokenKind::Or),
+    ("!", TokenKind::Not),
+    ("+=", TokenKind::PlusAssign),
+    ("-=", TokenKind::MinusAssign),
+    ("*=", TokenKind::StarAssign),
+    ("/=", TokenKind::SlashAssign),
+    ("%=", TokenKind::PercentAssign),
+];
+
+fn is_plain_decimal(buf: &str) -> bool {
+    buf.chars().all(|char| char.is_ascii_digit() || char == '_' || char == '.')
+}
+
+/// Returns nesting level of block comments which are still open at the end of `buf`.
+fn open_block_comments(buf: &str) -> usize {
+    let mut depth = 0usize;
+    let mut chars = buf.chars().peekable();
+    while let Some(char) = chars.next() {
+        match (char, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                depth += 1;
+            }
+            ('*', Some('/')) => {
+                chars.next();
+                depth = depth.saturating_sub(1);
+            }
+            _ => (),
+        }
+    }
+    depth
+}
+
+pub fn is_terminated_block_comment(buf: &str) -> bool {
+    buf.len() >= 4 && open_block_comments(buf) == 0
+}
+
+// `=` is a prefix of `==` here, but on its own it's matched by `Assign`
+fn is_operator_prefix(buf: &str, char: char) -> bool {
+    OPERATORS
+        .iter()
+        .any(|(operator, _)| operator.starts_with(buf) && operator[buf.len()..].starts_with(char))
+}
+
+fn find_operator(buf: &str) -> Option<TokenKind> {
+    OPERATORS.iter().find(|(operator, _)| *operator == buf).map(|(_, kind)| *kind)
+}
+
 token_matcher! {
     Spaces => {
-        fn check(_: &str, _: usize, char: char) -> bool {
+        fn check(_: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             char == ' ' || char == '\t'
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::Spaces, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Spaces, buf))
         }
     },
     LineBreak => {
-        fn check(_: &str, _: usize, char: char) -> bool {
+        fn check(_: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             is_line_break(char)
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::LineBreak, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::LineBreak, buf))
+        }
+    },
+    DocComment => {
+        fn check(_: &str, char_count: usize, char: char, _: &mut MatchState) -> bool {
+            match char_count {
+                0..=2 => char == '/',
+                3 => char != '/' && !is_line_break(char), // `////` is a plain comment
+                _ => !is_line_break(char),
+            }
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            (buf.len() >= 3).then(|| Token::of(TokenKind::DocComment, buf))
+        }
+    },
+    LineComment => {
+        fn check(_: &str, char_count: usize, char: char, _: &mut MatchState) -> bool {
+            match char_count {
+                0 | 1 => char == '/',
+                _ => !is_line_break(char),
+            }
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            (buf.len() >= 2).then(|| Token::of(TokenKind::Comment, buf))
+        }
+    },
+    BlockComment => {
+        fn check(buf: &str, char_count: usize, char: char, state: &mut MatchState) -> bool {
+            match char_count {
+                0 => char == '/',
+                1 => {
+                    state.depth = 1;
+                    state.pair_end = true;
+                    char == '*'
+                }
+                // the comment is closed by the last char
+                _ if state.depth == 0 => false,
+                _ => {
+                    let last = if state.pair_end { None } else { buf.chars().next_back() };
+                    state.pair_end = match (last, char) {
+                        (Some('/'), '*') => {
+                            state.depth += 1;
+                            true
+                        }
+                        (Some('*'), '/') => {
+                            state.depth -= 1;
+                            true
+                        }
+                        _ => false,
+                    };
+                    true
+                }
+            }
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            (buf.len() >= 2).then(|| Token::of(TokenKind::Comment, buf))
         }
     },
     KeywordOrIdent => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             if buf.is_empty() {
                 char.is_ascii_alphabetic()
             } else {
                 char.is_ascii_alphanumeric()
             }
         }
-        fn emit(buf: &str) -> Token {
+        fn emit(buf: &str) -> Option<Token> {
             let kind = match buf {
                 "void" => TokenKind::KeywordVoid,
                 "byte" => TokenKind::KeywordByte,
@@ -147,77 +428,163 @@ token_matcher! {
                 "string" => TokenKind::KeywordString,
                 "rune" => TokenKind::KeywordRune,
                 "dyn" => TokenKind::KeywordDyn,
+                "true" => TokenKind::KeywordTrue,
+                "false" => TokenKind::KeywordFalse,
+                "return" => TokenKind::KeywordReturn,
+                "if" => TokenKind::KeywordIf,
+                "else" => TokenKind::KeywordElse,
+                "while" => TokenKind::KeywordWhile,
+                "for" => TokenKind::KeywordFor,
+                "break" => TokenKind::KeywordBreak,
+                "continue" => TokenKind::KeywordContinue,
+                "class" => TokenKind::KeywordClass,
                 _ => TokenKind::Ident,
             };
-            Token::of(kind, buf)
+            Some(Token::of(kind, buf))
         }
     },
     Assign => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == '='
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::Assign, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Assign, buf))
         }
     },
-    IntegerLiteral => {
-        fn check(_: &str, _: usize, char: char) -> bool {
-            char.is_ascii_digit()
+    NumberLiteral => {
+        fn check(buf: &str, char_count: usize, char: char, _: &mut MatchState) -> bool {
+            if char_count == 0 {
+                return char.is_ascii_digit();
+            }
+            match char {
+                // fraction can't follow radix prefixes, suffixes or another fraction
+                '.' => is_plain_decimal(buf) && !buf.contains('.'),
+                // sign of the exponent: `1.5e-3`
+                '+' | '-' => buf.strip_suffix(['e', 'E']).is_some_and(is_plain_decimal),
+                _ => char.is_ascii_alphanumeric() || char == '_',
+            }
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::IntegerLiteral, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            // `1.` is not a number, so that `1..5` is lexed as a range
+            let complete = !buf.ends_with(['.', '+', '-']);
+            complete.then(|| Token::of(TokenKind::NumberLiteral, buf))
         }
     },
     StringLiteral => {
-        fn check(buf: &str, char_count: usize, char: char) -> bool {
-            match buf.chars().next_back() {
-                Some(last_char) if char_count != 1 => last_char != '"', // buf.len() > 1
-                None => char == '"', // buf is empty
-                _ => true, // buf.len() == 1
+        fn check(buf: &str, char_count: usize, char: char, _: &mut MatchState) -> bool {
+            match char_count {
+                0 => char == '"',
+                _ => !is_closed_quoted(buf, '"') && !is_line_break(char),
             }
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::StringLiteral, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::StringLiteral, buf))
+        }
+    },
+    RuneLiteral => {
+        fn check(buf: &str, char_count: usize, char: char, _: &mut MatchState) -> bool {
+            match char_count {
+                0 => char == '\'',
+                _ => !is_closed_quoted(buf, '\'') && !is_line_break(char),
+            }
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::RuneLiteral, buf))
         }
     },
     ParenOpen => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == '('
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::ParenOpen, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::ParenOpen, buf))
         }
     },
     ParenClose => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == ')'
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::ParenClose, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::ParenClose, buf))
         }
     },
     BraceOpen => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == '{'
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::BraceOpen, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::BraceOpen, buf))
         }
     },
     BraceClose => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == '}'
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::BraceClose, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::BraceClose, buf))
         }
     },
     Sep => {
-        fn check(buf: &str, _: usize, char: char) -> bool {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
             buf.is_empty() && char == ','
         }
-        fn emit(buf: &str) -> Token {
-            Token::of(TokenKind::Sep, buf)
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Sep, buf))
+        }
+    },    BracketOpen => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == '['
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::BracketOpen, buf))
+        }
+    },
+    BracketClose => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == ']'
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::BracketClose, buf))
+        }
+    },
+    Colon => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == ':'
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Colon, buf))
+        }
+    },
+    Semicolon => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == ';'
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Semicolon, buf))
+        }
+    },
+    At => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == '@'
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::At, buf))
+        }
+    },
+    Dollar => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            buf.is_empty() && char == '$'
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            Some(Token::of(TokenKind::Dollar, buf))
+        }
+    },
+    Operator => {
+        fn check(buf: &str, _: usize, char: char, _: &mut MatchState) -> bool {
+            is_operator_prefix(buf, char)
+        }
+        fn emit(buf: &str) -> Option<Token> {
+            find_operator(buf).map(|kind| Token::of(kind, buf))
         }
     },
 }
@@ -3,31 +3,95 @@
 extern crate core;
 extern crate smallvec;
 
+mod builtins;
 mod codegen;
+mod diagnostics;
+mod error;
 mod ir;
 mod lexer;
 mod parsing;
+mod sema;
 
+use diagnostics::Level;
+use error::CompileError;
 use lexer::Lexer;
 use std::env;
 use std::fs;
+use std::process;
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+/// Prints diagnostics of the problems and exits if some of them are errors, not just warnings.
+fn report(problems: &[CompileError], file_name: &str, source: &str) {
+    let diagnostics: Vec<_> = problems.iter().map(|problem| problem.diagnostic()).collect();
+    diagnostics::emit_all(&diagnostics, file_name, source);
+    if diagnostics.iter().any(|diagnostic| diagnostic.level() == Level::Error) {
+        process::exit(1);
+    }
+}
+
+/// What the transpiler writes to the output.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+enum Emit {
+    C,
+    Ir,
+}
+
+const USAGE: &str = "usage: transpiler [--emit=c|ir] <source file> [output file]";
+
+/// Builds the IR of a source file, or reads it directly from files with the `.ir` extension.
+fn build_program(file_name: &str, source: &str) -> ir::Program {
+    let result = if file_name.ends_with(".ir") {
+        ir::read_program(source).map(|(program, problems)| {
+            report(&problems, file_name, source);
+            program
+        })
+    } else {
+        let lexer = Lexer::new(source);
+        let (ast, errors) = parsing::build_ast(lexer);
+        report(&errors, file_name, source);
+        let (analysis, problems) = sema::analyze(&ast);
+        report(&problems, file_name, source);
+        ir::lower_program(&ast, &analysis)
+    };
+    result.unwrap_or_else(|err| {
+        diagnostics::emit_all([&err.diagnostic()], file_name, source);
+        process::exit(1);
+    })
+}
 
 fn main() {
-    let mut args = env::args().skip(1);
-    let file_to_parse = args.next().expect("nothing to parse");
-    let out_file = args.next().expect("no output file");
-
-    let to_parse = fs::read_to_string(file_to_parse).expect("failed to read file");
-
-    let lexer = Lexer::new(to_parse.as_str());
-    let ast = match parsing::build_ast(lexer) {
-        Ok(ast) => ast,
-        Err(err) => {
-            println!("error parsing code: {}", err);
-            return;
+    let mut emit = Emit::C;
+    let mut files = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.strip_prefix("--emit=") {
+            Some("c") => emit = Emit::C,
+            Some("ir") => emit = Emit::Ir,
+            Some(other) => exit_with_error(&format!("unknown kind of output `{}`, expected `c` or `ir`", other)),
+            None => files.push(arg),
         }
+    }
+    let (file_to_parse, out_file) = match files.as_slice() {
+        [file_to_parse] => (file_to_parse, None),
+        [file_to_parse, out_file] => (file_to_parse, Some(out_file)),
+        _ => exit_with_error(USAGE),
     };
 
-    let code = codegen::generate_c_code(&ast);
-    fs::write(out_file, code).expect("failed to write into file");
+    let to_parse = fs::read_to_string(file_to_parse)
+        .unwrap_or_else(|err| exit_with_error(&format!("failed to read {}: {}", file_to_parse, err)));
+
+    let program = build_program(file_to_parse, &to_parse);
+    let output = match emit {
+        Emit::C => codegen::generate_c_code(&program),
+        Emit::Ir => ir::print_program(&program),
+    };
+    match out_file {
+        Some(out_file) => {
+            fs::write(out_file, output).unwrap_or_else(|err| exit_with_error(&format!("failed to write {}: {}", out_file, err)))
+        }
+        None => print!("{}", output),
+    }
 }
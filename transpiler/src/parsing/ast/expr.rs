@@ -1 +1,175 @@
+use super::*;
+use crate::lexer::TokenKind;
+use stmt::FunctionCall;
 
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn from_token(kind: TokenKind) -> Option<BinaryOp> {
+        let op = match kind {
+            TokenKind::Plus => BinaryOp::Add,
+            TokenKind::Minus => BinaryOp::Sub,
+            TokenKind::Star => BinaryOp::Mul,
+            TokenKind::Slash => BinaryOp::Div,
+            TokenKind::Percent => BinaryOp::Rem,
+            TokenKind::Equal => BinaryOp::Equal,
+            TokenKind::NotEqual => BinaryOp::NotEqual,
+            TokenKind::Less => BinaryOp::Less,
+            TokenKind::LessOrEqual => BinaryOp::LessOrEqual,
+            TokenKind::Greater => BinaryOp::Greater,
+            TokenKind::GreaterOrEqual => BinaryOp::GreaterOrEqual,
+            TokenKind::And => BinaryOp::And,
+            TokenKind::Or => BinaryOp::Or,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Binding power of the operator, operators with higher precedence bind tighter.
+    pub const fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Equal | BinaryOp::NotEqual => 3,
+            BinaryOp::Less | BinaryOp::LessOrEqual | BinaryOp::Greater | BinaryOp::GreaterOrEqual => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessOrEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterOrEqual => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn from_token(kind: TokenKind) -> Option<UnaryOp> {
+        match kind {
+            TokenKind::Minus => Some(UnaryOp::Neg),
+            TokenKind::Not => Some(UnaryOp::Not),
+            _ => None,
+        }
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExprKind<'a> {
+    Literal(Literal),
+    Ident(Ident<'a>),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr<'a>>,
+        right: Box<Expr<'a>>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr<'a>>,
+    },
+    Call(FunctionCall<'a>),
+    Field {
+        object: Box<Expr<'a>>,
+        field: Ident<'a>,
+    },
+    Index {
+        object: Box<Expr<'a>>,
+        index: Box<Expr<'a>>,
+    },
+    Group(Box<Expr<'a>>),
+    /// Array literal: `[1, 2, 3]`.
+    Array(Vec<Expr<'a>>),
+    /// Explicit conversion of a value to a primitive type: `byte(value)`.
+    Conversion {
+        target: Type<'a>,
+        value: Box<Expr<'a>>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Expr<'a> {
+    kind: ExprKind<'a>,
+    span: Span,
+    id: NodeId,
+}
+
+impl<'a> Expr<'a> {
+    pub fn new(kind: ExprKind<'a>, span: Span, id: NodeId) -> Expr<'a> {
+        Expr { kind, span, id }
+    }
+
+    pub fn literal(literal: Literal, id: NodeId) -> Expr<'a> {
+        let span = literal.span();
+        Expr::new(ExprKind::Literal(literal), span, id)
+    }
+
+    pub fn ident(ident: Ident<'a>, id: NodeId) -> Expr<'a> {
+        Expr::new(ExprKind::Ident(ident), ident.span, id)
+    }
+
+    pub fn binary(op: BinaryOp, left: Expr<'a>, right: Expr<'a>, id: NodeId) -> Expr<'a> {
+        let span = left.span.to(right.span);
+        let kind = ExprKind::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        };
+        Expr::new(kind, span, id)
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn kind(&self) -> &ExprKind<'a> {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ExprKind<'a> {
+        self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
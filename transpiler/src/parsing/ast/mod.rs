@@ -1,5 +1,7 @@
 pub mod expr;
 pub mod stmt;
+use crate::lexer::Span;
+use std::borrow::Cow;
 use stmt::Statement;
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
@@ -17,7 +19,73 @@ pub enum Type<'a> {
     String,
     Rune,
     Dyn,
-    Custom { name: &'a str },
+    Custom {
+        name: &'a str,
+    },
+    /// Growable array `[]T`.
+    Array(ArrayId),
+}
+
+/// Number of an expression or identifier, unique in the AST. Spans can't identify nodes, as
+/// different nodes may cover the same text.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct NodeId(pub usize);
+
+/// Index of the element type of the array type in [`AST::arrays`].
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct ArrayId(pub usize);
+
+/// Returns the id of the array type with elements of `element`, adding the type if it is new,
+/// so that equal array types have equal ids.
+pub fn intern_array<'a>(arrays: &mut Vec<Type<'a>>, element: Type<'a>) -> ArrayId {
+    let index = arrays.iter().position(|type_| *type_ == element).unwrap_or_else(|| {
+        arrays.push(element);
+        arrays.len() - 1
+    });
+    ArrayId(index)
+}
+
+impl<'a> Type<'a> {
+    /// Range of values for integer types.
+    pub const fn integer_range(self) -> Option<(i128, i128)> {
+        match self {
+            Type::Byte => Some((u8::MIN as i128, u8::MAX as i128)),
+            Type::Short => Some((i16::MIN as i128, i16::MAX as i128)),
+            Type::UShort => Some((u16::MIN as i128, u16::MAX as i128)),
+            Type::Int => Some((i32::MIN as i128, i32::MAX as i128)),
+            Type::UInt => Some((u32::MIN as i128, u32::MAX as i128)),
+            Type::Long => Some((i64::MIN as i128, i64::MAX as i128)),
+            Type::ULong => Some((u64::MIN as i128, u64::MAX as i128)),
+            _ => None,
+        }
+    }
+
+    pub const fn is_integer(self) -> bool {
+        self.integer_range().is_some()
+    }
+
+    /// Name of the type as it is written in the source code, `arrays` are the element types of
+    /// the array types.
+    pub fn name(self, arrays: &[Type<'a>]) -> Cow<'a, str> {
+        let name = match self {
+            Type::Byte => "byte",
+            Type::Short => "short",
+            Type::UShort => "ushort",
+            Type::Int => "int",
+            Type::UInt => "uint",
+            Type::Long => "long",
+            Type::ULong => "ulong",
+            Type::Double => "double",
+            Type::Financial => "financial",
+            Type::Bool => "bool",
+            Type::String => "string",
+            Type::Rune => "rune",
+            Type::Dyn => "dyn",
+            Type::Custom { name } => name,
+            Type::Array(array) => return Cow::Owned(format!("[]{}", arrays[array.0].name(arrays))),
+        };
+        Cow::Borrowed(name)
+    }
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
@@ -29,6 +97,8 @@ pub enum TypeOrVoid<'a> {
 #[derive(Copy, Clone, Debug)]
 pub struct Ident<'a> {
     pub name: &'a str,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 impl<'a> PartialEq<&str> for Ident<'a> {
@@ -37,31 +107,68 @@ impl<'a> PartialEq<&str> for Ident<'a> {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-pub enum Literal<'a> {
-    Integer(i32),
-    String(&'a str),
+#[derive(Clone, Debug)]
+pub enum LiteralKind {
+    /// Integer of any width, `suffix` is the type written after the digits: `255byte`.
+    Integer {
+        value: u128,
+        suffix: Option<Type<'static>>,
+    },
+    Double(f64),
+    /// Exact decimal value of `financial` type: `digits * 10^-scale`.
+    Decimal {
+        digits: u128,
+        scale: u32,
+    },
+    /// Value with escape sequences already replaced.
+    String(String),
+    Rune(char),
+    Bool(bool),
+}
+
+#[derive(Clone, Debug)]
+pub struct Literal {
+    kind: LiteralKind,
+    span: Span,
 }
 
-impl<'a> Literal<'a> {
-    pub fn integer(val: i32) -> Literal<'a> {
-        Literal::Integer(val)
+impl Literal {
+    pub fn new(kind: LiteralKind, span: Span) -> Literal {
+        Literal { kind, span }
     }
 
-    pub fn string(val: &'a str) -> Literal<'a> {
-        Literal::String(val)
+    pub fn string(val: String, span: Span) -> Literal {
+        Literal::new(LiteralKind::String(val), span)
+    }
+
+    pub fn rune(val: char, span: Span) -> Literal {
+        Literal::new(LiteralKind::Rune(val), span)
+    }
+
+    pub fn bool(val: bool, span: Span) -> Literal {
+        Literal::new(LiteralKind::Bool(val), span)
+    }
+
+    pub fn kind(&self) -> &LiteralKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
     }
 }
 
+// codegen doesn't emit parameters yet
 #[derive(Debug)]
 pub struct FunctionArg<'a> {
     arg_type: Type<'a>,
     name: Ident<'a>,
+    span: Span,
 }
 
 impl<'a> FunctionArg<'a> {
-    pub fn new(arg_type: Type<'a>, name: Ident<'a>) -> FunctionArg<'a> {
-        FunctionArg { arg_type, name }
+    pub fn new(arg_type: Type<'a>, name: Ident<'a>, span: Span) -> FunctionArg<'a> {
+        FunctionArg { arg_type, name, span }
     }
 
     pub fn arg_type(&self) -> Type<'a> {
@@ -71,6 +178,10 @@ impl<'a> FunctionArg<'a> {
     pub fn name(&self) -> Ident<'a> {
         self.name
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 #[derive(Debug)]
@@ -79,6 +190,8 @@ pub struct FunctionDeclaration<'a> {
     name: Ident<'a>,
     args: Vec<FunctionArg<'a>>,
     stmts: Vec<Statement<'a>>,
+    span: Span,
+    docs: Vec<&'a str>,
 }
 
 impl<'a> FunctionDeclaration<'a> {
@@ -87,15 +200,23 @@ impl<'a> FunctionDeclaration<'a> {
         name: Ident<'a>,
         args: Vec<FunctionArg<'a>>,
         stmts: Vec<Statement<'a>>,
+        span: Span,
     ) -> FunctionDeclaration<'a> {
         FunctionDeclaration {
             return_type,
             name,
             args,
             stmts,
+            span,
+            docs: Vec::new(),
         }
     }
 
+    pub fn with_docs(mut self, docs: Vec<&'a str>) -> FunctionDeclaration<'a> {
+        self.docs = docs;
+        self
+    }
+
     pub fn return_type(&self) -> TypeOrVoid<'a> {
         self.return_type
     }
@@ -111,20 +232,133 @@ impl<'a> FunctionDeclaration<'a> {
     pub fn stmts(&self) -> &[Statement<'a>] {
         self.stmts.as_slice()
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Lines of `///` comments written before the declaration.
+    pub fn docs(&self) -> &[&'a str] {
+        self.docs.as_slice()
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassField<'a> {
+    field_type: Type<'a>,
+    name: Ident<'a>,
+    span: Span,
+}
+
+impl<'a> ClassField<'a> {
+    pub fn new(field_type: Type<'a>, name: Ident<'a>, span: Span) -> ClassField<'a> {
+        ClassField { field_type, name, span }
+    }
+
+    pub fn field_type(&self) -> Type<'a> {
+        self.field_type
+    }
+
+    pub fn name(&self) -> Ident<'a> {
+        self.name
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassDeclaration<'a> {
+    name: Ident<'a>,
+    fields: Vec<ClassField<'a>>,
+    span: Span,
+    docs: Vec<&'a str>,
+    value: bool,
+}
+
+impl<'a> ClassDeclaration<'a> {
+    pub fn new(name: Ident<'a>, fields: Vec<ClassField<'a>>, span: Span) -> ClassDeclaration<'a> {
+        ClassDeclaration {
+            name,
+            fields,
+            span,
+            docs: Vec::new(),
+            value: false,
+        }
+    }
+
+    pub fn with_docs(mut self, docs: Vec<&'a str>) -> ClassDeclaration<'a> {
+        self.docs = docs;
+        self
+    }
+
+    /// Makes it a `value class`.
+    pub fn with_value(mut self, value: bool) -> ClassDeclaration<'a> {
+        self.value = value;
+        self
+    }
+
+    pub fn name(&self) -> Ident<'a> {
+        self.name
+    }
+
+    /// Fields in the order of declaration, which is also the order of the constructor params.
+    pub fn fields(&self) -> &[ClassField<'a>] {
+        self.fields.as_slice()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Lines of `///` comments written before the declaration.
+    pub fn docs(&self) -> &[&'a str] {
+        self.docs.as_slice()
+    }
+
+    /// Whether objects of the class are immutable values which are copied on assignment,
+    /// instead of references to a shared object.
+    pub fn is_value(&self) -> bool {
+        self.value
+    }
+}
+
+/// Top-level declaration of the file.
+#[derive(Debug)]
+pub enum Declaration<'a> {
+    Function(FunctionDeclaration<'a>),
+    Class(ClassDeclaration<'a>),
 }
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub struct AST<'a> {
-    declarations: Vec<FunctionDeclaration<'a>>,
+    declarations: Vec<Declaration<'a>>,
+    /// Element types of the array types used in the program.
+    arrays: Vec<Type<'a>>,
 }
 
 impl<'a> AST<'a> {
-    pub fn new(declarations: Vec<FunctionDeclaration<'a>>) -> AST<'a> {
-        AST { declarations }
+    pub fn new(declarations: Vec<Declaration<'a>>, arrays: Vec<Type<'a>>) -> AST<'a> {
+        AST { declarations, arrays }
+    }
+
+    pub fn arrays(&self) -> &[Type<'a>] {
+        self.arrays.as_slice()
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionDeclaration<'a>> {
+        self.declarations.iter().filter_map(|decl| match decl {
+            Declaration::Function(function) => Some(function),
+            _ => None,
+        })
     }
 
-    pub fn declarations(&self) -> &[FunctionDeclaration<'a>] {
-        self.declarations.as_slice()
+    pub fn classes(&self) -> impl Iterator<Item = &ClassDeclaration<'a>> {
+        self.declarations.iter().filter_map(|decl| match decl {
+            Declaration::Class(class) => Some(class),
+            _ => None,
+        })
     }
 }
@@ -1,10 +1,14 @@
+use super::expr::{BinaryOp, Expr};
 use super::*;
+use crate::lexer::TokenKind;
 
+// codegen doesn't support variables yet
 #[derive(Debug)]
 pub struct VariableDeclaration<'a> {
     var_type: Type<'a>,
     name: Ident<'a>,
-    value: Literal<'a>,
+    value: Expr<'a>,
+    span: Span,
 }
 
 impl<'a> VariableDeclaration<'a> {
@@ -16,41 +20,342 @@ impl<'a> VariableDeclaration<'a> {
         self.name
     }
 
-    pub fn value(&self) -> Literal<'a> {
-        self.value
+    pub fn value(&self) -> &Expr<'a> {
+        &self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
     }
 }
 
 impl<'a> VariableDeclaration<'a> {
-    pub fn new(var_type: Type<'a>, name: Ident<'a>, value: Literal<'a>) -> VariableDeclaration<'a> {
-        VariableDeclaration { var_type, name, value }
+    pub fn new(var_type: Type<'a>, name: Ident<'a>, value: Expr<'a>, span: Span) -> VariableDeclaration<'a> {
+        VariableDeclaration {
+            var_type,
+            name,
+            value,
+            span,
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct FunctionCall<'a> {
     name: Ident<'a>,
-    args: Vec<Literal<'a>>,
+    args: Vec<Expr<'a>>,
+    span: Span,
 }
 
 impl<'a> FunctionCall<'a> {
-    pub fn new(name: Ident<'a>, args: Vec<Literal<'a>>) -> FunctionCall<'a> {
-        FunctionCall { name, args }
+    pub fn new(name: Ident<'a>, args: Vec<Expr<'a>>, span: Span) -> FunctionCall<'a> {
+        FunctionCall { name, args, span }
     }
 
     pub fn name(&self) -> Ident<'a> {
         self.name
     }
 
-    pub fn args(&self) -> &[Literal<'a>] {
+    pub fn args(&self) -> &[Expr<'a>] {
         self.args.as_slice()
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug)]
+pub struct Return<'a> {
+    value: Option<Expr<'a>>,
+    span: Span,
+}
+
+impl<'a> Return<'a> {
+    pub fn new(value: Option<Expr<'a>>, span: Span) -> Return<'a> {
+        Return { value, span }
+    }
+
+    /// Returned value, `None` in `void` functions.
+    pub fn value(&self) -> Option<&Expr<'a>> {
+        self.value.as_ref()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum AssignOp {
+    Assign,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl AssignOp {
+    pub fn from_token(kind: TokenKind) -> Option<AssignOp> {
+        let op = match kind {
+            TokenKind::Assign => AssignOp::Assign,
+            TokenKind::PlusAssign => AssignOp::Add,
+            TokenKind::MinusAssign => AssignOp::Sub,
+            TokenKind::StarAssign => AssignOp::Mul,
+            TokenKind::SlashAssign => AssignOp::Div,
+            TokenKind::PercentAssign => AssignOp::Rem,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Binary operation which compound assignment applies to the target, `None` for plain `=`.
+    pub const fn binary_op(self) -> Option<BinaryOp> {
+        match self {
+            AssignOp::Assign => None,
+            AssignOp::Add => Some(BinaryOp::Add),
+            AssignOp::Sub => Some(BinaryOp::Sub),
+            AssignOp::Mul => Some(BinaryOp::Mul),
+            AssignOp::Div => Some(BinaryOp::Div),
+            AssignOp::Rem => Some(BinaryOp::Rem),
+        }
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            AssignOp::Assign => "=",
+            AssignOp::Add => "+=",
+            AssignOp::Sub => "-=",
+            AssignOp::Mul => "*=",
+            AssignOp::Div => "/=",
+            AssignOp::Rem => "%=",
+        }
+    }
+}
+
+/// Assignment to a variable, field or array element: `count += 1`.
+#[derive(Debug)]
+pub struct Assignment<'a> {
+    target: Expr<'a>,
+    op: AssignOp,
+    value: Expr<'a>,
+    span: Span,
+}
+
+impl<'a> Assignment<'a> {
+    pub fn new(target: Expr<'a>, op: AssignOp, value: Expr<'a>) -> Assignment<'a> {
+        let span = target.span().to(value.span());
+        Assignment { target, op, value, span }
+    }
+
+    pub fn target(&self) -> &Expr<'a> {
+        &self.target
+    }
+
+    pub fn op(&self) -> AssignOp {
+        self.op
+    }
+
+    pub fn value(&self) -> &Expr<'a> {
+        &self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Statements in braces, which form a scope for the variables declared in them.
+#[derive(Debug)]
+pub struct Block<'a> {
+    stmts: Vec<Statement<'a>>,
+    span: Span,
+}
+
+impl<'a> Block<'a> {
+    pub fn new(stmts: Vec<Statement<'a>>, span: Span) -> Block<'a> {
+        Block { stmts, span }
+    }
+
+    pub fn stmts(&self) -> &[Statement<'a>] {
+        self.stmts.as_slice()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug)]
+pub enum Else<'a> {
+    If(Box<If<'a>>),
+    Block(Block<'a>),
+}
+
+#[derive(Debug)]
+pub struct If<'a> {
+    condition: Expr<'a>,
+    then_block: Block<'a>,
+    else_branch: Option<Else<'a>>,
+    span: Span,
+}
+
+impl<'a> If<'a> {
+    pub fn new(condition: Expr<'a>, then_block: Block<'a>, else_branch: Option<Else<'a>>, span: Span) -> If<'a> {
+        If {
+            condition,
+            then_block,
+            else_branch,
+            span,
+        }
+    }
+
+    pub fn condition(&self) -> &Expr<'a> {
+        &self.condition
+    }
+
+    pub fn then_block(&self) -> &Block<'a> {
+        &self.then_block
+    }
+
+    pub fn else_branch(&self) -> Option<&Else<'a>> {
+        self.else_branch.as_ref()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug)]
+pub struct While<'a> {
+    condition: Expr<'a>,
+    body: Block<'a>,
+    span: Span,
+}
+
+impl<'a> While<'a> {
+    pub fn new(condition: Expr<'a>, body: Block<'a>, span: Span) -> While<'a> {
+        While { condition, body, span }
+    }
+
+    pub fn condition(&self) -> &Expr<'a> {
+        &self.condition
+    }
+
+    pub fn body(&self) -> &Block<'a> {
+        &self.body
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// C-style loop: `for (int i = 0; i < 10; i += 1) { ... }`, every part of the header may be omitted.
+#[derive(Debug)]
+pub struct For<'a> {
+    init: Option<Box<Statement<'a>>>,
+    condition: Option<Expr<'a>>,
+    step: Option<Box<Statement<'a>>>,
+    body: Block<'a>,
+    span: Span,
+}
+
+impl<'a> For<'a> {
+    pub fn new(
+        init: Option<Statement<'a>>,
+        condition: Option<Expr<'a>>,
+        step: Option<Statement<'a>>,
+        body: Block<'a>,
+        span: Span,
+    ) -> For<'a> {
+        For {
+            init: init.map(Box::new),
+            condition,
+            step: step.map(Box::new),
+            body,
+            span,
+        }
+    }
+
+    pub fn init(&self) -> Option<&Statement<'a>> {
+        self.init.as_deref()
+    }
+
+    pub fn condition(&self) -> Option<&Expr<'a>> {
+        self.condition.as_ref()
+    }
+
+    pub fn step(&self) -> Option<&Statement<'a>> {
+        self.step.as_deref()
+    }
+
+    pub fn body(&self) -> &Block<'a> {
+        &self.body
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Loop over elements of an array: `for (Location loc : locations) { ... }`.
+#[derive(Debug)]
+pub struct ForEach<'a> {
+    item_type: Type<'a>,
+    item: Ident<'a>,
+    iterable: Expr<'a>,
+    body: Block<'a>,
+    span: Span,
+}
+
+impl<'a> ForEach<'a> {
+    pub fn new(item_type: Type<'a>, item: Ident<'a>, iterable: Expr<'a>, body: Block<'a>, span: Span) -> ForEach<'a> {
+        ForEach {
+            item_type,
+            item,
+            iterable,
+            body,
+            span,
+        }
+    }
+
+    pub fn item_type(&self) -> Type<'a> {
+        self.item_type
+    }
+
+    pub fn item(&self) -> Ident<'a> {
+        self.item
+    }
+
+    pub fn iterable(&self) -> &Expr<'a> {
+        &self.iterable
+    }
+
+    pub fn body(&self) -> &Block<'a> {
+        &self.body
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 #[derive(Debug)]
 pub enum Statement<'a> {
     VariableDeclaration(VariableDeclaration<'a>),
     FunctionCall(FunctionCall<'a>),
+    Assignment(Assignment<'a>),
+    Return(Return<'a>),
+    If(If<'a>),
+    While(While<'a>),
+    For(For<'a>),
+    ForEach(ForEach<'a>),
+    Break(Span),
+    Continue(Span),
+    Block(Block<'a>),
 }
 
 impl<'a> Statement<'a> {
@@ -61,4 +366,8 @@ impl<'a> Statement<'a> {
     pub fn function_call(val: FunctionCall<'a>) -> Statement<'a> {
         Statement::FunctionCall(val)
     }
+
+    pub fn return_(val: Return<'a>) -> Statement<'a> {
+        Statement::Return(val)
+    }
 }
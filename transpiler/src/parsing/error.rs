@@ -1,19 +1,89 @@
-use crate::lexer::TokenKind;
+use crate::diagnostics::Diagnostic;
+use crate::error::CompileError;
+use crate::lexer::{Span, Token, TokenKind};
 use smallvec::SmallVec;
 use std::error;
 use std::fmt;
 
+#[derive(Debug)]
+enum Problem {
+    UnexpectedToken {
+        found: TokenKind,
+        expected: SmallVec<[TokenKind; 4]>,
+        expected_what: Option<&'static str>,
+    },
+    /// Specific identifier is expected, e.g. a field name in the textual IR.
+    ExpectedWord { found: TokenKind, word: &'static str },
+    /// Tokens are fine on their own, but they don't form a valid construct.
+    Invalid(&'static str),
+}
+
 #[derive(Debug)]
 pub struct ParsingError {
-    found: TokenKind,
-    expected: SmallVec<[TokenKind; 4]>,
+    problem: Problem,
+    span: Span,
+    help: Option<&'static str>,
 }
 
 impl ParsingError {
-    pub fn new<I: IntoIterator<Item = TokenKind>>(found: TokenKind, expected: I) -> ParsingError {
+    pub fn new<I: IntoIterator<Item = TokenKind>>(found: Token, expected: I) -> ParsingError {
+        ParsingError {
+            problem: Problem::UnexpectedToken {
+                found: found.kind(),
+                expected: SmallVec::from_iter(expected),
+                expected_what: None,
+            },
+            span: found.span(),
+            help: None,
+        }
+    }
+
+    /// Creates error for a place where a whole class of tokens is expected, e.g. "a type".
+    pub fn expected(found: Token, what: &'static str) -> ParsingError {
+        ParsingError {
+            problem: Problem::UnexpectedToken {
+                found: found.kind(),
+                expected: SmallVec::new(),
+                expected_what: Some(what),
+            },
+            span: found.span(),
+            help: None,
+        }
+    }
+
+    pub fn expected_word(found: Token, word: &'static str) -> ParsingError {
         ParsingError {
-            found,
-            expected: SmallVec::from_iter(expected),
+            problem: Problem::ExpectedWord {
+                found: found.kind(),
+                word,
+            },
+            span: found.span(),
+            help: None,
+        }
+    }
+
+    pub fn invalid(message: &'static str, span: Span) -> ParsingError {
+        ParsingError {
+            problem: Problem::Invalid(message),
+            span,
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: &'static str) -> ParsingError {
+        self.help = Some(help);
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span);
+        match self.help {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
         }
     }
 }
@@ -23,14 +93,16 @@ fn format_expected(expected: &[TokenKind]) -> String {
     match iter.next() {
         Some(first) => match iter.next_back() {
             Some(last) => {
-                let mut string = format!("{:?} | ", first);
+                let mut string = first.describe().to_string();
                 for kind in iter {
-                    string.push_str(format!("{:?} | ", kind).as_str());
+                    string.push_str(", ");
+                    string.push_str(kind.describe());
                 }
-                string.push_str(format!("{:?}", last).as_str());
+                string.push_str(" or ");
+                string.push_str(last.describe());
                 string
             }
-            None => format!("{:?}", first),
+            None => first.describe().to_string(),
         },
         None => String::new(),
     }
@@ -38,11 +110,24 @@ fn format_expected(expected: &[TokenKind]) -> String {
 
 impl fmt::Display for ParsingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.expected.is_empty() {
-            f.write_fmt(format_args!("unexpected token {:?}", self.found))?;
+        let (found, expected, expected_what) = match &self.problem {
+            Problem::UnexpectedToken {
+                found,
+                expected,
+                expected_what,
+            } => (found, expected, expected_what),
+            Problem::ExpectedWord { found, word } => {
+                return f.write_fmt(format_args!("expected `{}`, but found {}", word, found.describe()))
+            }
+            Problem::Invalid(message) => return f.write_str(message),
+        };
+        if let Some(what) = expected_what {
+            f.write_fmt(format_args!("expected {}, but found {}", what, found.describe()))?;
+        } else if expected.is_empty() {
+            f.write_fmt(format_args!("unexpected {}", found.describe()))?;
         } else {
-            let expected = format_expected(self.expected.as_slice());
-            f.write_fmt(format_args!("expected {}, but found {:?}", expected, self.found))?;
+            let expected = format_expected(expected.as_slice());
+            f.write_fmt(format_args!("expected {}, but found {}", expected, found.describe()))?;
         }
         Ok(())
     }
@@ -50,4 +135,4 @@ impl fmt::Display for ParsingError {
 
 impl error::Error for ParsingError {}
 
-pub type Result<T> = std::result::Result<T, ParsingError>;
+pub type Result<T> = std::result::Result<T, CompileError>;
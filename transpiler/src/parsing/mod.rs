@@ -1,12 +1,17 @@
 mod error;
+mod number;
 mod parser;
-mod seq;
 
 pub mod ast;
+pub mod seq;
 
+pub use error::ParsingError;
+
+use crate::error::CompileError;
 use crate::lexer::Lexer;
-use error::Result;
 
-pub fn build_ast(lexer: Lexer) -> Result<ast::AST> {
+/// Builds AST from the token stream. The AST is returned even if there are errors,
+/// in which case it contains only the parts of the code that were parsed successfully.
+pub fn build_ast(lexer: Lexer) -> (ast::AST, Vec<CompileError>) {
     parser::Parser::new(lexer).parse()
 }
@@ -0,0 +1,186 @@
+use super::ast::{LiteralKind, Type};
+use crate::lexer::{LexError, LexErrorKind, Token};
+
+// longer suffixes go first, so that `uint` is not taken for `int`
+const SUFFIXES: [(&str, Type<'static>); 9] = [
+    ("financial", Type::Financial),
+    ("ushort", Type::UShort),
+    ("double", Type::Double),
+    ("ulong", Type::ULong),
+    ("short", Type::Short),
+    ("uint", Type::UInt),
+    ("long", Type::Long),
+    ("byte", Type::Byte),
+    ("int", Type::Int),
+];
+
+fn split_suffix(text: &str) -> (&str, Option<Type<'static>>) {
+    for (name, suffix) in SUFFIXES {
+        if let Some(body) = text.strip_suffix(name) {
+            if !body.is_empty() {
+                return (body, Some(suffix));
+            }
+        }
+    }
+    (text, None)
+}
+
+fn split_radix(body: &str) -> (&str, u32) {
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = body
+            .strip_prefix(prefix)
+            .or_else(|| body.strip_prefix(&prefix.to_uppercase()))
+        {
+            return (digits, radix);
+        }
+    }
+    (body, 10)
+}
+
+fn parse_decimal(body: &str) -> Option<(u128, u32)> {
+    let (integer, fraction) = body.split_once('.').unwrap_or((body, ""));
+    let all_digits = |part: &str| part.chars().all(|char| char.is_ascii_digit());
+    if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+        return None;
+    }
+    let digits = format!("{}{}", integer, fraction).parse::<u128>().ok()?;
+    Some((digits, fraction.len() as u32))
+}
+
+/// Parses number literal token: decimal, `0x`, `0o` or `0b` integer, floating point number,
+/// with optional `_` separators and type suffix (`10ulong`, `0.5financial`).
+/// Integers with suffix are checked to fit in the suffix type.
+pub fn parse_number_literal(token: Token) -> Result<LiteralKind, LexError> {
+    let invalid = || LexError::new(LexErrorKind::InvalidNumber, token.span());
+    let out_of_range = || LexError::new(LexErrorKind::IntegerOutOfRange, token.span());
+    // `parse` rounds literals which are too big to infinity
+    let parse_double = |digits: &str| match digits.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(LiteralKind::Double(value)),
+        Ok(_) => Err(LexError::new(LexErrorKind::DoubleOutOfRange, token.span())),
+        Err(_) => Err(invalid()),
+    };
+
+    let text = token.string().replace('_', "");
+    let (body, suffix) = split_suffix(text.as_str());
+    let (digits, radix) = split_radix(body);
+    let is_float = radix == 10 && digits.contains(['.', 'e', 'E']);
+
+    match suffix {
+        Some(Type::Financial) => {
+            let (digits, scale) = parse_decimal(digits).ok_or_else(invalid)?;
+            Ok(LiteralKind::Decimal { digits, scale })
+        }
+        Some(Type::Double) if radix == 10 => parse_double(digits),
+        None if is_float => parse_double(digits),
+        _ if is_float || suffix == Some(Type::Double) => Err(invalid()),
+        _ => {
+            if digits.is_empty() || !digits.chars().all(|char| char.is_digit(radix)) {
+                return Err(invalid());
+            }
+            let value = u128::from_str_radix(digits, radix).map_err(|_| out_of_range())?;
+            let (_, max) = suffix
+                .unwrap_or(Type::ULong)
+                .integer_range()
+                .expect("suffix is an integer type");
+            if value > max as u128 {
+                return Err(out_of_range());
+            }
+            Ok(LiteralKind::Integer { value, suffix })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_number_literal;
+    use crate::lexer::{LexError, LexErrorKind, Lexer};
+    use crate::parsing::ast::{LiteralKind, Type};
+
+    fn parse(text: &str) -> Result<LiteralKind, LexError> {
+        let token = Lexer::new(text).next().unwrap();
+        assert_eq!(token.string(), text, "the literal is lexed as one token");
+        parse_number_literal(token)
+    }
+
+    fn assert_integer(text: &str, expected: u128, expected_suffix: Option<Type>) {
+        match parse(text) {
+            Ok(LiteralKind::Integer { value, suffix }) => assert_eq!((value, suffix), (expected, expected_suffix), "{}", text),
+            other => panic!("{}: {:?}", text, other),
+        }
+    }
+
+    fn assert_double(text: &str, expected: f64) {
+        match parse(text) {
+            Ok(LiteralKind::Double(value)) => assert_eq!(value, expected, "{}", text),
+            other => panic!("{}: {:?}", text, other),
+        }
+    }
+
+    fn assert_error(text: &str, kind: LexErrorKind) {
+        let token = Lexer::new(text).next().unwrap();
+        assert_eq!(parse(text).unwrap_err(), LexError::new(kind, token.span()), "{}", text);
+    }
+
+    #[test]
+    fn parses_radixes() {
+        assert_integer("0", 0, None);
+        assert_integer("1234567890", 1234567890, None);
+        assert_integer("0x1F", 31, None);
+        assert_integer("0XfF", 255, None);
+        assert_integer("0o17", 15, None);
+        assert_integer("0b1010", 10, None);
+    }
+
+    #[test]
+    fn skips_separators() {
+        assert_integer("1_000_000", 1_000_000, None);
+        assert_integer("0xFF_FF", 0xFFFF, None);
+        assert_integer("0b_1_0", 2, None);
+        assert_double("1_000.5", 1000.5);
+    }
+
+    #[test]
+    fn parses_suffixes() {
+        assert_integer("255byte", 255, Some(Type::Byte));
+        assert_integer("7ushort", 7, Some(Type::UShort));
+        assert_integer("7uint", 7, Some(Type::UInt));
+        assert_integer("7int", 7, Some(Type::Int));
+        assert_integer("0xFFulong", 255, Some(Type::ULong));
+        assert_double("2double", 2.0);
+        assert_double("1.5e-3", 0.0015);
+        match parse("12.50financial") {
+            Ok(LiteralKind::Decimal { digits, scale }) => assert_eq!((digits, scale), (1250, 2)),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_invalid_literals() {
+        assert_error("0x", LexErrorKind::InvalidNumber);
+        assert_error("0b102", LexErrorKind::InvalidNumber);
+        assert_error("0o8", LexErrorKind::InvalidNumber);
+        assert_error("1.5int", LexErrorKind::InvalidNumber);
+        assert_error("0x10double", LexErrorKind::InvalidNumber);
+    }
+
+    #[test]
+    fn reports_integers_out_of_range() {
+        assert_integer("18446744073709551615", u64::MAX as u128, None);
+        assert_error("18446744073709551616", LexErrorKind::IntegerOutOfRange);
+        assert_integer("255byte", 255, Some(Type::Byte));
+        assert_error("256byte", LexErrorKind::IntegerOutOfRange);
+        assert_integer("2147483647int", 2147483647, Some(Type::Int));
+        assert_error("2147483648int", LexErrorKind::IntegerOutOfRange);
+        assert_error("4294967296uint", LexErrorKind::IntegerOutOfRange);
+        assert_error("0x1_0000_0000_0000_0000_0000_0000_0000_0000", LexErrorKind::IntegerOutOfRange);
+    }
+
+    #[test]
+    fn reports_doubles_which_overflow() {
+        assert_double("1.7976931348623157e308", f64::MAX);
+        assert_error("1e400", LexErrorKind::DoubleOutOfRange);
+        assert_error("1e309double", LexErrorKind::DoubleOutOfRange);
+        // too small numbers are rounded to zero
+        assert_double("1e-400", 0.0);
+    }
+}
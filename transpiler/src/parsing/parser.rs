@@ -1,47 +1,146 @@
 use super::{
     ast,
     error::{ParsingError, Result},
-    seq::TokenSeq,
+    seq::{is_literal, is_type_start, TokenSeq},
 };
-use crate::lexer::{Lexer, TokenKind, TOKEN_EOF};
+use crate::error::CompileError;
+use crate::lexer::{Lexer, Token, TokenKind};
+use ast::expr::{BinaryOp, Expr, ExprKind, UnaryOp};
 use ast::stmt;
 
 pub struct Parser<'c> {
     seq: TokenSeq<'c>,
+    errors: Vec<CompileError>,
+    /// Depth of parens and brackets around the current expression. Inside of them line breaks
+    /// don't end the statement, so an expression may be continued on the next line.
+    nesting: usize,
 }
 
 impl<'c> Parser<'c> {
     pub fn new(lexer: Lexer<'c>) -> Parser<'c> {
         Parser {
             seq: TokenSeq::new(lexer),
+            errors: Vec::new(),
+            nesting: 0,
         }
     }
 
-    pub fn parse(&mut self) -> Result<ast::AST<'c>> {
+    /// Parses the whole token sequence. Parsing doesn't stop on the first error: the parser
+    /// skips the broken statement or declaration and continues, so the returned AST contains
+    /// everything that could be parsed and the errors contain every problem found.
+    pub fn parse(mut self) -> (ast::AST<'c>, Vec<CompileError>) {
         let mut declarations = Vec::new();
         loop {
-            let token = self.seq.solid_token();
-            if token == TOKEN_EOF {
-                return Ok(ast::AST::new(declarations));
+            let token = match self.seq.solid_token() {
+                Ok(token) => token,
+                Err(err) => {
+                    self.errors.push(err);
+                    continue;
+                }
+            };
+            if token.kind() == TokenKind::Eof {
+                return (ast::AST::new(declarations, self.seq.take_arrays()), self.errors);
+            }
+            match self.parse_declaration() {
+                Ok(declaration) => declarations.push(declaration),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.recover_declaration();
+                }
             }
-            let declaration = self.parse_function()?;
-            declarations.push(declaration);
         }
     }
 
+    /// Returns the current token, recording lexer errors met on the way.
+    fn recovering_token(&mut self) -> Token<'c> {
+        loop {
+            match self.seq.current_token() {
+                Ok(token) => return token,
+                Err(err) => self.errors.push(err),
+            }
+        }
+    }
+
+    /// Skips tokens until the start of the next line which is outside of any braces
+    /// and begins with something that looks like a declaration.
+    fn recover_declaration(&mut self) {
+        let mut depth = 0usize;
+        let mut line_start = false;
+        loop {
+            let token = self.recovering_token();
+            match token.kind() {
+                TokenKind::Eof => return,
+                kind if depth == 0
+                    && line_start
+                    && (is_type_start(kind) || matches!(kind, TokenKind::KeywordVoid | TokenKind::KeywordClass)) =>
+                {
+                    return
+                }
+                TokenKind::LineBreak => line_start = true,
+                TokenKind::Spaces | TokenKind::Comment | TokenKind::DocComment => (),
+                TokenKind::BraceOpen => {
+                    depth += 1;
+                    line_start = false;
+                }
+                TokenKind::BraceClose => {
+                    depth = depth.saturating_sub(1);
+                    line_start = false;
+                }
+                _ => line_start = false,
+            }
+            self.seq.go_next();
+        }
+    }
+
+    /// Skips tokens until the end of the current statement. Line break is passed,
+    /// while `}` closing the enclosing block is left for the block parser.
+    fn recover_statement(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            match self.recovering_token().kind() {
+                TokenKind::Eof => return,
+                TokenKind::LineBreak if depth == 0 => {
+                    self.seq.go_next();
+                    return;
+                }
+                TokenKind::BraceClose if depth == 0 => return,
+                TokenKind::BraceOpen => depth += 1,
+                TokenKind::BraceClose => depth -= 1,
+                _ => (),
+            }
+            self.seq.go_next();
+        }
+    }
+
+    /// Parses nodes until `needle` is found. Errors in a node are recorded and the parser
+    /// continues from the next statement.
     fn parse_until<N, F>(&mut self, needle: TokenKind, parse: F) -> Result<Vec<N>>
     where
         F: Fn(&mut Parser<'c>) -> Result<N>,
     {
         let mut result = Vec::<N>::new();
         loop {
-            let token = self.seq.solid_token();
+            let token = match self.seq.solid_token() {
+                Ok(token) => token,
+                Err(err) => {
+                    self.errors.push(err);
+                    continue;
+                }
+            };
             if token.kind() == needle {
                 self.seq.go_next();
                 break;
             }
-            let node = parse(self)?;
-            result.push(node);
+            if token.kind() == TokenKind::Eof {
+                return Err(ParsingError::new(token, [needle]).into());
+            }
+            match parse(self) {
+                Ok(node) => result.push(node),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.recover_statement();
+                }
+            }
         }
         Ok(result)
     }
@@ -55,7 +154,8 @@ impl<'c> Parser<'c> {
         let mut result = Vec::<N>::new();
         let mut first = true;
         loop {
-            let kind = self.seq.solid_token().kind();
+            let token = self.seq.solid_token()?;
+            let kind = token.kind();
             if kind == needle {
                 self.seq.go_next();
                 break;
@@ -64,7 +164,7 @@ impl<'c> Parser<'c> {
                 if kind == TokenKind::Sep {
                     self.seq.go_next();
                 } else {
-                    return Err(ParsingError::new(kind, [TokenKind::Sep, needle]));
+                    return Err(ParsingError::new(token, [TokenKind::Sep, needle]).into());
                 }
             }
             let node = parse(self)?;
@@ -74,50 +174,635 @@ impl<'c> Parser<'c> {
         Ok(result)
     }
 
+    /// Runs `parse` for a part of expression enclosed in parens or brackets.
+    fn nested<T, F>(&mut self, parse: F) -> Result<T>
+    where
+        F: FnOnce(&mut Parser<'c>) -> Result<T>,
+    {
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    /// Returns the token which may continue the expression: a binary or postfix operator.
+    fn continuation_token(&mut self) -> Result<Token<'c>> {
+        if self.nesting > 0 {
+            self.seq.solid_token()
+        } else {
+            self.seq.inline_token()
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr<'c>> {
+        self.parse_binary(0)
+    }
+
+    /// Parses binary operators using precedence climbing: operators which bind tighter than
+    /// `min_precedence` are consumed here, the rest are left for the caller.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr<'c>> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match BinaryOp::from_token(self.continuation_token()?.kind()) {
+                Some(op) if op.precedence() > min_precedence => op,
+                _ => break,
+            };
+            self.seq.go_next();
+            // operators of the same precedence are left-associative
+            let right = self.parse_binary(op.precedence())?;
+            left = Expr::binary(op, left, right, self.seq.new_node_id());
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr<'c>> {
+        let token = self.seq.solid_token()?;
+        match UnaryOp::from_token(token.kind()) {
+            Some(op) => {
+                self.seq.go_next();
+                let operand = self.parse_unary()?;
+                let span = token.span().to(operand.span());
+                let kind = ExprKind::Unary {
+                    op,
+                    operand: Box::new(operand),
+                };
+                Ok(Expr::new(kind, span, self.seq.new_node_id()))
+            }
+            None => {
+                let primary = self.parse_primary()?;
+                self.parse_postfix(primary)
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr<'c>> {
+        let token = self.seq.solid_token()?;
+        match token.kind() {
+            kind if is_literal(kind) => Ok(Expr::literal(self.seq.expect_literal()?, self.seq.new_node_id())),
+            TokenKind::Ident => Ok(Expr::ident(self.seq.expect_ident()?, self.seq.new_node_id())),
+            TokenKind::BracketOpen => {
+                self.seq.go_next();
+                let items = self.nested(|parser| parser.parse_list(TokenKind::BracketClose, |parser| parser.parse_expr()))?;
+                let span = token.span().to(self.seq.last_span());
+                Ok(Expr::new(ExprKind::Array(items), span, self.seq.new_node_id()))
+            }
+            // primitive types can't be called, so their names followed by parens are conversions
+            kind if is_type_start(kind) => {
+                let target = self.seq.expect_type()?;
+                self.seq.expect_token(TokenKind::ParenOpen)?;
+                let value = self.nested(|parser| {
+                    let value = parser.parse_expr()?;
+                    parser.seq.expect_token(TokenKind::ParenClose)?;
+                    Ok(value)
+                })?;
+                let span = token.span().to(self.seq.last_span());
+                let kind = ExprKind::Conversion {
+                    target,
+                    value: Box::new(value),
+                };
+                Ok(Expr::new(kind, span, self.seq.new_node_id()))
+            }
+            TokenKind::ParenOpen => {
+                self.seq.go_next();
+                let inner = self.nested(|parser| {
+                    let inner = parser.parse_expr()?;
+                    parser.seq.expect_token(TokenKind::ParenClose)?;
+                    Ok(inner)
+                })?;
+                let span = token.span().to(self.seq.last_span());
+                Ok(Expr::new(ExprKind::Group(Box::new(inner)), span, self.seq.new_node_id()))
+            }
+            _ => Err(ParsingError::expected(token, "an expression").into()),
+        }
+    }
+
+    fn parse_postfix(&mut self, mut expr: Expr<'c>) -> Result<Expr<'c>> {
+        loop {
+            let start = expr.span();
+            let kind = match self.continuation_token()?.kind() {
+                TokenKind::ParenOpen => {
+                    // only named functions can be called
+                    let name = match expr.kind() {
+                        ExprKind::Ident(name) => *name,
+                        _ => break,
+                    };
+                    self.seq.go_next();
+                    let args = self.nested(|parser| parser.parse_list(TokenKind::ParenClose, |parser| parser.parse_expr()))?;
+                    let span = start.to(self.seq.last_span());
+                    ExprKind::Call(stmt::FunctionCall::new(name, args, span))
+                }
+                TokenKind::Dot => {
+                    self.seq.go_next();
+                    let field = self.seq.expect_ident()?;
+                    ExprKind::Field {
+                        object: Box::new(expr),
+                        field,
+                    }
+                }
+                TokenKind::BracketOpen => {
+                    self.seq.go_next();
+                    let index = self.nested(|parser| {
+                        let index = parser.parse_expr()?;
+                        parser.seq.expect_token(TokenKind::BracketClose)?;
+                        Ok(index)
+                    })?;
+                    ExprKind::Index {
+                        object: Box::new(expr),
+                        index: Box::new(index),
+                    }
+                }
+                _ => break,
+            };
+            let span = start.to(self.seq.last_span());
+            expr = Expr::new(kind, span, self.seq.new_node_id());
+        }
+        Ok(expr)
+    }
+
     fn parse_variable(&mut self) -> Result<stmt::VariableDeclaration<'c>> {
+        let start = self.seq.solid_token()?.span();
         let variable_type = self.seq.expect_type()?;
         let variable_name = self.seq.expect_ident()?;
         self.seq.expect_token(TokenKind::Assign)?;
-        let literal = self.seq.expect_literal()?;
-        self.seq.expect_end()?;
-        Ok(stmt::VariableDeclaration::new(variable_type, variable_name, literal))
+        let value = self.parse_expr()?;
+
+        let span = start.to(value.span());
+        Ok(stmt::VariableDeclaration::new(variable_type, variable_name, value, span))
     }
 
-    fn parse_function_call(&mut self) -> Result<stmt::FunctionCall<'c>> {
-        let function_name = self.seq.expect_ident()?;
+    fn parse_return(&mut self) -> Result<stmt::Return<'c>> {
+        let start = self.seq.expect_token(TokenKind::KeywordReturn)?.span();
+        let value = match self.seq.inline_token()?.kind() {
+            TokenKind::LineBreak | TokenKind::Eof => None,
+            _ => Some(self.parse_expr()?),
+        };
+
+        let span = value.as_ref().map_or(start, |value| start.to(value.span()));
+        Ok(stmt::Return::new(value, span))
+    }
 
+    fn parse_block(&mut self) -> Result<stmt::Block<'c>> {
+        let start = self.seq.expect_token(TokenKind::BraceOpen)?.span();
+        let stmts = self.parse_until(TokenKind::BraceClose, |parser| parser.parse_statement())?;
+        Ok(stmt::Block::new(stmts, start.to(self.seq.last_span())))
+    }
+
+    fn parse_if(&mut self) -> Result<stmt::If<'c>> {
+        let start = self.seq.expect_token(TokenKind::KeywordIf)?.span();
+        let condition = self.parse_expr()?;
+        let then_block = self.parse_block()?;
+        // `else` continues the statement only on the line of the closing brace
+        let else_branch = match self.seq.inline_token()?.kind() {
+            TokenKind::KeywordElse => {
+                self.seq.go_next();
+                match self.seq.solid_token()?.kind() {
+                    TokenKind::KeywordIf => Some(stmt::Else::If(Box::new(self.parse_if()?))),
+                    _ => Some(stmt::Else::Block(self.parse_block()?)),
+                }
+            }
+            _ => None,
+        };
+        let end = match &else_branch {
+            Some(stmt::Else::If(else_if)) => else_if.span(),
+            Some(stmt::Else::Block(block)) => block.span(),
+            None => then_block.span(),
+        };
+        Ok(stmt::If::new(condition, then_block, else_branch, start.to(end)))
+    }
+
+    fn parse_while(&mut self) -> Result<stmt::While<'c>> {
+        let start = self.seq.expect_token(TokenKind::KeywordWhile)?.span();
+        let condition = self.parse_expr()?;
+        let body = self.parse_block()?;
+        let span = start.to(body.span());
+        Ok(stmt::While::new(condition, body, span))
+    }
+
+    /// Parses both C-style `for (init; condition; step)` and for-each `for (Type item : iterable)` loops.
+    fn parse_for(&mut self) -> Result<stmt::Statement<'c>> {
+        let start = self.seq.expect_token(TokenKind::KeywordFor)?.span();
         self.seq.expect_token(TokenKind::ParenOpen)?;
-        let args = self.parse_list(TokenKind::ParenClose, |parser| parser.seq.expect_literal())?;
-        self.seq.expect_end()?;
+        let is_for_each = self.is_declaration_start()? && {
+            let type_tokens = self.type_token_count()?;
+            self.seq.peek_solid_token(type_tokens + 1)?.kind() == TokenKind::Colon
+        };
+        if is_for_each {
+            let (item_type, item, iterable) = self.nested(|parser| {
+                let item_type = parser.seq.expect_type()?;
+                let item = parser.seq.expect_ident()?;
+                parser.seq.expect_token(TokenKind::Colon)?;
+                let iterable = parser.parse_expr()?;
+                parser.seq.expect_token(TokenKind::ParenClose)?;
+                Ok((item_type, item, iterable))
+            })?;
+            let body = self.parse_block()?;
+            let span = start.to(body.span());
+            return Ok(stmt::Statement::ForEach(stmt::ForEach::new(
+                item_type, item, iterable, body, span,
+            )));
+        }
+
+        let (init, condition, step) = self.nested(|parser| {
+            let init = match parser.seq.solid_token()?.kind() {
+                TokenKind::Semicolon => None,
+                _ => Some(parser.parse_simple_statement()?),
+            };
+            parser.seq.expect_token(TokenKind::Semicolon)?;
+            let condition = match parser.seq.solid_token()?.kind() {
+                TokenKind::Semicolon => None,
+                _ => Some(parser.parse_expr()?),
+            };
+            parser.seq.expect_token(TokenKind::Semicolon)?;
+            let step = match parser.seq.solid_token()?.kind() {
+                TokenKind::ParenClose => None,
+                _ => Some(parser.parse_simple_statement()?),
+            };
+            parser.seq.expect_token(TokenKind::ParenClose)?;
+            Ok((init, condition, step))
+        })?;
+        let body = self.parse_block()?;
+        let span = start.to(body.span());
+        Ok(stmt::Statement::For(stmt::For::new(init, condition, step, body, span)))
+    }
+
+    /// Checks whether a variable declaration starts at the current token.
+    fn is_declaration_start(&mut self) -> Result<bool> {
+        match self.seq.solid_token()?.kind() {
+            // two identifiers in a row start a declaration of a variable of a custom type
+            TokenKind::Ident => Ok(self.seq.peek_solid_token(1)?.kind() == TokenKind::Ident),
+            // `[` followed by anything but `]` starts an array literal
+            TokenKind::BracketOpen => Ok(self.seq.peek_solid_token(1)?.kind() == TokenKind::BracketClose),
+            kind => Ok(is_type_start(kind)),
+        }
+    }
+
+    /// Counts the tokens of the type starting at the current token: `[]int` takes three.
+    fn type_token_count(&mut self) -> Result<usize> {
+        let mut count = 0;
+        while self.seq.peek_solid_token(count)?.kind() == TokenKind::BracketOpen {
+            count += 2;
+        }
+        Ok(count + 1)
+    }
+
+    /// Parses a statement which may be used in the header of a C-style `for` loop:
+    /// a variable declaration, an assignment or a function call.
+    fn parse_simple_statement(&mut self) -> Result<stmt::Statement<'c>> {
+        if self.is_declaration_start()? {
+            return Ok(stmt::Statement::var_decl(self.parse_variable()?));
+        }
+
+        let expr = self.parse_expr()?;
+        if let Some(op) = stmt::AssignOp::from_token(self.continuation_token()?.kind()) {
+            if !matches!(
+                expr.kind(),
+                ExprKind::Ident(_) | ExprKind::Field { .. } | ExprKind::Index { .. }
+            ) {
+                let err = ParsingError::invalid("invalid left-hand side of assignment", expr.span())
+                    .with_help("only variables, fields and array elements can be assigned");
+                return Err(err.into());
+            }
+            self.seq.go_next();
+            let value = self.parse_expr()?;
+            return Ok(stmt::Statement::Assignment(stmt::Assignment::new(expr, op, value)));
+        }
 
-        Ok(stmt::FunctionCall::new(function_name, args))
+        let span = expr.span();
+        match expr.into_kind() {
+            ExprKind::Call(call) => Ok(stmt::Statement::function_call(call)),
+            _ => Err(ParsingError::invalid("only function calls and assignments can be used as statements", span).into()),
+        }
     }
 
     fn parse_statement(&mut self) -> Result<stmt::Statement<'c>> {
-        let stmt = match self.seq.solid_token().kind() {
-            TokenKind::Ident => stmt::Statement::function_call(self.parse_function_call()?),
-            _ => stmt::Statement::var_decl(self.parse_variable()?),
+        let token = self.seq.solid_token()?;
+        let stmt = match token.kind() {
+            TokenKind::KeywordReturn => stmt::Statement::return_(self.parse_return()?),
+            TokenKind::KeywordIf => stmt::Statement::If(self.parse_if()?),
+            TokenKind::KeywordWhile => stmt::Statement::While(self.parse_while()?),
+            TokenKind::KeywordFor => self.parse_for()?,
+            TokenKind::KeywordBreak => {
+                self.seq.go_next();
+                stmt::Statement::Break(token.span())
+            }
+            TokenKind::KeywordContinue => {
+                self.seq.go_next();
+                stmt::Statement::Continue(token.span())
+            }
+            TokenKind::BraceOpen => stmt::Statement::Block(self.parse_block()?),
+            TokenKind::KeywordElse => {
+                let err = ParsingError::new(token, []).with_help("`else` must be on the line of the `}` which closes `if`");
+                return Err(err.into());
+            }
+            _ => self.parse_simple_statement()?,
         };
+        self.seq.expect_end()?;
         Ok(stmt)
     }
 
+    fn parse_declaration(&mut self) -> Result<ast::Declaration<'c>> {
+        let token = self.seq.solid_token()?;
+        match token.kind() {
+            TokenKind::KeywordClass => Ok(ast::Declaration::Class(self.parse_class()?)),
+            // `value` is not a keyword, so that it still can be used as a name
+            TokenKind::Ident if token.string() == "value" && self.seq.peek_solid_token(1)?.kind() == TokenKind::KeywordClass => {
+                Ok(ast::Declaration::Class(self.parse_class()?))
+            }
+            _ => Ok(ast::Declaration::Function(self.parse_function()?)),
+        }
+    }
+
+    /// Parses `class Name { Type field, ... }` or `value class ...`, fields may be written on separate lines.
+    fn parse_class(&mut self) -> Result<ast::ClassDeclaration<'c>> {
+        let start_token = self.seq.solid_token()?;
+        let start = start_token.span();
+        let docs = self.seq.take_docs();
+        let value = start_token.kind() == TokenKind::Ident;
+        if value {
+            self.seq.go_next();
+        }
+        self.seq.expect_token(TokenKind::KeywordClass)?;
+        let name = self.seq.expect_ident()?;
+        self.seq.expect_token(TokenKind::BraceOpen)?;
+        let fields = self.parse_list(TokenKind::BraceClose, |parser| {
+            let field_start = parser.seq.solid_token()?.span();
+            let field_type = parser.seq.expect_type()?;
+            let field_name = parser.seq.expect_ident()?;
+            Ok(ast::ClassField::new(field_type, field_name, field_start.to(field_name.span)))
+        })?;
+        let end = self.seq.last_span();
+        self.seq.expect_end()?;
+        Ok(ast::ClassDeclaration::new(name, fields, start.to(end))
+            .with_docs(docs)
+            .with_value(value))
+    }
+
     fn parse_function(&mut self) -> Result<ast::FunctionDeclaration<'c>> {
+        let start = self.seq.solid_token()?.span();
+        let docs = self.seq.take_docs();
         let return_type = self.seq.expect_type_or_void()?;
         let function_name = self.seq.expect_ident()?;
 
         // parse args
         self.seq.expect_token(TokenKind::ParenOpen)?;
         let args = self.parse_list(TokenKind::ParenClose, |parser| {
+            let arg_start = parser.seq.solid_token()?.span();
             let arg_type = parser.seq.expect_type()?;
             let arg_name = parser.seq.expect_ident()?;
-            Ok(ast::FunctionArg::new(arg_type, arg_name))
+            let span = arg_start.to(arg_name.span);
+            Ok(ast::FunctionArg::new(arg_type, arg_name, span))
         })?;
         // parse body
         self.seq.expect_token(TokenKind::BraceOpen)?;
         let stmts = self.parse_until(TokenKind::BraceClose, |parser| parser.parse_statement())?;
+        let end = self.seq.last_span();
 
         self.seq.expect_end()?;
 
-        return Ok(ast::FunctionDeclaration::new(return_type, function_name, args, stmts));
+        let span = start.to(end);
+        let declaration = ast::FunctionDeclaration::new(return_type, function_name, args, stmts, span).with_docs(docs);
+        return Ok(declaration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::{Lexer, Span};
+    use crate::parsing::ast::expr::ExprKind;
+    use crate::parsing::ast::stmt::{Else, Statement};
+    use crate::parsing::ast::Type;
+    use crate::parsing::build_ast;
+
+    #[test]
+    fn attaches_spans_to_nodes() {
+        let source = "void main(int a) {\n    int x = 5\n    print(\"hi\", 1)\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let main = ast.functions().next().unwrap();
+        assert_eq!(main.span(), Span::new(0, 53, 1, 1));
+        assert_eq!(main.name().span, Span::new(5, 4, 1, 6));
+        assert_eq!(main.args()[0].span(), Span::new(10, 5, 1, 11));
+        match &main.stmts()[0] {
+            Statement::VariableDeclaration(decl) => {
+                assert_eq!(decl.span(), Span::new(23, 9, 2, 5));
+                assert_eq!(decl.value().span(), Span::new(31, 1, 2, 13));
+            }
+            other => panic!("{:?}", other),
+        }
+        match &main.stmts()[1] {
+            Statement::FunctionCall(call) => {
+                assert_eq!(call.span(), Span::new(37, 14, 3, 5));
+                assert_eq!(call.args()[1].span(), Span::new(49, 1, 3, 17));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_type_keywords() {
+        let source = "void main(byte a, short b, ushort c, int d, uint e, long f, ulong g, double h, financial i, bool j, string k, rune l, dyn m, Point n) {\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let types: Vec<_> = ast
+            .functions()
+            .next()
+            .unwrap()
+            .args()
+            .iter()
+            .map(|arg| arg.arg_type())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                Type::Byte,
+                Type::Short,
+                Type::UShort,
+                Type::Int,
+                Type::UInt,
+                Type::Long,
+                Type::ULong,
+                Type::Double,
+                Type::Financial,
+                Type::Bool,
+                Type::String,
+                Type::Rune,
+                Type::Dyn,
+                Type::Custom { name: "Point" },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_postfix_expressions() {
+        let source = "void main() {\n    f(a.b[1], (2))\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let call = match &ast.functions().next().unwrap().stmts()[0] {
+            Statement::FunctionCall(call) => call,
+            other => panic!("{:?}", other),
+        };
+        match call.args()[0].kind() {
+            ExprKind::Index { object, index } => {
+                assert!(matches!(object.kind(), ExprKind::Field { field, .. } if field.name == "b"));
+                assert_eq!(index.span(), Span::new(24, 1, 2, 11));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(call.args()[1].span(), Span::new(28, 3, 2, 15));
+        // nodes are told apart by their ids, which differ even for nodes nested into each other
+        match call.args()[1].kind() {
+            ExprKind::Group(inner) => assert_ne!(inner.id(), call.args()[1].id()),
+            other => panic!("{:?}", other),
+        }
+        assert_ne!(call.args()[0].id(), call.args()[1].id());
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        let source = "void main() {\n    1 + 2\n    int e = *\n}\n";
+        let (_, errors) = build_ast(Lexer::new(source));
+        let messages: Vec<_> = errors.iter().map(|err| (err.to_string(), err.span())).collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    "only function calls and assignments can be used as statements".to_string(),
+                    Some(Span::new(18, 5, 2, 5))
+                ),
+                (
+                    "expected an expression, but found `*`".to_string(),
+                    Some(Span::new(36, 1, 3, 13))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_loops() {
+        let source = "void main() {\n    for (Point p : points) {\n    }\n    for (;;) {\n    }\n    for (int i = 0; i < 3; tick()) {\n        break\n    }\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let stmts = ast.functions().next().unwrap().stmts();
+        match &stmts[0] {
+            Statement::ForEach(for_each) => assert_eq!(for_each.span(), Span::new(18, 30, 2, 5)),
+            other => panic!("{:?}", other),
+        }
+        match &stmts[1] {
+            Statement::For(for_) => {
+                assert!(for_.init().is_none() && for_.condition().is_none() && for_.step().is_none());
+            }
+            other => panic!("{:?}", other),
+        }
+        match &stmts[2] {
+            Statement::For(for_) => {
+                assert!(matches!(for_.init(), Some(Statement::VariableDeclaration(_))));
+                assert!(matches!(for_.step(), Some(Statement::FunctionCall(_))));
+                assert!(matches!(for_.body().stmts(), [Statement::Break(_)]));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_else_only_on_the_same_line() {
+        let source =
+            "void main() {\n    if (a) {\n    } else if (b) {\n    } else {\n    }\n    if (a) {\n    }\n    else {\n    }\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        match &ast.functions().next().unwrap().stmts()[0] {
+            Statement::If(if_) => match if_.else_branch() {
+                Some(Else::If(else_if)) => assert!(matches!(else_if.else_branch(), Some(Else::Block(_)))),
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+        let messages: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(messages, ["unexpected `else`"]);
+    }
+
+    #[test]
+    fn reports_readable_token_names() {
+        let source = "void main() {\n    print(\"foo\" 1)\n}\n";
+        let (_, errors) = build_ast(Lexer::new(source));
+        assert_eq!(
+            errors[0].diagnostic().render("main.hl", source),
+            "error: expected `,` or `)`, but found number literal
+ --> main.hl:2:17
+  |
+2 |     print(\"foo\" 1)
+  |                 ^
+"
+        );
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let source = "void main() {
+    int x 1
+    print(\"a\")
+    print(\"b\" 1)
+}
+
+void broken(int {
+    print(\"c\")
+}
+
+void other() {
+    print(\"d\")
+}
+";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        let messages: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected `=`, but found number literal",
+                "expected `,` or `)`, but found number literal",
+                "expected identifier, but found `{`",
+            ]
+        );
+        // statements and declarations around the errors are kept
+        let names: Vec<_> = ast.functions().map(|decl| decl.name().name).collect();
+        assert_eq!(names, ["main", "other"]);
+        assert_eq!(ast.functions().next().unwrap().stmts().len(), 1);
+    }
+
+    #[test]
+    fn reports_lexer_errors() {
+        let source = "void main() {\n    int x = 0b102\n    print(\"a\" # \"b\")\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        let messages: Vec<_> = errors.iter().map(|err| (err.to_string(), err.span())).collect();
+        assert_eq!(
+            messages,
+            [
+                ("invalid number literal".to_string(), Some(Span::new(26, 5, 2, 13))),
+                ("unknown character '#'".to_string(), Some(Span::new(46, 1, 3, 15))),
+            ]
+        );
+        assert_eq!(ast.functions().next().unwrap().stmts().len(), 0);
+    }
+
+    #[test]
+    fn attaches_doc_comments() {
+        let source = "// not a doc\n/// Says hi.\n///\n///   Twice.\nvoid main() { /* block\n comment */\n    print(\"hi\") /// trailing\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let main = ast.functions().next().unwrap();
+        assert_eq!(main.docs(), ["Says hi.", "", "  Twice."]);
+        assert_eq!(main.stmts().len(), 1);
+    }
+
+    #[test]
+    fn reports_invalid_runes() {
+        let source = "void main() {\n    int r = 'ab'\n    int e = ''\n}\n";
+        let (_, errors) = build_ast(Lexer::new(source));
+        let messages: Vec<_> = errors.iter().map(|err| (err.to_string(), err.span())).collect();
+        let message = "rune literal must contain exactly one character".to_string();
+        assert_eq!(
+            messages,
+            [
+                (message.clone(), Some(Span::new(26, 4, 2, 13))),
+                (message, Some(Span::new(43, 2, 3, 13))),
+            ]
+        );
     }
 }
@@ -1,137 +1,293 @@
 use super::{
     ast,
     error::{ParsingError, Result},
+    number::parse_number_literal,
 };
-use crate::lexer::{Lexer, Token, TokenKind};
-use std::iter;
+use crate::lexer::{self, LexError, LexErrorKind, Lexer, Span, Token, TokenKind};
+use std::collections::VecDeque;
+use std::mem;
 
 macro_rules! match_token {
     ( ($expr:expr) { $( $variant:path => $branch:expr),* $(,)? } ) => {
         {
-            let value = ($expr);
-            match value {
-                $( $variant => Ok(($branch)), )*
-                _ => Err(ParsingError::new(value, [ $($variant),* ])),
+            let token = ($expr);
+            match token.kind() {
+                $( $variant => Ok($branch), )*
+                _ => Err(ParsingError::new(token, [ $($variant),* ]).into()),
             }
         }
     };
 }
 
-const TYPE_TOKENS_EXPECTED: [TokenKind; 2] = [TokenKind::KeywordInt, TokenKind::Ident];
+const PRIMITIVE_TYPES: [(TokenKind, ast::Type<'static>); 13] = [
+    (TokenKind::KeywordByte, ast::Type::Byte),
+    (TokenKind::KeywordShort, ast::Type::Short),
+    (TokenKind::KeywordUnsignedShort, ast::Type::UShort),
+    (TokenKind::KeywordInt, ast::Type::Int),
+    (TokenKind::KeywordUnsignedInt, ast::Type::UInt),
+    (TokenKind::KeywordLong, ast::Type::Long),
+    (TokenKind::KeywordUnsignedLong, ast::Type::ULong),
+    (TokenKind::KeywordDouble, ast::Type::Double),
+    (TokenKind::KeywordFinancial, ast::Type::Financial),
+    (TokenKind::KeywordBool, ast::Type::Bool),
+    (TokenKind::KeywordString, ast::Type::String),
+    (TokenKind::KeywordRune, ast::Type::Rune),
+    (TokenKind::KeywordDyn, ast::Type::Dyn),
+];
+
+/// Checks whether a type can start with the token.
+pub fn is_type_start(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Ident | TokenKind::BracketOpen) || PRIMITIVE_TYPES.iter().any(|(type_kind, _)| *type_kind == kind)
+}
+
+pub fn is_literal(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::NumberLiteral
+            | TokenKind::StringLiteral
+            | TokenKind::RuneLiteral
+            | TokenKind::KeywordTrue
+            | TokenKind::KeywordFalse
+    )
+}
 
 fn token_to_type(token: Token) -> Option<ast::Type> {
     match token.kind() {
-        TokenKind::KeywordInt => Some(ast::Type::Int),
         TokenKind::Ident => Some(ast::Type::Custom { name: token.string() }),
-        _ => None,
+        kind => PRIMITIVE_TYPES
+            .iter()
+            .find(|(type_kind, _)| *type_kind == kind)
+            .map(|(_, type_)| *type_),
     }
 }
 
 pub struct TokenSeq<'c> {
     lexer: Lexer<'c>,
-    buf: Option<Token<'c>>,
+    /// Current token followed by the tokens that were looked ahead.
+    buf: VecDeque<Token<'c>>,
+    last_span: Span,
+    docs: Vec<&'c str>,
+    /// Element types of the array types parsed so far.
+    arrays: Vec<ast::Type<'c>>,
+    /// Number of the nodes which got ids so far.
+    nodes: usize,
+}
+
+const fn is_trivia(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Spaces | TokenKind::LineBreak | TokenKind::Comment | TokenKind::DocComment
+    )
+}
+
+fn doc_comment_text(comment: &str) -> &str {
+    let text = comment.strip_prefix("///").unwrap_or(comment);
+    text.strip_prefix(' ').unwrap_or(text).trim_end()
 }
 
 impl<'c> TokenSeq<'c> {
     pub fn new(lexer: Lexer<'c>) -> TokenSeq<'c> {
-        TokenSeq { lexer, buf: None }
+        TokenSeq {
+            lexer,
+            buf: VecDeque::new(),
+            last_span: Span::default(),
+            docs: Vec::new(),
+            arrays: Vec::new(),
+            nodes: 0,
+        }
     }
 
-    pub fn current_token(&mut self) -> Token<'c> {
-        match self.buf {
-            Some(t) => t,
-            None => {
-                let next = self.lexer.next().expect("failed to match token");
-                self.buf = Some(next);
-                next
-            }
+    pub fn new_node_id(&mut self) -> ast::NodeId {
+        self.nodes += 1;
+        ast::NodeId(self.nodes - 1)
+    }
+
+    fn buffered_token(&mut self, index: usize) -> Result<Token<'c>> {
+        while self.buf.len() <= index {
+            let next = self.lexer.next()?;
+            self.buf.push_back(next);
         }
+        Ok(self.buf[index])
+    }
+
+    pub fn current_token(&mut self) -> Result<Token<'c>> {
+        self.buffered_token(0)
     }
 
     pub fn go_next(&mut self) {
-        self.buf = None;
+        if let Some(token) = self.buf.pop_front() {
+            self.last_span = token.span();
+            if !is_trivia(token.kind()) {
+                self.docs.clear();
+            }
+        }
     }
 
-    pub fn next_token(&mut self) -> Token<'c> {
-        let token = self.current_token();
-        self.go_next();
-        token
+    /// Takes doc comments that were skipped since the last solid token, i.e. the docs
+    /// of the node which starts at the current solid token.
+    pub fn take_docs(&mut self) -> Vec<&'c str> {
+        std::mem::take(&mut self.docs)
+    }
+
+    /// Span of the last token which was passed with [`TokenSeq::go_next`].
+    pub fn last_span(&self) -> Span {
+        self.last_span
+    }
+
+    pub fn solid_token(&mut self) -> Result<Token<'c>> {
+        loop {
+            let token = self.current_token()?;
+            if token.kind() == TokenKind::DocComment {
+                self.docs.push(doc_comment_text(token.string()));
+            }
+            if is_trivia(token.kind()) {
+                self.go_next();
+                continue;
+            }
+            return Ok(token);
+        }
     }
 
-    pub fn solid_token(&mut self) -> Token<'c> {
+    /// Returns `n`-th solid token without passing anything, the current solid token is the 0th.
+    pub fn peek_solid_token(&mut self, n: usize) -> Result<Token<'c>> {
+        self.solid_token()?;
+        let mut index = 0;
+        let mut solid_count = 0;
         loop {
-            let token = self.current_token();
-            if matches!(token.kind(), TokenKind::Spaces | TokenKind::LineBreak) {
+            let token = self.buffered_token(index)?;
+            if !is_trivia(token.kind()) {
+                if solid_count == n || token.kind() == TokenKind::Eof {
+                    return Ok(token);
+                }
+                solid_count += 1;
+            }
+            index += 1;
+        }
+    }
+
+    /// Like [`TokenSeq::solid_token`], but doesn't skip line breaks, as they end statements.
+    pub fn inline_token(&mut self) -> Result<Token<'c>> {
+        loop {
+            let token = self.current_token()?;
+            if token.kind() != TokenKind::LineBreak && is_trivia(token.kind()) {
                 self.go_next();
                 continue;
             }
-            return token;
+            return Ok(token);
         }
     }
 
-    pub fn next_solid_token(&mut self) -> Token<'c> {
-        let token = self.solid_token();
+    pub fn next_solid_token(&mut self) -> Result<Token<'c>> {
+        let token = self.solid_token()?;
         self.go_next();
-        token
+        Ok(token)
+    }
+
+    /// Passes the current token if `result` is successful. Erroneous tokens are left in place
+    /// so that the parser can resynchronise on them.
+    fn advance_if_ok<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_ok() {
+            self.go_next();
+        }
+        result
     }
 
     pub fn expect_type(&mut self) -> Result<ast::Type<'c>> {
-        let token = self.next_solid_token();
-        token_to_type(token).ok_or_else(|| ParsingError::new(token.kind(), TYPE_TOKENS_EXPECTED))
+        let token = self.solid_token()?;
+        if token.kind() == TokenKind::BracketOpen {
+            return self.expect_array_type();
+        }
+        let result = token_to_type(token).ok_or_else(|| ParsingError::expected(token, "a type").into());
+        self.advance_if_ok(result)
     }
 
     pub fn expect_type_or_void(&mut self) -> Result<ast::TypeOrVoid<'c>> {
-        let token = self.next_solid_token();
-        match token_to_type(token) {
+        let token = self.solid_token()?;
+        if token.kind() == TokenKind::BracketOpen {
+            return self.expect_array_type().map(ast::TypeOrVoid::Type);
+        }
+        let result = match token_to_type(token) {
             Some(t) => Ok(ast::TypeOrVoid::Type(t)),
             None => match token.kind() {
                 TokenKind::KeywordVoid => Ok(ast::TypeOrVoid::Void),
-                _ => {
-                    let expected = TYPE_TOKENS_EXPECTED.into_iter().chain(iter::once(TokenKind::KeywordVoid));
-                    Err(ParsingError::new(token.kind(), expected))
-                }
+                _ => Err(ParsingError::expected(token, "a type or `void`").into()),
             },
-        }
+        };
+        self.advance_if_ok(result)
+    }
+
+    /// Parses `[]T`.
+    fn expect_array_type(&mut self) -> Result<ast::Type<'c>> {
+        self.expect_token(TokenKind::BracketOpen)?;
+        self.expect_token(TokenKind::BracketClose)?;
+        let element = self.expect_type()?;
+        Ok(ast::Type::Array(ast::intern_array(&mut self.arrays, element)))
+    }
+
+    /// Takes the element types of the array types parsed so far.
+    pub fn take_arrays(&mut self) -> Vec<ast::Type<'c>> {
+        mem::take(&mut self.arrays)
     }
 
     pub fn expect_ident(&mut self) -> Result<ast::Ident<'c>> {
-        let token = self.next_solid_token();
-        match_token!((token.kind()) {
-            TokenKind::Ident => ast::Ident { name: token.string() },
-        })
+        let token = self.solid_token()?;
+        let result = match_token!((token) {
+            TokenKind::Ident => ast::Ident {
+                name: token.string(),
+                span: token.span(),
+                id: self.new_node_id(),
+            },
+        });
+        self.advance_if_ok(result)
     }
 
-    pub fn expect_token(&mut self, kind: TokenKind) -> Result<()> {
-        let token = self.next_solid_token();
-        if kind == token.kind() {
-            Ok(())
+    pub fn expect_token(&mut self, kind: TokenKind) -> Result<Token<'c>> {
+        let token = self.solid_token()?;
+        let result = if kind == token.kind() {
+            Ok(token)
         } else {
-            Err(ParsingError::new(token.kind(), [kind]))
-        }
+            Err(ParsingError::new(token, [kind]).into())
+        };
+        self.advance_if_ok(result)
     }
 
-    // TODO: change error handling here
-    pub fn expect_literal(&mut self) -> Result<ast::Literal<'c>> {
-        let token = self.next_solid_token();
-        match_token!((token.kind()) {
-            TokenKind::IntegerLiteral => {
-                let value = token.string().parse::<i32>().expect("failed to parse integer literal");
-                ast::Literal::integer(value)
-            },
-            TokenKind::StringLiteral => {
-                let string = token.string();
-                let value = string.get(1..(string.len() - 1)).expect("failed to parse string literal");
-                ast::Literal::string(value)
+    pub fn expect_literal(&mut self) -> Result<ast::Literal> {
+        let token = self.solid_token()?;
+        let result = match_token!((token) {
+            TokenKind::NumberLiteral => ast::Literal::new(parse_number_literal(token)?, token.span()),
+            TokenKind::KeywordTrue => ast::Literal::bool(true, token.span()),
+            TokenKind::KeywordFalse => ast::Literal::bool(false, token.span()),
+            TokenKind::StringLiteral => ast::Literal::string(lexer::unescape(token)?, token.span()),
+            TokenKind::RuneLiteral => {
+                let value = lexer::unescape(token)?;
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => ast::Literal::rune(char, token.span()),
+                    _ => return Err(LexError::new(LexErrorKind::InvalidRune, token.span()).into()),
+                }
             },
-        })
+        });
+        self.advance_if_ok(result)
     }
 
     pub fn expect_end(&mut self) -> Result<()> {
-        match_token!((self.current_token().kind()) {
-            TokenKind::LineBreak => (),
-            TokenKind::Eof => (),
-        })?;
-        self.go_next();
-        Ok(())
+        while matches!(
+            self.current_token()?.kind(),
+            TokenKind::Spaces | TokenKind::Comment | TokenKind::DocComment
+        ) {
+            self.go_next();
+        }
+        let token = self.current_token()?;
+        match token.kind() {
+            TokenKind::LineBreak | TokenKind::Eof => {
+                self.go_next();
+                Ok(())
+            }
+            _ => {
+                let err = ParsingError::new(token, [TokenKind::LineBreak, TokenKind::Eof])
+                    .with_help("each statement and declaration must end with a line break");
+                Err(err.into())
+            }
+        }
     }
 }
@@ -0,0 +1,283 @@
+use crate::diagnostics::{Diagnostic, Level};
+use crate::lexer::Span;
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SemaError {
+    Undefined {
+        what: &'static str,
+        name: String,
+        span: Span,
+    },
+    AlreadyDefined {
+        what: &'static str,
+        name: String,
+        span: Span,
+        /// Span of the first definition, builtins don't have one.
+        previous: Option<Span>,
+    },
+    /// Variable hides a variable with the same name from an enclosing scope. This is allowed,
+    /// but usually is a mistake, so it is reported as a warning.
+    Shadowing {
+        name: String,
+        span: Span,
+        previous: Span,
+    },
+    MismatchedTypes {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    /// Literal is implicitly cast to a type which can't represent its value.
+    LiteralOutOfRange {
+        type_name: String,
+        span: Span,
+    },
+    InvalidOperator {
+        operator: &'static str,
+        type_name: String,
+        span: Span,
+    },
+    InvalidConversion {
+        from: String,
+        to: String,
+        span: Span,
+    },
+    WrongArgumentCount {
+        /// What is called: `function` or `constructor of class`.
+        what: &'static str,
+        function: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    /// Result of a `void` function is used as a value.
+    NoValue {
+        function: String,
+        span: Span,
+    },
+    MissingReturn {
+        function: String,
+        span: Span,
+    },
+    MissingReturnValue {
+        expected: String,
+        span: Span,
+    },
+    ReturnInVoidFunction {
+        span: Span,
+    },
+    UnusedObject {
+        class: String,
+        span: Span,
+    },
+    EmptyClass {
+        name: String,
+        span: Span,
+    },
+    NoField {
+        type_name: String,
+        field: String,
+        span: Span,
+    },
+    NotIndexable {
+        type_name: String,
+        span: Span,
+    },
+    NotIterable {
+        type_name: String,
+        span: Span,
+    },
+    InvalidIndex {
+        type_name: String,
+        span: Span,
+    },
+    NotArray {
+        type_name: String,
+        span: Span,
+    },
+    UntypedArrayLiteral {
+        span: Span,
+    },
+    /// Only values with a textual form can be printed, which objects and arrays don't have.
+    NotPrintable {
+        type_name: String,
+        span: Span,
+    },
+    OutsideOfLoop {
+        what: &'static str,
+        span: Span,
+    },
+    /// Field of a value class is assigned, while values are immutable.
+    ImmutableField {
+        class: String,
+        field: String,
+        span: Span,
+    },
+    /// Value class has a field of a type which is not copied by value.
+    ReferenceInValueClass {
+        class: String,
+        type_name: String,
+        span: Span,
+    },
+    /// Value class contains itself through its fields, so it would have infinite size.
+    RecursiveValueClass {
+        class: String,
+        span: Span,
+    },
+}
+
+/// Types between which values can be converted with `type(value)`.
+const CONVERTIBLE_TYPES: [&str; 9] = ["byte", "short", "ushort", "int", "uint", "long", "ulong", "double", "rune"];
+
+impl SemaError {
+    pub fn span(&self) -> Span {
+        match self {
+            SemaError::Undefined { span, .. } => *span,
+            SemaError::AlreadyDefined { span, .. } => *span,
+            SemaError::Shadowing { span, .. } => *span,
+            SemaError::MismatchedTypes { span, .. } => *span,
+            SemaError::LiteralOutOfRange { span, .. } => *span,
+            SemaError::InvalidOperator { span, .. } => *span,
+            SemaError::InvalidConversion { span, .. } => *span,
+            SemaError::WrongArgumentCount { span, .. } => *span,
+            SemaError::NoValue { span, .. } => *span,
+            SemaError::MissingReturn { span, .. } => *span,
+            SemaError::MissingReturnValue { span, .. } => *span,
+            SemaError::ReturnInVoidFunction { span } => *span,
+            SemaError::UnusedObject { span, .. } => *span,
+            SemaError::EmptyClass { span, .. } => *span,
+            SemaError::NoField { span, .. } => *span,
+            SemaError::NotIndexable { span, .. } => *span,
+            SemaError::NotIterable { span, .. } => *span,
+            SemaError::InvalidIndex { span, .. } => *span,
+            SemaError::NotArray { span, .. } => *span,
+            SemaError::UntypedArrayLiteral { span } => *span,
+            SemaError::NotPrintable { span, .. } => *span,
+            SemaError::OutsideOfLoop { span, .. } => *span,
+            SemaError::ImmutableField { span, .. } => *span,
+            SemaError::ReferenceInValueClass { span, .. } => *span,
+            SemaError::RecursiveValueClass { span, .. } => *span,
+        }
+    }
+
+    pub fn level(&self) -> Level {
+        match self {
+            SemaError::Shadowing { .. } => Level::Warning,
+            _ => Level::Error,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::new(self.level(), self.to_string(), self.span());
+        let previous = match self {
+            SemaError::AlreadyDefined {
+                previous: None, name, ..
+            } => {
+                return diagnostic.with_help(format!("`{}` is a builtin function", name));
+            }
+            SemaError::AlreadyDefined { previous, .. } => *previous,
+            SemaError::Shadowing { previous, .. } => Some(*previous),
+            SemaError::MismatchedTypes { expected, found, .. }
+                if CONVERTIBLE_TYPES.contains(&expected.as_str()) && CONVERTIBLE_TYPES.contains(&found.as_str()) =>
+            {
+                return diagnostic.with_help(format!("use an explicit conversion: `{}(value)`", expected));
+            }
+            SemaError::ImmutableField { class, .. } => {
+                return diagnostic.with_help(format!("values are immutable, construct a new `{}` instead", class));
+            }
+            SemaError::UntypedArrayLiteral { .. } => {
+                return diagnostic.with_help("declare a variable of the array type: `[]int values = [1, 2]`");
+            }
+            SemaError::ReferenceInValueClass { .. } => {
+                return diagnostic.with_help("fields of value classes must be numbers, strings, runes, bools or value classes");
+            }
+            _ => None,
+        };
+        match previous {
+            Some(previous) => {
+                diagnostic.with_help(format!("previous definition is at {}:{}", previous.line(), previous.column()))
+            }
+            None => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for SemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemaError::Undefined { what, name, .. } => f.write_fmt(format_args!("cannot find {} `{}`", what, name)),
+            SemaError::AlreadyDefined { what, name, .. } => f.write_fmt(format_args!("{} `{}` is already defined", what, name)),
+            SemaError::Shadowing { name, .. } => {
+                f.write_fmt(format_args!("variable `{}` shadows a variable of the enclosing scope", name))
+            }
+            SemaError::MismatchedTypes { expected, found, .. } => f.write_fmt(format_args!(
+                "mismatched types: expected value of type `{}`, but found `{}`",
+                expected, found
+            )),
+            SemaError::LiteralOutOfRange { type_name, .. } => {
+                f.write_fmt(format_args!("literal doesn't fit into type `{}`", type_name))
+            }
+            SemaError::InvalidOperator { operator, type_name, .. } => f.write_fmt(format_args!(
+                "operator `{}` can't be applied to type `{}`",
+                operator, type_name
+            )),
+            SemaError::InvalidConversion { from, to, .. } => {
+                f.write_fmt(format_args!("can't convert value of type `{}` to `{}`", from, to))
+            }
+            SemaError::WrongArgumentCount {
+                what,
+                function,
+                expected,
+                found,
+                ..
+            } => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                let verb = if *found == 1 { "was" } else { "were" };
+                f.write_fmt(format_args!(
+                    "{} `{}` takes {} argument{}, but {} {} given",
+                    what, function, expected, plural, found, verb
+                ))
+            }
+            SemaError::NoValue { function, .. } => f.write_fmt(format_args!("function `{}` doesn't return a value", function)),
+            SemaError::MissingReturn { function, .. } => {
+                f.write_fmt(format_args!("function `{}` doesn't return a value on every path", function))
+            }
+            SemaError::MissingReturnValue { expected, .. } => {
+                f.write_fmt(format_args!("`return` must have a value of type `{}`", expected))
+            }
+            SemaError::ReturnInVoidFunction { .. } => f.write_str("`void` function can't return a value"),
+            SemaError::UnusedObject { class, .. } => {
+                f.write_fmt(format_args!("object of class `{}` is constructed but never used", class))
+            }
+            SemaError::EmptyClass { name, .. } => f.write_fmt(format_args!("class `{}` must have at least one field", name)),
+            SemaError::NoField { type_name, field, .. } => {
+                f.write_fmt(format_args!("type `{}` has no field `{}`", type_name, field))
+            }
+            SemaError::NotIndexable { type_name, .. } => f.write_fmt(format_args!("type `{}` can't be indexed", type_name)),
+            SemaError::NotIterable { type_name, .. } => f.write_fmt(format_args!("type `{}` can't be iterated over", type_name)),
+            SemaError::InvalidIndex { type_name, .. } => {
+                f.write_fmt(format_args!("arrays can't be indexed with value of type `{}`", type_name))
+            }
+            SemaError::NotArray { type_name, .. } => {
+                f.write_fmt(format_args!("expected an array, but found value of type `{}`", type_name))
+            }
+            SemaError::UntypedArrayLiteral { .. } => f.write_str("type of array literal can't be inferred"),
+            SemaError::NotPrintable { type_name, .. } => {
+                f.write_fmt(format_args!("values of type `{}` can't be printed", type_name))
+            }
+            SemaError::OutsideOfLoop { what, .. } => f.write_fmt(format_args!("`{}` outside of a loop", what)),
+            SemaError::ImmutableField { class, field, .. } => {
+                f.write_fmt(format_args!("field `{}` of value class `{}` can't be assigned", field, class))
+            }
+            SemaError::ReferenceInValueClass { class, type_name, .. } => f.write_fmt(format_args!(
+                "value class `{}` can't have a field of type `{}`",
+                class, type_name
+            )),
+            SemaError::RecursiveValueClass { class, .. } => f.write_fmt(format_args!("value class `{}` contains itself", class)),
+        }
+    }
+}
+
+impl error::Error for SemaError {}
@@ -0,0 +1,607 @@
+mod error;
+mod scope;
+mod typeck;
+
+pub use error::SemaError;
+pub use typeck::{as_constant, financial_units};
+
+use crate::builtins::{self, Builtin};
+use crate::error::CompileError;
+use crate::lexer::Span;
+use crate::parsing::ast::expr::{Expr, ExprKind};
+use crate::parsing::ast::stmt::{self, Statement};
+use crate::parsing::ast::{self, Ident, NodeId, Type, TypeOrVoid};
+use scope::Scopes;
+use std::collections::{HashMap, HashSet};
+
+/// Index of a symbol in [`Analysis`].
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct SymbolId(usize);
+
+#[derive(Clone, Debug)]
+pub enum SymbolKind<'a> {
+    /// Function provided by the compiler.
+    Builtin(&'static Builtin),
+    Function {
+        return_type: TypeOrVoid<'a>,
+        params: Vec<Type<'a>>,
+    },
+    /// Class, calls of which construct its objects.
+    Class {
+        fields: Vec<(&'a str, Type<'a>)>,
+        /// Objects of value classes are immutable and copied on assignment.
+        value: bool,
+    },
+    Param(Type<'a>),
+    Local(Type<'a>),
+}
+
+/// Declaration which names in the code may refer to.
+#[derive(Clone, Debug)]
+pub struct Symbol<'a> {
+    kind: SymbolKind<'a>,
+    /// Span of the declared name, builtins have the default span.
+    span: Span,
+}
+
+impl<'a> Symbol<'a> {
+    pub fn kind(&self) -> &SymbolKind<'a> {
+        &self.kind
+    }
+}
+
+/// Result of the semantic analysis: every identifier of the AST, both in declarations and in uses,
+/// is mapped to the symbol it refers to, and every expression is mapped to its static type.
+#[derive(Debug)]
+pub struct Analysis<'a> {
+    symbols: Vec<Symbol<'a>>,
+    /// Symbols by the ids of identifiers.
+    resolutions: HashMap<NodeId, SymbolId>,
+    /// Types by the ids of expressions.
+    expr_types: HashMap<NodeId, Type<'a>>,
+    classes: HashMap<&'a str, SymbolId>,
+    /// Element types of the array types, copied from the AST.
+    arrays: Vec<Type<'a>>,
+}
+
+impl<'a> Analysis<'a> {
+    /// Type of the elements of an array type, `None` for other types.
+    pub fn element(&self, type_: Type<'a>) -> Option<Type<'a>> {
+        match type_ {
+            Type::Array(array) => Some(self.arrays[array.0]),
+            _ => None,
+        }
+    }
+
+    /// Element types of the array types.
+    pub fn arrays(&self) -> &[Type<'a>] {
+        self.arrays.as_slice()
+    }
+
+    pub fn type_name(&self, type_: Type<'a>) -> String {
+        type_.name(&self.arrays).into_owned()
+    }
+
+    pub fn symbol(&self, id: SymbolId) -> &Symbol<'a> {
+        &self.symbols[id.0]
+    }
+
+    pub fn resolve(&self, ident: Ident) -> Option<SymbolId> {
+        self.resolutions.get(&ident.id).copied()
+    }
+
+    pub fn resolved_symbol(&self, ident: Ident) -> Option<&Symbol<'a>> {
+        self.resolve(ident).map(|id| self.symbol(id))
+    }
+
+    /// Names and types of the fields of the class, `None` if there is no class with the name.
+    pub fn class_fields(&self, name: &str) -> Option<&[(&'a str, Type<'a>)]> {
+        let id = self.classes.get(name)?;
+        match &self.symbol(*id).kind {
+            SymbolKind::Class { fields, .. } => Some(fields.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn is_value_class(&self, name: &str) -> bool {
+        self.classes
+            .get(name)
+            .is_some_and(|id| matches!(self.symbol(*id).kind, SymbolKind::Class { value: true, .. }))
+    }
+
+    /// Static type of the expression, `None` if it couldn't be inferred because of errors.
+    /// Literals have the type they are implicitly cast to.
+    pub fn expr_type(&self, expr: &Expr) -> Option<Type<'a>> {
+        self.expr_types.get(&expr.id()).copied()
+    }
+}
+
+/// Builds symbol tables of the program, resolves names and checks types. The analysis is returned
+/// even if there are errors, unresolved names and erroneous expressions are just missing from it.
+/// Returned problems include warnings, which don't prevent compilation.
+pub fn analyze<'a>(tree: &ast::AST<'a>) -> (Analysis<'a>, Vec<CompileError>) {
+    let mut resolver = Resolver::new();
+    resolver.analysis.arrays = tree.arrays().to_vec();
+    for builtin in &builtins::BUILTINS {
+        let id = resolver.add_symbol(SymbolKind::Builtin(builtin), Span::default());
+        resolver.functions.insert(builtin.name, id);
+    }
+    // classes and functions are visible in the whole file, so they are declared before resolving bodies
+    for class in tree.classes() {
+        resolver.declare_class(class);
+    }
+    for decl in tree.functions() {
+        resolver.declare_function(decl);
+    }
+    for class in tree.classes() {
+        for field in class.fields() {
+            resolver.resolve_type(field.field_type(), field.span());
+        }
+        if class.is_value() {
+            resolver.check_value_class(class);
+        }
+    }
+    for decl in tree.functions() {
+        resolver.resolve_function(decl);
+    }
+    let (mut analysis, mut errors) = (resolver.analysis, resolver.errors);
+    typeck::check(tree, &mut analysis, &mut errors);
+    (analysis, errors)
+}
+
+struct Resolver<'a> {
+    analysis: Analysis<'a>,
+    /// Everything that can be called: functions, builtins and classes.
+    functions: HashMap<&'a str, SymbolId>,
+    variables: Scopes<'a>,
+    errors: Vec<CompileError>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new() -> Resolver<'a> {
+        Resolver {
+            analysis: Analysis {
+                symbols: Vec::new(),
+                resolutions: HashMap::new(),
+                expr_types: HashMap::new(),
+                classes: HashMap::new(),
+                arrays: Vec::new(),
+            },
+            functions: HashMap::new(),
+            variables: Scopes::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn add_symbol(&mut self, kind: SymbolKind<'a>, span: Span) -> SymbolId {
+        let id = SymbolId(self.analysis.symbols.len());
+        self.analysis.symbols.push(Symbol { kind, span });
+        id
+    }
+
+    fn record(&mut self, ident: Ident, id: SymbolId) {
+        self.analysis.resolutions.insert(ident.id, id);
+    }
+
+    fn report(&mut self, err: SemaError) {
+        self.errors.push(err.into());
+    }
+
+    fn already_defined(&mut self, what: &'static str, name: Ident, previous: SymbolId) {
+        let previous = self.analysis.symbol(previous);
+        let previous = match previous.kind {
+            SymbolKind::Builtin(_) => None,
+            _ => Some(previous.span),
+        };
+        self.report(SemaError::AlreadyDefined {
+            what,
+            name: name.name.to_string(),
+            span: name.span,
+            previous,
+        });
+    }
+
+    fn declare_class(&mut self, class: &ast::ClassDeclaration<'a>) {
+        let name = class.name();
+        let mut fields: Vec<(&'a str, Type<'a>)> = Vec::with_capacity(class.fields().len());
+        for field in class.fields() {
+            let field_name = field.name();
+            if fields.iter().any(|(name, _)| *name == field_name.name) {
+                self.report(SemaError::AlreadyDefined {
+                    what: "field",
+                    name: field_name.name.to_string(),
+                    span: field_name.span,
+                    previous: class
+                        .fields()
+                        .iter()
+                        .find(|previous| previous.name() == field_name.name)
+                        .map(|previous| previous.name().span),
+                });
+                continue;
+            }
+            fields.push((field_name.name, field.field_type()));
+        }
+        if fields.is_empty() {
+            // C doesn't allow structs without members
+            self.report(SemaError::EmptyClass {
+                name: name.name.to_string(),
+                span: class.span(),
+            });
+        }
+        let kind = SymbolKind::Class {
+            fields,
+            value: class.is_value(),
+        };
+        let id = self.add_symbol(kind, name.span);
+        self.record(name, id);
+        if let Some(previous) = self.functions.get(name.name).copied() {
+            self.already_defined("class", name, previous);
+        } else {
+            self.functions.insert(name.name, id);
+            self.analysis.classes.insert(name.name, id);
+        }
+    }
+
+    /// Checks that the custom type refers to a class, `span` is the span of the code which mentions it.
+    fn resolve_type(&mut self, type_: Type<'a>, span: Span) {
+        match type_ {
+            Type::Custom { name } if !self.analysis.classes.contains_key(name) => self.report(SemaError::Undefined {
+                what: "type",
+                name: name.to_string(),
+                span,
+            }),
+            Type::Array(array) => self.resolve_type(self.analysis.arrays[array.0], span),
+            _ => (),
+        }
+    }
+
+    /// Checks that the fields of the value class are values too and that it doesn't contain itself.
+    fn check_value_class(&mut self, class: &ast::ClassDeclaration<'a>) {
+        let name = class.name().name;
+        for field in class.fields() {
+            let is_reference = match field.field_type() {
+                Type::Dyn | Type::Array(_) => true,
+                Type::Custom { name } => self.analysis.classes.contains_key(name) && !self.analysis.is_value_class(name),
+                _ => false,
+            };
+            if is_reference {
+                self.report(SemaError::ReferenceInValueClass {
+                    class: name.to_string(),
+                    type_name: self.analysis.type_name(field.field_type()),
+                    span: field.span(),
+                });
+            }
+        }
+
+        // value classes reachable through the fields, each of them is visited once
+        let mut visited = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            for (_, type_) in self.analysis.class_fields(current).unwrap_or_default() {
+                let Type::Custom { name: field_class } = *type_ else {
+                    continue;
+                };
+                if field_class == name {
+                    self.report(SemaError::RecursiveValueClass {
+                        class: name.to_string(),
+                        span: class.name().span,
+                    });
+                    return;
+                }
+                if self.analysis.is_value_class(field_class) && visited.insert(field_class) {
+                    pending.push(field_class);
+                }
+            }
+        }
+    }
+
+    fn declare_function(&mut self, decl: &ast::FunctionDeclaration<'a>) {
+        let name = decl.name();
+        let kind = SymbolKind::Function {
+            return_type: decl.return_type(),
+            params: decl.args().iter().map(|arg| arg.arg_type()).collect(),
+        };
+        let id = self.add_symbol(kind, name.span);
+        self.record(name, id);
+        if let Some(previous) = self.functions.get(name.name).copied() {
+            self.already_defined("function", name, previous);
+        } else {
+            self.functions.insert(name.name, id);
+        }
+    }
+
+    fn declare_variable(&mut self, name: Ident<'a>, kind: SymbolKind<'a>) {
+        let id = self.add_symbol(kind, name.span);
+        self.record(name, id);
+        if let Some(previous) = self.variables.lookup_innermost(name.name) {
+            self.already_defined("variable", name, previous);
+            return;
+        }
+        if let Some(shadowed) = self.variables.lookup(name.name) {
+            let previous = self.analysis.symbol(shadowed).span;
+            self.report(SemaError::Shadowing {
+                name: name.name.to_string(),
+                span: name.span,
+                previous,
+            });
+        }
+        self.variables.declare(name.name, id);
+    }
+
+    fn resolve_function(&mut self, decl: &ast::FunctionDeclaration<'a>) {
+        if let TypeOrVoid::Type(return_type) = decl.return_type() {
+            self.resolve_type(return_type, decl.name().span);
+        }
+        // params and locals of the function body share the scope
+        self.variables.push();
+        for arg in decl.args() {
+            self.resolve_type(arg.arg_type(), arg.span());
+            self.declare_variable(arg.name(), SymbolKind::Param(arg.arg_type()));
+        }
+        self.resolve_stmts(decl.stmts());
+        self.variables.pop();
+    }
+
+    fn resolve_block(&mut self, block: &stmt::Block<'a>) {
+        self.variables.push();
+        self.resolve_stmts(block.stmts());
+        self.variables.pop();
+    }
+
+    fn resolve_stmts(&mut self, stmts: &[Statement<'a>]) {
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Statement<'a>) {
+        match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                // the variable is not visible in its own initialiser
+                self.resolve_expr(var_decl.value());
+                self.resolve_type(var_decl.var_type(), var_decl.span());
+                self.declare_variable(var_decl.name(), SymbolKind::Local(var_decl.var_type()));
+            }
+            Statement::FunctionCall(func_call) => self.resolve_call(func_call),
+            Statement::Assignment(assignment) => {
+                self.resolve_expr(assignment.target());
+                self.resolve_expr(assignment.value());
+            }
+            Statement::Return(return_) => {
+                if let Some(value) = return_.value() {
+                    self.resolve_expr(value);
+                }
+            }
+            Statement::If(if_) => self.resolve_if(if_),
+            Statement::While(while_) => {
+                self.resolve_expr(while_.condition());
+                self.resolve_block(while_.body());
+            }
+            Statement::For(for_) => {
+                // variable declared in the header is visible only in the loop
+                self.variables.push();
+                if let Some(init) = for_.init() {
+                    self.resolve_stmt(init);
+                }
+                if let Some(condition) = for_.condition() {
+                    self.resolve_expr(condition);
+                }
+                if let Some(step) = for_.step() {
+                    self.resolve_stmt(step);
+                }
+                self.resolve_block(for_.body());
+                self.variables.pop();
+            }
+            Statement::ForEach(for_each) => {
+                self.resolve_expr(for_each.iterable());
+                self.variables.push();
+                self.resolve_type(for_each.item_type(), for_each.item().span);
+                self.declare_variable(for_each.item(), SymbolKind::Local(for_each.item_type()));
+                self.resolve_block(for_each.body());
+                self.variables.pop();
+            }
+            Statement::Break(_) | Statement::Continue(_) => (),
+            Statement::Block(block) => self.resolve_block(block),
+        }
+    }
+
+    fn resolve_if(&mut self, if_: &stmt::If<'a>) {
+        self.resolve_expr(if_.condition());
+        self.resolve_block(if_.then_block());
+        match if_.else_branch() {
+            Some(stmt::Else::If(else_if)) => self.resolve_if(else_if),
+            Some(stmt::Else::Block(block)) => self.resolve_block(block),
+            None => (),
+        }
+    }
+
+    fn resolve_call(&mut self, func_call: &stmt::FunctionCall<'a>) {
+        let name = func_call.name();
+        match self.functions.get(name.name).copied() {
+            Some(id) => self.record(name, id),
+            None => self.report(SemaError::Undefined {
+                what: "function",
+                name: name.name.to_string(),
+                span: name.span,
+            }),
+        }
+        for arg in func_call.args() {
+            self.resolve_expr(arg);
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr<'a>) {
+        match expr.kind() {
+            ExprKind::Literal(_) => (),
+            ExprKind::Ident(ident) => match self.variables.lookup(ident.name) {
+                Some(id) => self.record(*ident, id),
+                None => self.report(SemaError::Undefined {
+                    what: "variable",
+                    name: ident.name.to_string(),
+                    span: ident.span,
+                }),
+            },
+            ExprKind::Binary { left, right, .. } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            ExprKind::Unary { operand, .. } => self.resolve_expr(operand),
+            ExprKind::Call(func_call) => self.resolve_call(func_call),
+            // fields are resolved against the type of the object, which is the job of the type checker
+            ExprKind::Field { object, .. } => self.resolve_expr(object),
+            ExprKind::Index { object, index } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            }
+            ExprKind::Group(inner) => self.resolve_expr(inner),
+            ExprKind::Array(items) => {
+                for item in items {
+                    self.resolve_expr(item);
+                }
+            }
+            ExprKind::Conversion { value, .. } => self.resolve_expr(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, SymbolKind};
+    use crate::diagnostics::Level;
+    use crate::lexer::{Lexer, Span};
+    use crate::parsing::ast::expr::ExprKind;
+    use crate::parsing::ast::stmt::Statement;
+    use crate::parsing::ast::Type;
+    use crate::parsing::build_ast;
+
+    /// Analyses the source and returns level, message and span of every problem.
+    fn problems(source: &str) -> Vec<(Level, String, Span)> {
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (_, problems) = analyze(&ast);
+        problems
+            .iter()
+            .map(|problem| {
+                let diagnostic = problem.diagnostic();
+                (diagnostic.level(), problem.to_string(), problem.span().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolves_names_to_innermost_declarations() {
+        let source = "void main() {\n    int x = 1\n    {\n        bool x = true\n        print(x)\n    }\n    print(x)\n}\n";
+        let (ast, errors) = build_ast(Lexer::new(source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (analysis, _) = analyze(&ast);
+        let stmts = ast.functions().next().unwrap().stmts();
+        let printed_type = |stmt: &Statement| {
+            let arg = match stmt {
+                Statement::FunctionCall(call) => &call.args()[0],
+                other => panic!("{:?}", other),
+            };
+            let ident = match arg.kind() {
+                ExprKind::Ident(ident) => *ident,
+                other => panic!("{:?}", other),
+            };
+            match analysis.resolved_symbol(ident).map(|symbol| symbol.kind()) {
+                Some(SymbolKind::Local(type_)) => *type_,
+                other => panic!("{:?}", other),
+            }
+        };
+        let inner = match &stmts[1] {
+            Statement::Block(block) => &block.stmts()[1],
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(printed_type(inner), Type::Bool);
+        assert_eq!(printed_type(&stmts[2]), Type::Int);
+    }
+
+    #[test]
+    fn reports_undefined_names() {
+        let source = "void main() {\n    int x = x\n    foo(y)\n    bar()\n}\n\nvoid bar() {\n}\n";
+        assert_eq!(
+            problems(source),
+            [
+                (Level::Error, "cannot find variable `x`".to_string(), Span::new(26, 1, 2, 13)),
+                (Level::Error, "cannot find function `foo`".to_string(), Span::new(32, 3, 3, 5)),
+                (Level::Error, "cannot find variable `y`".to_string(), Span::new(36, 1, 3, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_redefinitions_and_shadowing() {
+        let source = "void main(int a) {\n    int a = 1\n    for (int i = 0; i < 1; ) {\n        int a = 2\n    }\n}\n\nvoid print() {\n}\n";
+        assert_eq!(
+            problems(source),
+            [
+                (
+                    Level::Error,
+                    "function `print` is already defined".to_string(),
+                    Span::new(96, 5, 8, 6)
+                ),
+                (
+                    Level::Error,
+                    "variable `a` is already defined".to_string(),
+                    Span::new(27, 1, 2, 9)
+                ),
+                (
+                    Level::Warning,
+                    "variable `a` shadows a variable of the enclosing scope".to_string(),
+                    Span::new(76, 1, 4, 13)
+                ),
+            ]
+        );
+
+        let (ast, _) = build_ast(Lexer::new(source));
+        let (_, problems) = analyze(&ast);
+        let helps: Vec<_> = problems
+            .iter()
+            .map(|problem| problem.diagnostic().render("main.hl", source))
+            .collect();
+        assert!(helps[0].ends_with("= help: `print` is a builtin function\n"), "{}", helps[0]);
+        assert!(helps[1].ends_with("= help: previous definition is at 1:15\n"), "{}", helps[1]);
+        assert!(helps[2].starts_with("warning: "), "{}", helps[2]);
+    }
+
+    #[test]
+    fn reports_problems_of_classes() {
+        let source = "class Empty {}\n\nclass Point { int x, int x }\n\nvalue class Line { Point from, dyn to }\n\nvalue class Node { Edge edge }\n\nvalue class Edge { Node to }\n";
+        assert_eq!(
+            problems(source),
+            [
+                (
+                    Level::Error,
+                    "class `Empty` must have at least one field".to_string(),
+                    Span::new(0, 14, 1, 1)
+                ),
+                (
+                    Level::Error,
+                    "field `x` is already defined".to_string(),
+                    Span::new(41, 1, 3, 26)
+                ),
+                (
+                    Level::Error,
+                    "value class `Line` can't have a field of type `Point`".to_string(),
+                    Span::new(65, 10, 5, 20)
+                ),
+                (
+                    Level::Error,
+                    "value class `Line` can't have a field of type `dyn`".to_string(),
+                    Span::new(77, 6, 5, 32)
+                ),
+                (
+                    Level::Error,
+                    "value class `Node` contains itself".to_string(),
+                    Span::new(99, 4, 7, 13)
+                ),
+                (
+                    Level::Error,
+                    "value class `Edge` contains itself".to_string(),
+                    Span::new(131, 4, 9, 13)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use super::SymbolId;
+use std::collections::HashMap;
+
+/// Stack of lexical scopes of variables, the innermost scope is the last one.
+pub struct Scopes<'a> {
+    scopes: Vec<HashMap<&'a str, SymbolId>>,
+}
+
+impl<'a> Scopes<'a> {
+    pub fn new() -> Scopes<'a> {
+        Scopes { scopes: Vec::new() }
+    }
+
+    pub fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Adds name to the innermost scope, returns the symbol previously declared there with the same name.
+    pub fn declare(&mut self, name: &'a str, symbol: SymbolId) -> Option<SymbolId> {
+        let scope = self.scopes.last_mut().expect("variables are declared only inside of a scope");
+        scope.insert(name, symbol)
+    }
+
+    pub fn lookup_innermost(&self, name: &str) -> Option<SymbolId> {
+        self.scopes.last().and_then(|scope| scope.get(name)).copied()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<SymbolId> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).copied()
+    }
+}
@@ -0,0 +1,862 @@
+use super::{Analysis, SemaError, SymbolKind};
+use crate::builtins::ParamType;
+use crate::error::CompileError;
+use crate::lexer::Span;
+use crate::parsing::ast::expr::{BinaryOp, Expr, ExprKind, UnaryOp};
+use crate::parsing::ast::stmt::{self, Statement};
+use crate::parsing::ast::{self, LiteralKind, Type, TypeOrVoid};
+
+/// Number of decimal digits after the point kept by `financial` values, `FINANCIAL_SCALE` of `financial.h`.
+pub const FINANCIAL_SCALE: u32 = 4;
+
+/// Checks types in the function bodies and records the type of every expression in `analysis`.
+pub fn check<'a>(tree: &ast::AST<'a>, analysis: &mut Analysis<'a>, errors: &mut Vec<CompileError>) {
+    for decl in tree.functions() {
+        let mut checker = Checker {
+            analysis,
+            errors,
+            return_type: decl.return_type(),
+            loop_depth: 0,
+        };
+        checker.check_stmts(decl.stmts());
+        if decl.return_type() != TypeOrVoid::Void && !always_returns(decl.stmts()) {
+            // a loop at the end of the function is pointed at, as it's the loop which may run zero times
+            let span = match decl.stmts().last() {
+                Some(Statement::While(while_)) => while_.span(),
+                Some(Statement::For(for_)) => for_.span(),
+                Some(Statement::ForEach(for_each)) => for_each.span(),
+                _ => decl.span(),
+            };
+            checker.report(SemaError::MissingReturn {
+                function: decl.name().name.to_string(),
+                span,
+            });
+        }
+    }
+}
+
+/// Value of a `financial` constant in units of [`FINANCIAL_SCALE`], `None` if it doesn't fit.
+pub fn financial_units(literal: &LiteralKind, negative: bool) -> Option<i64> {
+    let units = match literal {
+        LiteralKind::Decimal { digits, scale } if *scale <= FINANCIAL_SCALE => i128::try_from(*digits)
+            .ok()?
+            .checked_mul(10i128.pow(FINANCIAL_SCALE - scale))?,
+        LiteralKind::Integer { value, suffix: None } => i128::try_from(*value).ok()?.checked_mul(10i128.pow(FINANCIAL_SCALE))?,
+        LiteralKind::Double(value) => {
+            // `0.1` is not exact in binary, so the scaled value is only close to an integer
+            let units = value * 10f64.powi(FINANCIAL_SCALE as i32);
+            let rounded = units.round();
+            if (units - rounded).abs() > rounded.abs().max(1.0) * f64::EPSILON * 4.0 || rounded.abs() >= i64::MAX as f64 {
+                return None;
+            }
+            rounded as i128
+        }
+        _ => return None,
+    };
+    i64::try_from(if negative { -units } else { units }).ok()
+}
+
+/// Literal, possibly negated or parenthesised, which takes its type from the context it is used in.
+pub fn as_constant<'e>(expr: &'e Expr) -> Option<(&'e LiteralKind, bool)> {
+    match expr.kind() {
+        ExprKind::Literal(literal) => Some((literal.kind(), false)),
+        ExprKind::Group(inner) => as_constant(inner),
+        ExprKind::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => match as_constant(operand)? {
+            (literal @ (LiteralKind::Integer { .. } | LiteralKind::Double(_) | LiteralKind::Decimal { .. }), false) => {
+                Some((literal, true))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn integer_fits(type_: Type, value: u128, negative: bool) -> bool {
+    let (min, max) = type_.integer_range().expect("integer type");
+    if negative {
+        value <= min.unsigned_abs()
+    } else {
+        value <= max as u128
+    }
+}
+
+/// Type of a constant used where any type is accepted.
+fn default_type(literal: &LiteralKind, negative: bool) -> Type<'static> {
+    match literal {
+        LiteralKind::Integer {
+            suffix: Some(suffix), ..
+        } => *suffix,
+        LiteralKind::Integer { value, suffix: None } => [Type::Int, Type::Long, Type::ULong]
+            .into_iter()
+            .find(|type_| integer_fits(*type_, *value, negative))
+            .unwrap_or(Type::Long),
+        LiteralKind::Double(_) => Type::Double,
+        LiteralKind::Decimal { .. } => Type::Financial,
+        LiteralKind::String(_) => Type::String,
+        LiteralKind::Rune(_) => Type::Rune,
+        LiteralKind::Bool(_) => Type::Bool,
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+enum Coercion {
+    Fits,
+    /// Constant has a suitable kind, but its value can't be represented by the type.
+    OutOfRange,
+    Mismatch,
+}
+
+/// Checks the implicit cast of a constant: unsuffixed integers become any number type and
+/// floating point literals become `double` or `financial`, as long as the value fits.
+fn coerce_constant(literal: &LiteralKind, negative: bool, expected: Type) -> Coercion {
+    let fits = |fits: bool| if fits { Coercion::Fits } else { Coercion::OutOfRange };
+    match (literal, expected) {
+        (_, Type::Dyn) => coerce_constant(literal, negative, default_type(literal, negative)),
+        (LiteralKind::Integer { value, suffix }, _) if expected.is_integer() && suffix.is_none_or(|s| s == expected) => {
+            fits(integer_fits(expected, *value, negative))
+        }
+        (LiteralKind::Integer { suffix: None, .. } | LiteralKind::Double(_), Type::Double) => Coercion::Fits,
+        (LiteralKind::Integer { suffix: None, .. } | LiteralKind::Double(_) | LiteralKind::Decimal { .. }, Type::Financial) => {
+            fits(financial_units(literal, negative).is_some())
+        }
+        (LiteralKind::String(_), Type::String) | (LiteralKind::Rune(_), Type::Rune) | (LiteralKind::Bool(_), Type::Bool) => {
+            Coercion::Fits
+        }
+        _ => Coercion::Mismatch,
+    }
+}
+
+fn operator_applies(op: BinaryOp, type_: Type) -> bool {
+    match op {
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+            type_.is_integer() || matches!(type_, Type::Double | Type::Financial)
+        }
+        // C has no `%` for floating point numbers
+        BinaryOp::Rem => type_.is_integer(),
+        BinaryOp::Less | BinaryOp::LessOrEqual | BinaryOp::Greater | BinaryOp::GreaterOrEqual => {
+            type_.is_integer() || matches!(type_, Type::Double | Type::Rune | Type::Financial | Type::String)
+        }
+        BinaryOp::Equal | BinaryOp::NotEqual => {
+            type_.is_integer() || matches!(type_, Type::Double | Type::Rune | Type::Bool | Type::Financial | Type::String)
+        }
+        BinaryOp::And | BinaryOp::Or => type_ == Type::Bool,
+    }
+}
+
+/// Types between which explicit conversions are allowed.
+fn is_convertible(type_: Type) -> bool {
+    type_.is_integer() || matches!(type_, Type::Double | Type::Rune)
+}
+
+/// Checks whether execution of `stmts` can't reach their end without returning.
+fn always_returns(stmts: &[Statement]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Statement::Return(_) => true,
+        Statement::Block(block) => always_returns(block.stmts()),
+        Statement::If(if_) => if_returns(if_),
+        // loops may run zero times
+        _ => false,
+    })
+}
+
+/// Checks whether every branch of `if` returns, which requires it to have `else`.
+fn if_returns(if_: &stmt::If) -> bool {
+    let else_returns = match if_.else_branch() {
+        Some(stmt::Else::If(else_if)) => if_returns(else_if),
+        Some(stmt::Else::Block(block)) => always_returns(block.stmts()),
+        None => false,
+    };
+    else_returns && always_returns(if_.then_block().stmts())
+}
+
+struct Checker<'t, 'a> {
+    analysis: &'t mut Analysis<'a>,
+    errors: &'t mut Vec<CompileError>,
+    return_type: TypeOrVoid<'a>,
+    /// Number of loops around the current statement.
+    loop_depth: usize,
+}
+
+impl<'t, 'a> Checker<'t, 'a> {
+    fn report(&mut self, err: SemaError) {
+        self.errors.push(err.into());
+    }
+
+    fn mismatched_types(&mut self, expected: Type, found: Type, span: Span) {
+        self.report(SemaError::MismatchedTypes {
+            expected: self.analysis.type_name(expected),
+            found: self.analysis.type_name(found),
+            span,
+        });
+    }
+
+    fn record(&mut self, expr: &Expr, type_: Type<'a>) {
+        self.analysis.expr_types.insert(expr.id(), type_);
+    }
+
+    /// Records the type of a constant and of every node it consists of.
+    fn record_constant(&mut self, expr: &Expr, type_: Type<'a>) {
+        self.record(expr, type_);
+        match expr.kind() {
+            ExprKind::Group(inner) | ExprKind::Unary { operand: inner, .. } => self.record_constant(inner, type_),
+            _ => (),
+        }
+    }
+
+    fn check_stmts(&mut self, stmts: &[Statement<'a>]) {
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Statement<'a>) {
+        match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                self.expect(var_decl.value(), var_decl.var_type());
+            }
+            Statement::FunctionCall(func_call) => {
+                self.check_call(func_call);
+                if let Some(SymbolKind::Class { .. }) =
+                    self.analysis.resolved_symbol(func_call.name()).map(|symbol| symbol.kind())
+                {
+                    // constructors have no effects, so an object which isn't used is a mistake
+                    self.report(SemaError::UnusedObject {
+                        class: func_call.name().name.to_string(),
+                        span: func_call.span(),
+                    });
+                }
+            }
+            Statement::Assignment(assignment) => self.check_assignment(assignment),
+            Statement::Return(return_) => self.check_return(return_),
+            Statement::If(if_) => self.check_if(if_),
+            Statement::While(while_) => {
+                self.expect(while_.condition(), Type::Bool);
+                self.check_loop_body(while_.body());
+            }
+            Statement::For(for_) => {
+                if let Some(init) = for_.init() {
+                    self.check_stmt(init);
+                }
+                if let Some(condition) = for_.condition() {
+                    self.expect(condition, Type::Bool);
+                }
+                if let Some(step) = for_.step() {
+                    self.check_stmt(step);
+                }
+                self.check_loop_body(for_.body());
+            }
+            Statement::ForEach(for_each) => {
+                if let Some(type_) = self.infer(for_each.iterable()) {
+                    match self.analysis.element(type_) {
+                        Some(element) if element != for_each.item_type() => {
+                            self.mismatched_types(for_each.item_type(), element, for_each.item().span)
+                        }
+                        Some(_) => (),
+                        None => self.report(SemaError::NotIterable {
+                            type_name: self.analysis.type_name(type_),
+                            span: for_each.iterable().span(),
+                        }),
+                    }
+                }
+                self.check_loop_body(for_each.body());
+            }
+            Statement::Break(span) | Statement::Continue(span) => {
+                if self.loop_depth == 0 {
+                    let what = if matches!(stmt, Statement::Break(_)) {
+                        "break"
+                    } else {
+                        "continue"
+                    };
+                    self.report(SemaError::OutsideOfLoop { what, span: *span });
+                }
+            }
+            Statement::Block(block) => self.check_stmts(block.stmts()),
+        }
+    }
+
+    /// Checks loop body, in which `break` and `continue` are allowed.
+    fn check_loop_body(&mut self, body: &stmt::Block<'a>) {
+        self.loop_depth += 1;
+        self.check_stmts(body.stmts());
+        self.loop_depth -= 1;
+    }
+
+    fn check_if(&mut self, if_: &stmt::If<'a>) {
+        self.expect(if_.condition(), Type::Bool);
+        self.check_stmts(if_.then_block().stmts());
+        match if_.else_branch() {
+            Some(stmt::Else::If(else_if)) => self.check_if(else_if),
+            Some(stmt::Else::Block(block)) => self.check_stmts(block.stmts()),
+            None => (),
+        }
+    }
+
+    fn check_assignment(&mut self, assignment: &stmt::Assignment<'a>) {
+        let target_type = match self.infer(assignment.target()) {
+            Some(type_) => type_,
+            None => {
+                self.infer(assignment.value());
+                return;
+            }
+        };
+        if let ExprKind::Field { object, field } = assignment.target().kind() {
+            if let Some(Type::Custom { name }) = self.analysis.expr_type(object) {
+                if self.analysis.is_value_class(name) {
+                    self.report(SemaError::ImmutableField {
+                        class: name.to_string(),
+                        field: field.name.to_string(),
+                        span: assignment.target().span(),
+                    });
+                    self.infer(assignment.value());
+                    return;
+                }
+            }
+        }
+        if let Some(op) = assignment.op().binary_op() {
+            if !operator_applies(op, target_type) {
+                self.report(SemaError::InvalidOperator {
+                    operator: assignment.op().symbol(),
+                    type_name: self.analysis.type_name(target_type),
+                    span: assignment.span(),
+                });
+                return;
+            }
+        }
+        self.expect(assignment.value(), target_type);
+    }
+
+    fn check_return(&mut self, return_: &stmt::Return<'a>) {
+        match (self.return_type, return_.value()) {
+            (TypeOrVoid::Void, None) => (),
+            (TypeOrVoid::Type(return_type), Some(value)) => {
+                self.expect(value, return_type);
+            }
+            (TypeOrVoid::Void, Some(value)) => {
+                self.report(SemaError::ReturnInVoidFunction { span: value.span() });
+                self.infer(value);
+            }
+            (TypeOrVoid::Type(return_type), None) => self.report(SemaError::MissingReturnValue {
+                expected: self.analysis.type_name(return_type),
+                span: return_.span(),
+            }),
+        }
+    }
+
+    /// Checks the arguments of the call and returns the result type, `None` if the function is unknown.
+    fn check_call(&mut self, func_call: &stmt::FunctionCall<'a>) -> Option<TypeOrVoid<'a>> {
+        let kind = self
+            .analysis
+            .resolved_symbol(func_call.name())
+            .map(|symbol| symbol.kind().clone());
+        let mut what = "function";
+        let (return_type, params): (_, Vec<ParamType>) = match kind {
+            Some(SymbolKind::Function { return_type, params }) => {
+                (return_type, params.into_iter().map(ParamType::Exact).collect())
+            }
+            Some(SymbolKind::Builtin(builtin)) => (builtin.returns, builtin.param_types(func_call.args().len())),
+            // constructor takes the values of all fields
+            Some(SymbolKind::Class { fields, .. }) => {
+                what = "constructor of class";
+                let class_type = Type::Custom {
+                    name: func_call.name().name,
+                };
+                (
+                    TypeOrVoid::Type(class_type),
+                    fields.iter().map(|(_, type_)| ParamType::Exact(*type_)).collect(),
+                )
+            }
+            _ => {
+                for arg in func_call.args() {
+                    self.infer(arg);
+                }
+                return None;
+            }
+        };
+        if params.len() != func_call.args().len() {
+            self.report(SemaError::WrongArgumentCount {
+                what,
+                function: func_call.name().name.to_string(),
+                expected: params.len(),
+                found: func_call.args().len(),
+                span: func_call.span(),
+            });
+            for arg in func_call.args() {
+                self.infer(arg);
+            }
+        } else {
+            let mut element = None;
+            for (arg, param) in func_call.args().iter().zip(params) {
+                match (param, element) {
+                    (ParamType::Exact(param), _) | (ParamType::Element, Some(param)) => {
+                        self.expect(arg, param);
+                    }
+                    (ParamType::Array, _) => element = self.infer_array(arg),
+                    (ParamType::Any, _) => self.infer_printable(arg),
+                    (ParamType::Element, None) => {
+                        self.infer(arg);
+                    }
+                }
+            }
+        }
+        Some(return_type)
+    }
+
+    /// Infers the type of the array, returns the type of its elements.
+    fn infer_array(&mut self, expr: &Expr<'a>) -> Option<Type<'a>> {
+        let type_ = self.infer(expr)?;
+        if self.analysis.element(type_).is_none() {
+            self.report(SemaError::NotArray {
+                type_name: self.analysis.type_name(type_),
+                span: expr.span(),
+            });
+        }
+        self.analysis.element(type_)
+    }
+
+    /// Infers the type of the printed value, objects of classes and arrays have no textual form.
+    fn infer_printable(&mut self, expr: &Expr<'a>) {
+        let Some(type_) = self.infer(expr) else {
+            return;
+        };
+        let printable = match type_ {
+            Type::Custom { name } => self.analysis.is_value_class(name),
+            Type::Array(_) => false,
+            _ => true,
+        };
+        if !printable {
+            self.report(SemaError::NotPrintable {
+                type_name: self.analysis.type_name(type_),
+                span: expr.span(),
+            });
+        }
+    }
+
+    /// Checks that `expr` can be used where a value of `expected` type is required,
+    /// returns `false` if it can't.
+    fn expect(&mut self, expr: &Expr<'a>, expected: Type<'a>) -> bool {
+        if let Some((literal, negative)) = as_constant(expr) {
+            let type_ = match expected {
+                // constants are wrapped into `dyn` with the type they would have on their own
+                Type::Dyn => default_type(literal, negative),
+                _ => expected,
+            };
+            let coercion = coerce_constant(literal, negative, type_);
+            match coercion {
+                Coercion::Fits => self.record_constant(expr, type_),
+                Coercion::OutOfRange => self.report(SemaError::LiteralOutOfRange {
+                    type_name: self.analysis.type_name(type_),
+                    span: expr.span(),
+                }),
+                Coercion::Mismatch => self.mismatched_types(expected, default_type(literal, negative), expr.span()),
+            }
+            return coercion == Coercion::Fits;
+        }
+        // array literals take the type they are expected to have
+        if let (ExprKind::Array(items), Some(element)) = (expr.kind(), self.analysis.element(expected)) {
+            let mut fits = true;
+            for item in items {
+                fits &= self.expect(item, element);
+            }
+            self.record(expr, expected);
+            return fits;
+        }
+        match self.infer(expr) {
+            // every value except objects and arrays can be wrapped into `dyn`
+            Some(found)
+                if found != expected && (expected != Type::Dyn || matches!(found, Type::Custom { .. } | Type::Array(_))) =>
+            {
+                self.mismatched_types(expected, found, expr.span());
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Infers the type of `expr`, `None` if it is erroneous, in which case the error is already reported.
+    fn infer(&mut self, expr: &Expr<'a>) -> Option<Type<'a>> {
+        if let Some((literal, negative)) = as_constant(expr) {
+            let type_ = default_type(literal, negative);
+            return self.expect(expr, type_).then_some(type_);
+        }
+        let type_ = match expr.kind() {
+            ExprKind::Ident(ident) => match self.analysis.resolved_symbol(*ident).map(|symbol| symbol.kind()) {
+                Some(SymbolKind::Local(type_) | SymbolKind::Param(type_)) => *type_,
+                _ => return None,
+            },
+            ExprKind::Binary { op, left, right } => self.infer_binary(*op, left, right, expr.span())?,
+            ExprKind::Unary { op, operand } => {
+                let operand_type = self.infer(operand)?;
+                let applies = match op {
+                    UnaryOp::Neg => matches!(
+                        operand_type,
+                        Type::Short | Type::Int | Type::Long | Type::Double | Type::Financial
+                    ),
+                    UnaryOp::Not => operand_type == Type::Bool,
+                };
+                if !applies {
+                    self.report(SemaError::InvalidOperator {
+                        operator: op.symbol(),
+                        type_name: self.analysis.type_name(operand_type),
+                        span: expr.span(),
+                    });
+                    return None;
+                }
+                operand_type
+            }
+            ExprKind::Call(func_call) => match self.check_call(func_call)? {
+                TypeOrVoid::Type(type_) => type_,
+                TypeOrVoid::Void => {
+                    self.report(SemaError::NoValue {
+                        function: func_call.name().name.to_string(),
+                        span: func_call.span(),
+                    });
+                    return None;
+                }
+            },
+            ExprKind::Field { object, field } => {
+                let object_type = self.infer(object)?;
+                let field_type = match object_type {
+                    Type::Array(_) if *field == "len" => Some(Type::Int),
+                    Type::Custom { name } => self
+                        .analysis
+                        .class_fields(name)
+                        .and_then(|fields| fields.iter().find(|(name, _)| *field == *name))
+                        .map(|(_, type_)| *type_),
+                    _ => None,
+                };
+                if let Some(field_type) = field_type {
+                    self.record(expr, field_type);
+                    return Some(field_type);
+                }
+                self.report(SemaError::NoField {
+                    type_name: self.analysis.type_name(object_type),
+                    field: field.name.to_string(),
+                    span: field.span,
+                });
+                return None;
+            }
+            ExprKind::Index { object, index } => {
+                let object_type = self.infer(object);
+                let index_type = match as_constant(index) {
+                    Some(_) => self.expect(index, Type::Int).then_some(Type::Int),
+                    None => self.infer(index),
+                };
+                let element = self.analysis.element(object_type?);
+                if element.is_none() {
+                    self.report(SemaError::NotIndexable {
+                        type_name: self.analysis.type_name(object_type?),
+                        span: object.span(),
+                    });
+                }
+                let index_type = index_type?;
+                if !index_type.is_integer() {
+                    self.report(SemaError::InvalidIndex {
+                        type_name: self.analysis.type_name(index_type),
+                        span: index.span(),
+                    });
+                    return None;
+                }
+                element?
+            }
+            ExprKind::Array(_) => {
+                self.report(SemaError::UntypedArrayLiteral { span: expr.span() });
+                return None;
+            }
+            ExprKind::Group(inner) => self.infer(inner)?,
+            ExprKind::Conversion { target, value } => self.check_conversion(*target, value, expr.span())?,
+            ExprKind::Literal(_) => unreachable!("literals are constants"),
+        };
+        self.record(expr, type_);
+        Some(type_)
+    }
+
+    fn infer_binary(&mut self, op: BinaryOp, left: &Expr<'a>, right: &Expr<'a>, span: Span) -> Option<Type<'a>> {
+        // constants take the type of the other operand, so `x + 1` works for `x` of any number type
+        let operand_type = match (as_constant(left), as_constant(right)) {
+            (None, Some(_)) => {
+                let type_ = self.infer(left)?;
+                if !self.expect(right, type_) {
+                    return None;
+                }
+                type_
+            }
+            (Some(_), None) => {
+                let type_ = self.infer(right)?;
+                if !self.expect(left, type_) {
+                    return None;
+                }
+                type_
+            }
+            (Some((left_literal, left_negative)), Some((right_literal, right_negative))) => {
+                // `1 + 0.5` is a `double`
+                let right_type = default_type(right_literal, right_negative);
+                let type_ = match coerce_constant(left_literal, left_negative, right_type) {
+                    Coercion::Fits => right_type,
+                    _ => default_type(left_literal, left_negative),
+                };
+                let left_fits = self.expect(left, type_);
+                let right_fits = self.expect(right, type_);
+                if !(left_fits && right_fits) {
+                    return None;
+                }
+                type_
+            }
+            (None, None) => {
+                let left_type = self.infer(left);
+                let right_type = self.infer(right);
+                let (left_type, right_type) = (left_type?, right_type?);
+                if left_type != right_type {
+                    self.mismatched_types(left_type, right_type, right.span());
+                    return None;
+                }
+                left_type
+            }
+        };
+        if !operator_applies(op, operand_type) && !self.compares_values(op, operand_type) {
+            self.report(SemaError::InvalidOperator {
+                operator: op.symbol(),
+                type_name: self.analysis.type_name(operand_type),
+                span,
+            });
+            return None;
+        }
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => Some(operand_type),
+            _ => Some(Type::Bool),
+        }
+    }
+
+    /// Values of value classes are compared field by field, in the order of declaration.
+    fn compares_values(&self, op: BinaryOp, type_: Type) -> bool {
+        let comparison = matches!(
+            op,
+            BinaryOp::Equal
+                | BinaryOp::NotEqual
+                | BinaryOp::Less
+                | BinaryOp::LessOrEqual
+                | BinaryOp::Greater
+                | BinaryOp::GreaterOrEqual
+        );
+        comparison && matches!(type_, Type::Custom { name } if self.analysis.is_value_class(name))
+    }
+
+    fn check_conversion(&mut self, target: Type<'a>, value: &Expr<'a>, span: Span) -> Option<Type<'a>> {
+        // `financial(1.5)` is just a typed constant
+        if let Some((literal, negative)) = as_constant(value).filter(|_| target != Type::Dyn) {
+            if coerce_constant(literal, negative, target) == Coercion::Fits {
+                self.record_constant(value, target);
+                return Some(target);
+            }
+        }
+        let source = self.infer(value)?;
+        let wraps_into_dyn = target == Type::Dyn && !matches!(source, Type::Custom { .. } | Type::Array(_));
+        // `string(value)` formats values of value classes
+        let formats_value =
+            target == Type::String && matches!(source, Type::Custom { name } if self.analysis.is_value_class(name));
+        let allowed = source == target || wraps_into_dyn || formats_value || (is_convertible(source) && is_convertible(target));
+        if !allowed {
+            self.report(SemaError::InvalidConversion {
+                from: self.analysis.type_name(source),
+                to: self.analysis.type_name(target),
+                span,
+            });
+            return None;
+        }
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coerce_constant, Coercion};
+    use crate::lexer::Lexer;
+    use crate::parsing::ast::{LiteralKind, Type};
+    use crate::parsing::build_ast;
+    use crate::sema::analyze;
+
+    /// Checks the body of `main` and returns the message of every problem.
+    fn problems(body: &str) -> Vec<String> {
+        let source = format!(
+            "void main() {{\n{}\n}}\n\nclass Point {{ int x, int y }}\n\nvalue class Money {{ int units }}\n",
+            body
+        );
+        let (ast, errors) = build_ast(Lexer::new(&source));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (_, problems) = analyze(&ast);
+        problems.iter().map(|problem| problem.to_string()).collect()
+    }
+
+    fn integer(value: u128) -> LiteralKind {
+        LiteralKind::Integer { value, suffix: None }
+    }
+
+    fn decimal(digits: u128, scale: u32) -> LiteralKind {
+        LiteralKind::Decimal { digits, scale }
+    }
+
+    #[test]
+    fn coerces_constants_up_to_the_bounds_of_their_types() {
+        let byte = LiteralKind::Integer {
+            value: 1,
+            suffix: Some(Type::Byte),
+        };
+        // 4 decimal places are kept, so whole numbers are scaled by 10^4
+        let max_financial = i64::MAX as u128 / 10_000;
+        let cases = [
+            (integer(255), false, Type::Byte, Coercion::Fits),
+            (integer(256), false, Type::Byte, Coercion::OutOfRange),
+            (integer(0), true, Type::Byte, Coercion::Fits),
+            (integer(1), true, Type::Byte, Coercion::OutOfRange),
+            (integer(32768), true, Type::Short, Coercion::Fits),
+            (integer(32768), false, Type::Short, Coercion::OutOfRange),
+            (integer(65535), false, Type::UShort, Coercion::Fits),
+            (integer(1 << 31), true, Type::Int, Coercion::Fits),
+            (integer((1 << 31) + 1), true, Type::Int, Coercion::OutOfRange),
+            (integer(u32::MAX as u128), false, Type::UInt, Coercion::Fits),
+            (integer(1 << 63), true, Type::Long, Coercion::Fits),
+            (integer(1 << 63), false, Type::Long, Coercion::OutOfRange),
+            (integer(u64::MAX as u128), false, Type::ULong, Coercion::Fits),
+            (integer(1), true, Type::ULong, Coercion::OutOfRange),
+            (integer(u64::MAX as u128), false, Type::Double, Coercion::Fits),
+            (integer(1), false, Type::String, Coercion::Mismatch),
+            (byte.clone(), false, Type::Byte, Coercion::Fits),
+            (byte.clone(), false, Type::Int, Coercion::Mismatch),
+            (byte.clone(), false, Type::Double, Coercion::Mismatch),
+            (byte, false, Type::Dyn, Coercion::Fits),
+            (integer(max_financial), false, Type::Financial, Coercion::Fits),
+            (integer(max_financial + 1), true, Type::Financial, Coercion::OutOfRange),
+            (decimal(12345, 4), false, Type::Financial, Coercion::Fits),
+            (decimal(123456, 5), false, Type::Financial, Coercion::OutOfRange),
+            (decimal(i64::MAX as u128, 4), true, Type::Financial, Coercion::Fits),
+            (decimal(i64::MAX as u128 + 1, 4), false, Type::Financial, Coercion::OutOfRange),
+            (decimal(1, 1), false, Type::Double, Coercion::Mismatch),
+            (LiteralKind::Double(0.1), false, Type::Financial, Coercion::Fits),
+            (LiteralKind::Double(0.00001), false, Type::Financial, Coercion::OutOfRange),
+            (LiteralKind::Double(1e300), false, Type::Financial, Coercion::OutOfRange),
+            (LiteralKind::Double(0.5), false, Type::Int, Coercion::Mismatch),
+            (integer(u64::MAX as u128), false, Type::Dyn, Coercion::Fits),
+            (integer(u64::MAX as u128), true, Type::Dyn, Coercion::OutOfRange),
+            (LiteralKind::Bool(true), false, Type::Dyn, Coercion::Fits),
+            (LiteralKind::Bool(true), false, Type::Int, Coercion::Mismatch),
+        ];
+        for (literal, negative, type_, expected) in cases {
+            assert_eq!(
+                coerce_constant(&literal, negative, type_),
+                expected,
+                "{:?} (negative: {}) as {:?}",
+                literal,
+                negative,
+                type_
+            );
+        }
+    }
+
+    #[test]
+    fn checks_types_of_statements() {
+        let cases: [(&str, &[&str]); 39] = [
+            ("    byte b = 255", &[]),
+            ("    byte b = 256", &["literal doesn't fit into type `byte`"]),
+            ("    byte b = -1", &["literal doesn't fit into type `byte`"]),
+            ("    int i = -2147483648", &[]),
+            ("    int i = 2147483648", &["literal doesn't fit into type `int`"]),
+            ("    long l = -(9223372036854775808)", &[]),
+            ("    financial f = 0.0001", &[]),
+            ("    financial f = 0.00001", &["literal doesn't fit into type `financial`"]),
+            (
+                "    financial f = 1.5\n    f = -(f * 2 + f / 3 - 0.25)\n    bool b = f < 1 && f != 0",
+                &[],
+            ),
+            (
+                "    financial f = 1.5\n    f %= 2",
+                &["operator `%=` can't be applied to type `financial`"],
+            ),
+            ("    string s = \"a\"\n    bool b = s == \"b\" || s < s", &[]),
+            (
+                "    string s = \"a\" + \"b\"",
+                &["operator `+` can't be applied to type `string`"],
+            ),
+            (
+                "    string s = 1",
+                &["mismatched types: expected value of type `string`, but found `int`"],
+            ),
+            (
+                "    int i = 1\n    long l = i",
+                &["mismatched types: expected value of type `long`, but found `int`"],
+            ),
+            ("    int i = 1\n    long l = long(i)", &[]),
+            (
+                "    bool b = true\n    int i = int(b)",
+                &["can't convert value of type `bool` to `int`"],
+            ),
+            ("    int i = 1 + 2 * 3", &[]),
+            (
+                "    double d = 1.5 % 2.0",
+                &["operator `%` can't be applied to type `double`"],
+            ),
+            ("    bool b = 1 < 2 && true", &[]),
+            (
+                "    while 1 {\n    }",
+                &["mismatched types: expected value of type `bool`, but found `int`"],
+            ),
+            ("    print(1)", &[]),
+            ("    println(1, Money(2))", &[]),
+            ("    println(1, Point(1, 2))", &["values of type `Point` can't be printed"]),
+            (
+                "    []int xs = [1]\n    print(xs)",
+                &["values of type `[]int` can't be printed"],
+            ),
+            ("    Point p = Point(1, 2)\n    p.x = p.y + 1", &[]),
+            (
+                "    Point p = Point(1)",
+                &["constructor of class `Point` takes 2 arguments, but 1 was given"],
+            ),
+            (
+                "    Point p = Point(1, 2)\n    int z = p.z",
+                &["type `Point` has no field `z`"],
+            ),
+            ("    Point(1, 2)", &["object of class `Point` is constructed but never used"]),
+            (
+                "    Money m = Money(1)\n    bool b = m < m && m != m\n    string s = string(m)",
+                &[],
+            ),
+            (
+                "    Money m = Money(1)\n    m.units = 2",
+                &["field `units` of value class `Money` can't be assigned"],
+            ),
+            (
+                "    Point p = Point(1, 2)\n    bool b = p < p",
+                &["operator `<` can't be applied to type `Point`"],
+            ),
+            (
+                "    Point p = Point(1, 2)\n    string s = string(p)",
+                &["can't convert value of type `Point` to `string`"],
+            ),
+            (
+                "    Money m = Money(1)\n    Money n = m + m",
+                &["operator `+` can't be applied to type `Money`"],
+            ),
+            ("    []int xs = [1, 2]\n    xs[0] = xs[1] + xs.len\n    append(xs, 3)", &[]),
+            (
+                "    []int xs = [1, true]",
+                &["mismatched types: expected value of type `int`, but found `bool`"],
+            ),
+            (
+                "    []int xs = [1]\n    int x = xs[true]",
+                &["mismatched types: expected value of type `int`, but found `bool`"],
+            ),
+            ("    int i = 1\n    int x = i[0]", &["type `int` can't be indexed"]),
+            ("    for (int x : 1) {\n    }", &["type `int` can't be iterated over"]),
+            (
+                "    []int xs = [1]\n    append(xs, \"a\")",
+                &["mismatched types: expected value of type `int`, but found `string`"],
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(problems(body), expected, "{}", body);
+        }
+    }
+}